@@ -49,6 +49,45 @@ pub struct Args {
     #[clap(short, long)]
     pub function: Option<String>,
 
+    /// Run the function as the handler of the exception with the given exception number.
+    #[clap(long)]
+    pub exception: Option<u32>,
+
+    /// Interrupt that may preempt the function, given as `handler:exception number:priority`.
+    #[clap(long)]
+    pub interrupt: Vec<String>,
+
+    /// Leave the statics in `.data` and `.bss` unconstrained instead of initialising them.
+    #[clap(long)]
+    pub symbolic_statics: bool,
+
+    /// Memory map of the target, the only supported memory map is `rp2040`.
+    #[clap(long)]
+    pub memory_map: Option<String>,
+
+    /// CMSIS-SVD file describing the peripherals of the target, reads from peripheral registers
+    /// are symbolic and writes to them are listed in the path results.
+    #[clap(long)]
+    pub svd: Option<String>,
+
+    /// Maximum number of times a instruction may be executed on a path, unbounded by default.
+    /// Paths exceeding it end early and their cycle count is a lower bound.
+    #[clap(long)]
+    pub max_iter_count: Option<usize>,
+
+    /// Maximum call depth of a path, defaults to 1000.
+    #[clap(long)]
+    pub max_call_depth: Option<usize>,
+
+    /// Maximum number of possible values of a symbolic memory address, defaults to 100.
+    #[clap(long)]
+    pub max_memory_access_resolutions: Option<usize>,
+
+    /// Write the timing of all paths to this file as Chrome Trace Event JSON, which can be opened
+    /// in Perfetto.
+    #[clap(long)]
+    pub chrome_trace: Option<String>,
+
     #[clap(subcommand)]
     pub subcommand: Option<Subcommands>,
 
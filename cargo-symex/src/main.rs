@@ -75,7 +75,9 @@ fn run() -> Result<()> {
 
 #[cfg(not(feature = "llvm"))]
 fn run_elf(args: Args) -> Result<()> {
-    use symex::general_assembly::RunConfig;
+    use symex::general_assembly::{
+        memory_map::MemoryMap, svd::Device, Config, Interrupt, RunConfig, Statics,
+    };
 
     use crate::build::generate_binary_build_command;
 
@@ -111,16 +113,69 @@ fn run_elf(args: Args) -> Result<()> {
     };
     debug!("Starting analasys on target: {path}, function: {function_name}");
 
-    let cfg = RunConfig {
+    let mut interrupts = vec![];
+    for interrupt in args.interrupt.iter() {
+        let parts: Vec<&str> = interrupt.split(':').collect();
+        let [handler, exception_number, priority] = parts[..] else {
+            return Err(anyhow!(
+                "Invalid interrupt {interrupt}, expected handler:exception number:priority"
+            ));
+        };
+        interrupts.push(Interrupt {
+            handler: handler.to_owned(),
+            exception_number: exception_number.parse()?,
+            priority: priority.parse()?,
+        });
+    }
+
+    let memory_map = match args.memory_map.as_deref() {
+        Some("rp2040") => MemoryMap::rp2040(),
+        Some(name) => return Err(anyhow!("Unknown memory map {name}, expected rp2040")),
+        None => MemoryMap::new(),
+    };
+
+    let default_config = Config::default();
+    let config = Config {
+        max_iter_count: args.max_iter_count.unwrap_or(default_config.max_iter_count),
+        max_call_depth: args.max_call_depth.unwrap_or(default_config.max_call_depth),
+        max_memory_access_resolutions: args
+            .max_memory_access_resolutions
+            .unwrap_or(default_config.max_memory_access_resolutions),
+        ..default_config
+    };
+
+    let mut cfg = RunConfig {
         pc_hooks: vec![],
         register_read_hooks: vec![],
         register_write_hooks: vec![],
         memory_read_hooks: vec![],
         memory_write_hooks: vec![],
         show_path_results: true,
+        exception_handler: args.exception,
+        interrupts,
+        special_instructions: vec![],
+        clock_frequency: None,
+        config,
+        statics: match args.symbolic_statics {
+            true => Statics::Symbolic,
+            false => Statics::Initialised,
+        },
+        memory_map,
+        peripherals: vec![],
+        observers: vec![],
     };
 
-    symex::run_elf::run_elf(&path, &function_name, cfg)?;
+    if let Some(svd) = args.svd {
+        let device = Device::from_path(&svd)?;
+        device.add_hooks(&mut cfg);
+    }
+
+    let results = symex::run_elf::run_elf(&path, &function_name, cfg)?;
+
+    if let Some(trace_path) = args.chrome_trace {
+        symex::chrome_trace::write_chrome_trace(&trace_path, &function_name, &results)?;
+        println!("Wrote the Chrome trace to {trace_path}");
+    }
     Ok(())
 }
 
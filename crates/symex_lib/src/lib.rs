@@ -77,6 +77,85 @@ pub fn end_cyclecount() {
     black_box(&mut s);
 }
 
+/// Marks a instant named `name` in the timing events of the path.
+///
+/// Does nothing when not running in symex. The label is read from memory by the executor and
+/// the marker is recorded with the cycle count when [`cycle_mark_raw`] is called. The call
+/// instruction is not counted but passing the label is, each marker adds the two instructions
+/// that load the address and the length of `name` into the first two argument registers. On
+/// Cortex-M that is a literal load and a move, 3 cycles.
+///
+/// # Example
+///
+/// ```rust
+/// # use symex_lib::cycle_mark;
+/// fn foo() {
+///     cycle_mark("sensor read");
+/// }
+/// ```
+#[inline(always)]
+pub fn cycle_mark(name: &str) {
+    cycle_mark_raw(name.as_ptr(), name.len());
+}
+
+#[doc(hidden)]
+#[inline(never)]
+pub extern "C" fn cycle_mark_raw(mut ptr: *const u8, mut len: usize) {
+    let mut s: u8 = 0;
+    black_box(&mut s);
+    black_box(&mut ptr);
+    black_box(&mut len);
+}
+
+/// Begins a span named `name` in the timing events of the path, ended by [`cycle_span_end`]
+/// with the same name.
+///
+/// Does nothing when not running in symex. Spans can be nested and are reconstructed into a
+/// tree for each path. As for [`cycle_mark`] the call instruction is not counted and passing
+/// the label adds 3 cycles on Cortex-M.
+///
+/// # Example
+///
+/// ```rust
+/// # use symex_lib::{cycle_span_begin, cycle_span_end};
+/// fn foo() {
+///     cycle_span_begin("filter");
+///     // the cycles spent here are in the span "filter"
+///     cycle_span_end("filter");
+/// }
+/// ```
+#[inline(always)]
+pub fn cycle_span_begin(name: &str) {
+    cycle_span_begin_raw(name.as_ptr(), name.len());
+}
+
+#[doc(hidden)]
+#[inline(never)]
+pub extern "C" fn cycle_span_begin_raw(mut ptr: *const u8, mut len: usize) {
+    let mut s: u16 = 0;
+    black_box(&mut s);
+    black_box(&mut ptr);
+    black_box(&mut len);
+}
+
+/// Ends the innermost span named `name` begun by [`cycle_span_begin`].
+///
+/// Does nothing when not running in symex. Passing the label adds 3 cycles on Cortex-M, as for
+/// [`cycle_mark`].
+#[inline(always)]
+pub fn cycle_span_end(name: &str) {
+    cycle_span_end_raw(name.as_ptr(), name.len());
+}
+
+#[doc(hidden)]
+#[inline(never)]
+pub extern "C" fn cycle_span_end_raw(mut ptr: *const u8, mut len: usize) {
+    let mut s: u32 = 0;
+    black_box(&mut s);
+    black_box(&mut ptr);
+    black_box(&mut len);
+}
+
 /// Creates a new symbolic value for `value`. This removes all constraints.
 ///
 /// This creates a new symbolic variable and assigns overwrites the passed `value`. This must be
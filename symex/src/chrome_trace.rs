@@ -0,0 +1,196 @@
+//! Export of the timing of paths as Chrome Trace Event JSON, which can be opened in Perfetto
+//! (<https://ui.perfetto.dev>) or `chrome://tracing`.
+//!
+//! Every path is a thread of a single process and the cycle count is used as the timestamp, so a
+//! microsecond in the viewer is a cycle. Each thread has a span for the whole path, the calls
+//! made by the path, the spans begun and ended by hooks and the instant markers. The worst case
+//! path, the path with the most cycles, is sorted first and its name ends with "(worst case)".
+
+use std::{fmt::Write, fs, io, path::Path};
+
+use crate::{
+    elf_util::{PathStatus, VisualPathResult},
+    general_assembly::timing::Span,
+};
+
+/// Create a Chrome trace of the results of the paths of `function`.
+pub fn chrome_trace(function: &str, results: &[VisualPathResult]) -> String {
+    let worst_case = results
+        .iter()
+        .max_by_key(|result| result.max_cycles)
+        .map(|result| result.path);
+
+    let mut events = vec![format!(
+        r#"{{"name":"process_name","ph":"M","pid":1,"tid":0,"args":{{"name":{}}}}}"#,
+        json_string(function)
+    )];
+    for result in results {
+        let tid = result.path;
+        let is_worst_case = Some(result.path) == worst_case;
+        let thread_name = match is_worst_case {
+            true => format!("path {} (worst case)", result.path),
+            false => format!("path {}", result.path),
+        };
+        events.push(format!(
+            r#"{{"name":"thread_name","ph":"M","pid":1,"tid":{},"args":{{"name":{}}}}}"#,
+            tid,
+            json_string(&thread_name)
+        ));
+        // the worst case path is shown first
+        let sort_index = if is_worst_case { -1 } else { tid as i64 };
+        events.push(format!(
+            r#"{{"name":"thread_sort_index","ph":"M","pid":1,"tid":{},"args":{{"sort_index":{}}}}}"#,
+            tid, sort_index
+        ));
+
+        let status = match &result.result {
+            PathStatus::Ok(_) => "ok".to_owned(),
+            PathStatus::Failed(reason) => reason.error_message.to_owned(),
+            PathStatus::LimitReached(limit) => limit.to_owned(),
+        };
+        let mut args = format!(
+            r#""status":{},"cycles":{},"instructions":{}"#,
+            json_string(&status),
+            result.max_cycles,
+            result.instruction_count
+        );
+        for (key, value) in result.timeline.metadata() {
+            write!(args, ",{}:{}", json_string(key), json_string(value)).unwrap();
+        }
+        // "terrible" is the red color of the trace viewer
+        let color = match is_worst_case {
+            true => r#","cname":"terrible""#,
+            false => "",
+        };
+        events.push(format!(
+            r#"{{"name":{},"cat":"path","ph":"X","ts":0,"dur":{},"pid":1,"tid":{}{},"args":{{{}}}}}"#,
+            json_string(&thread_name),
+            result.max_cycles,
+            tid,
+            color,
+            args
+        ));
+
+        for span in result.function_spans.iter() {
+            push_spans(&mut events, span, "function", tid);
+        }
+        for span in result.spans.iter() {
+            push_spans(&mut events, span, "span", tid);
+        }
+        for (cycle, name) in result.timeline.instants() {
+            events.push(format!(
+                r#"{{"name":{},"cat":"mark","ph":"i","s":"t","ts":{},"pid":1,"tid":{}}}"#,
+                json_string(name),
+                cycle,
+                tid
+            ));
+        }
+    }
+
+    format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+}
+
+/// Write a Chrome trace of the results of the paths of `function` to the file at `path`.
+pub fn write_chrome_trace(
+    path: impl AsRef<Path>,
+    function: &str,
+    results: &[VisualPathResult],
+) -> io::Result<()> {
+    fs::write(path, chrome_trace(function, results))
+}
+
+/// Add a complete event for `span` and all spans nested in it.
+fn push_spans(events: &mut Vec<String>, span: &Span, category: &str, tid: usize) {
+    events.push(format!(
+        r#"{{"name":{},"cat":"{}","ph":"X","ts":{},"dur":{},"pid":1,"tid":{}}}"#,
+        json_string(&span.name),
+        category,
+        span.start,
+        span.cycles(),
+        tid
+    ));
+    for child in span.children.iter() {
+        push_spans(events, child, category, tid);
+    }
+}
+
+/// Quote and escape a string for JSON.
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::general_assembly::{
+        profile::Profile,
+        timing::{Timeline, TimingEventKind},
+    };
+
+    fn path_result(path: usize, max_cycles: usize, timeline: Timeline) -> VisualPathResult {
+        VisualPathResult {
+            path,
+            result: PathStatus::Ok(None),
+            symbolics: vec![],
+            end_state: vec![],
+            instruction_count: 1,
+            max_cycles,
+            profile: Profile::default(),
+            spans: timeline.spans(max_cycles),
+            timeline,
+            function_spans: vec![Span {
+                name: "main".to_owned(),
+                start: 0,
+                end: max_cycles,
+                children: vec![],
+            }],
+            preemptions: vec![],
+            peripheral_writes: vec![],
+            output: String::new(),
+            exit_status: None,
+            observers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let mut timeline = Timeline::default();
+        timeline.push(1, TimingEventKind::SpanBegin("lock".to_owned()));
+        timeline.push(2, TimingEventKind::Instant("mark".to_owned()));
+        timeline.push(3, TimingEventKind::SpanEnd("lock".to_owned()));
+        let results = [
+            path_result(1, 10, timeline),
+            path_result(2, 20, Timeline::default()),
+        ];
+
+        let trace = chrome_trace("task", &results);
+        assert!(trace.starts_with("{\"traceEvents\":["));
+        assert!(trace.contains(r#""name":"path 2 (worst case)""#));
+        assert!(
+            trace.contains(r#""name":"lock","cat":"span","ph":"X","ts":1,"dur":2,"pid":1,"tid":1"#)
+        );
+        assert!(trace.contains(
+            r#""name":"main","cat":"function","ph":"X","ts":0,"dur":20,"pid":1,"tid":2"#
+        ));
+        assert!(trace.contains(r#""name":"mark","cat":"mark","ph":"i","s":"t","ts":2"#));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), r#""a\"b\\c\n\u0001""#);
+    }
+}
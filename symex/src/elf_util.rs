@@ -1,6 +1,12 @@
 //! Utility structures mostly related to passing information to runner and display to user.
-use crate::general_assembly::state::GAState;
-use crate::general_assembly::GAError;
+use crate::general_assembly::state::{GAState, PeripheralWrite, Preemption};
+use crate::general_assembly::{
+    observer::Observer,
+    profile::Profile,
+    project::SourceLocation,
+    timing::{Span, Timeline},
+    GAError,
+};
 use colored::*;
 use core::fmt::{self, Write};
 use indenter::indented;
@@ -36,8 +42,32 @@ pub struct VisualPathResult {
     /// the maximum number of clock cycles the path can contain
     pub max_cycles: usize,
 
-    /// cycle counts at marked events
-    pub cycle_laps: Vec<(usize, String)>,
+    /// Cycles and instructions spent in each function.
+    pub profile: Profile,
+
+    /// Timing events recorded by hooks, in the order they happened.
+    pub timeline: Timeline,
+
+    /// The spans of the timing events as a tree.
+    pub spans: Vec<Span>,
+
+    /// The calls made by the path as a tree of spans named after the called functions.
+    pub function_spans: Vec<Span>,
+
+    /// Interrupts that preempted the path, in the order they were taken.
+    pub preemptions: Vec<Preemption>,
+
+    /// Writes to peripheral registers, in the order they were made.
+    pub peripheral_writes: Vec<PeripheralWrite>,
+
+    /// Output written by the path with semihosting.
+    pub output: String,
+
+    /// Exit status reported by the path with semihosting.
+    pub exit_status: Option<u64>,
+
+    /// The observers of the path as they were when the path ended.
+    pub observers: Vec<Box<dyn Observer>>,
 }
 
 fn elf_get_values<'a, I>(vars: I, state: &GAState) -> Result<Vec<Variable>, GAError>
@@ -61,7 +91,7 @@ where
 impl VisualPathResult {
     /// Creates a result from a state.
     pub fn from_state(
-        state: GAState,
+        mut state: GAState,
         path_num: usize,
         result: PathStatus,
     ) -> Result<Self, GAError> {
@@ -72,10 +102,21 @@ impl VisualPathResult {
             .map(|(reg_name, value)| Variable {
                 name: Some(reg_name.to_owned()),
                 value: value.to_owned(),
-                ty: ExpressionType::Integer(state.project.get_word_size() as usize),
+                ty: state.register_type(reg_name),
             })
             .collect();
         let end_state = elf_get_values(registers.iter(), &state)?;
+        let written_values =
+            elf_get_values(state.peripheral_writes.iter().map(|w| &w.value), &state)?;
+        let peripheral_writes = state
+            .peripheral_writes
+            .iter()
+            .zip(written_values)
+            .map(|(write, value)| PeripheralWrite {
+                value,
+                ..write.clone()
+            })
+            .collect();
 
         Ok(VisualPathResult {
             path: path_num,
@@ -84,9 +125,24 @@ impl VisualPathResult {
             end_state,
             instruction_count: state.get_instruction_count(),
             max_cycles: state.cycle_count,
-            cycle_laps: state.cycle_laps.clone(),
+            profile: std::mem::take(&mut state.profile),
+            spans: state.timeline.spans(state.cycle_count),
+            function_spans: state.function_spans.spans(state.cycle_count),
+            timeline: std::mem::take(&mut state.timeline),
+            preemptions: state.preemptions.clone(),
+            peripheral_writes,
+            output: state.output.clone(),
+            exit_status: state.exit_status,
+            observers: std::mem::take(&mut state.observers),
         })
     }
+
+    /// Get the observer of type `T` of the path.
+    pub fn observer<T: Observer + 'static>(&self) -> Option<&T> {
+        self.observers
+            .iter()
+            .find_map(|observer| observer.as_any().downcast_ref::<T>())
+    }
 }
 
 impl fmt::Display for VisualPathResult {
@@ -106,6 +162,23 @@ impl fmt::Display for VisualPathResult {
             }
             PathStatus::Failed(err) => {
                 writeln!(f, "{}: {}", "Error".red(), err.error_message)?;
+                if let Some(call_site) = &err.call_site {
+                    writeln!(indented(f), "called at {}", call_site)?;
+                }
+                if let Some(location) = &err.location {
+                    writeln!(indented(f), "last user code at {}", location)?;
+                }
+
+                writeln!(f, "Stacktrace:")?;
+                for (n, line) in err.stack_trace.iter().enumerate() {
+                    writeln!(f, "{n:4}: {}", line.function_name)?;
+                    if let Some(line) = &line.line {
+                        writeln!(indented(f), "at {line}")?;
+                    }
+                }
+            }
+            PathStatus::LimitReached(limit) => {
+                writeln!(f, "{}: {}", "Limit reached".yellow(), limit)?;
             }
         }
 
@@ -133,6 +206,62 @@ impl fmt::Display for VisualPathResult {
             }
         }
 
+        if !self.preemptions.is_empty() {
+            writeln!(f, "\nPreempted by:")?;
+            for preemption in self.preemptions.iter() {
+                writeln!(
+                    indented(f),
+                    "{} (exception {}) at {:#010x} after {} cycles",
+                    preemption.handler,
+                    preemption.exception_number,
+                    preemption.pc,
+                    preemption.cycle
+                )?;
+            }
+        }
+
+        if !self.peripheral_writes.is_empty() {
+            writeln!(f, "\nPeripheral writes:")?;
+            for write in self.peripheral_writes.iter() {
+                writeln!(
+                    indented(f),
+                    "{} ({:#010x}): {} after {} cycles",
+                    write.register,
+                    write.address,
+                    write.value,
+                    write.cycle
+                )?;
+            }
+        }
+
+        if !self.output.is_empty() {
+            writeln!(f, "\nOutput:")?;
+            writeln!(indented(f), "{}", self.output.trim_end())?;
+        }
+
+        if let Some(status) = self.exit_status {
+            writeln!(f, "Exit status: {}", status)?;
+        }
+
+        for observer in self.observers.iter() {
+            if let Some(result) = observer.result() {
+                writeln!(f, "\n{}:", observer.name())?;
+                writeln!(indented(f), "{}", result.trim_end())?;
+            }
+        }
+
+        if !self.spans.is_empty() {
+            writeln!(f, "\nSpans:")?;
+            for span in self.spans.iter() {
+                write!(indented(f), "{}", span)?;
+            }
+        }
+
+        if !self.profile.is_empty() {
+            writeln!(f, "\nProfile:")?;
+            write!(indented(f), "{}", self.profile)?;
+        }
+
         writeln!(f, "Instructions executed: {}", self.instruction_count)?;
 
         writeln!(f, "Max number of cycles: {}", self.max_cycles)?;
@@ -152,6 +281,9 @@ pub enum PathStatus {
 
     /// The path failed.
     Failed(ErrorReason),
+
+    /// The path was ended because it exceeded a exploration limit.
+    LimitReached(String),
 }
 
 /// Detailed description of why a run failed.
@@ -161,6 +293,17 @@ pub enum PathStatus {
 pub struct ErrorReason {
     /// Error message from the received error.
     pub error_message: String,
+
+    /// Location of the last executed instruction in the user code.
+    pub location: Option<SourceLocation>,
+
+    /// Location of the call to the function that ended the path, such as a panic function.
+    pub call_site: Option<SourceLocation>,
+
+    /// The stack trace to where the error was encountered.
+    ///
+    /// The stack trace is in the order of the innermost call to the outermost.
+    pub stack_trace: Vec<LineTrace>,
 }
 
 /// One line in the stack trace. Contains the name of the function and the line where it occurred.
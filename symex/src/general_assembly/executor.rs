@@ -10,26 +10,32 @@ use crate::{
 };
 
 use super::{
-    instruction::{Instruction, Operand, Operation},
-    project::Project,
+    instruction::{Comparison, Instruction, Operand, Operation},
+    memory_map::Access,
+    project::{HookResult, PCHook, Project},
     state::{ContinueInsideInstruction, GAState},
     vm::VM,
-    DataWord, Result,
+    DataWord, InstructionBehaviour, Limit, Result,
 };
 
+mod semihosting;
+
 pub struct GAExecutor<'vm> {
     pub vm: &'vm mut VM,
     pub state: GAState,
     pub project: &'static Project,
     //current_instruction: Option<Instruction>,
     current_operation_index: usize,
+    end_path: Option<PathResult>,
+    sleeping: bool,
 }
 
 pub enum PathResult {
     Success(Option<DExpr>),
-    Faliure(&'static str),
+    Faliure(String),
     AssumptionUnsat,
     Suppress,
+    LimitReached(Limit),
 }
 
 struct AddWithCarryResult {
@@ -47,6 +53,8 @@ impl<'vm> GAExecutor<'vm> {
             project,
             //current_instruction: None,
             current_operation_index: 0,
+            end_path: None,
+            sleeping: false,
         }
     }
 
@@ -57,42 +65,27 @@ impl<'vm> GAExecutor<'vm> {
             Some(i) => {
                 self.continue_executing_instruction(&i)?;
                 self.state.continue_in_instruction = None;
-                self.state.set_last_instruction(i.instruction);
+                self.finish_instruction(i.instruction);
             }
             None => (),
         }
 
         loop {
+            if self.state.is_exception_return() {
+                // Count the instruction that performed the exception return.
+                self.state.increment_cycle_count();
+                self.state.exception_return()?;
+                self.state.last_instruction = None;
+                continue;
+            }
+
+            self.fork_preemptions()?;
+
             let instruction = match self.state.get_next_instruction()? {
                 HookOrInstruction::Instruction(v) => v,
-                HookOrInstruction::PcHook(hook) => match hook {
-                    crate::general_assembly::project::PCHook::Continue => {
-                        debug!("Continuing");
-                        let lr = self.state.get_register("LR".to_owned()).unwrap();
-                        self.state.set_register("PC".to_owned(), lr)?;
-                        continue;
-                    }
-                    crate::general_assembly::project::PCHook::EndSuccess => {
-                        debug!("Symbolic execution ended succesfully");
-                        self.state.increment_cycle_count();
-                        return Ok(PathResult::Success(None));
-                    }
-                    crate::general_assembly::project::PCHook::EndFaliure(reason) => {
-                        debug!("Symbolic execution ended unsuccesfully");
-                        self.state.increment_cycle_count();
-                        return Ok(PathResult::Faliure(reason));
-                    }
-                    crate::general_assembly::project::PCHook::Suppress => {
-                        self.state.increment_cycle_count();
-                        return Ok(PathResult::Suppress);
-                    }
-                    crate::general_assembly::project::PCHook::Intrinsic(f) => {
-                        f(&mut self.state)?;
-
-                        // set last instruction to empty to no count instruction twice
-                        self.state.last_instruction = None;
-                        continue;
-                    }
+                HookOrInstruction::PcHook(hook) => match self.run_pc_hook(hook)? {
+                    Some(result) => return Ok(result),
+                    None => continue,
                 },
             };
 
@@ -101,14 +94,67 @@ impl<'vm> GAExecutor<'vm> {
 
             trace!("executing instruction: {:?}", instruction);
             self.execute_instruction(&instruction)?;
+            self.finish_instruction(instruction);
+
+            if let Some(result) = self.end_path.take() {
+                // A sleeping core can be woken up by a interrupt after the instruction.
+                if self.sleeping {
+                    self.fork_preemptions()?;
+                }
+                self.state.increment_cycle_count();
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Run the hook on the function at the PC instead of the function, returns the result of the
+    /// path if the hook ended it.
+    fn run_pc_hook(&mut self, hook: PCHook) -> Result<Option<PathResult>> {
+        match hook {
+            PCHook::Continue => {
+                debug!("Continuing");
+                let return_address_register = self.project.get_target().return_address_register();
+                let lr = self
+                    .state
+                    .get_register(return_address_register.to_owned())
+                    .unwrap();
+                self.state.set_register("PC".to_owned(), lr)?;
+            }
+            PCHook::EndSuccess => {
+                debug!("Symbolic execution ended succesfully");
+                self.state.increment_cycle_count();
+                return Ok(Some(PathResult::Success(None)));
+            }
+            PCHook::EndFaliure(reason) => {
+                debug!("Symbolic execution ended unsuccesfully");
+                self.state.increment_cycle_count();
+                return Ok(Some(PathResult::Faliure(reason)));
+            }
+            PCHook::Suppress => {
+                self.state.increment_cycle_count();
+                return Ok(Some(PathResult::Suppress));
+            }
+            PCHook::Intrinsic(_) => {
+                let result = self.state.run_intrinsic_hook()?;
 
-            self.state.set_last_instruction(instruction);
+                // set last instruction to empty to no count instruction twice
+                self.state.last_instruction = None;
+                if let Some(result) = self.handle_hook_result(result)? {
+                    return Ok(Some(result));
+                }
+            }
         }
+
+        // the hook returns without executing a instruction, pop the call to the hooked function
+        self.state.return_to_pc();
+        Ok(None)
     }
 
     // Fork execution. Will create a new path with `constraint`.
     fn fork(&mut self, constraint: DExpr) -> Result<()> {
         trace!("Save backtracking path: constraint={:?}", constraint);
+        self.state
+            .notify(|observer, state| observer.fork(state, &constraint));
         let forked_state = self.state.clone();
         let path = Path::new(forked_state, Some(constraint));
 
@@ -116,6 +162,79 @@ impl<'vm> GAExecutor<'vm> {
         Ok(())
     }
 
+    /// Carry out the result of a intrinsic hook, returns the result of the path if the hook
+    /// ended it.
+    fn handle_hook_result(&mut self, result: HookResult) -> Result<Option<PathResult>> {
+        match result {
+            HookResult::Continue => Ok(None),
+            HookResult::Fork(condition) => {
+                let true_possible = self.state.constraints.is_sat_with_constraint(&condition)?;
+                let false_possible = self
+                    .state
+                    .constraints
+                    .is_sat_with_constraint(&condition.not())?;
+                match (true_possible, false_possible) {
+                    (true, true) => {
+                        self.fork(condition.not())?;
+                        self.state.constraints.assert(&condition);
+                    }
+                    (true, false) => (),
+                    (false, true) => self.state.constraints.assert(&condition.not()),
+                    (false, false) => return Err(SolverError::Unsat.into()),
+                }
+                Ok(None)
+            }
+            HookResult::Assume(condition) => {
+                if !self.state.constraints.is_sat_with_constraint(&condition)? {
+                    debug!("Assumption made by hook can not hold");
+                    return Ok(Some(PathResult::AssumptionUnsat));
+                }
+                self.state.constraints.assert(&condition);
+                Ok(None)
+            }
+            HookResult::EndSuccess => {
+                debug!("Symbolic execution ended succesfully");
+                self.state.increment_cycle_count();
+                Ok(Some(PathResult::Success(None)))
+            }
+            HookResult::EndFaliure(reason) => {
+                debug!("Symbolic execution ended unsuccesfully");
+                self.state.increment_cycle_count();
+                Ok(Some(PathResult::Faliure(reason)))
+            }
+            HookResult::Suppress => {
+                self.state.increment_cycle_count();
+                Ok(Some(PathResult::Suppress))
+            }
+        }
+    }
+
+    /// Create a new path for each configured interrupt that can preempt the execution at the
+    /// current instruction boundary.
+    fn fork_preemptions(&mut self) -> Result<()> {
+        for interrupt in self.project.get_interrupts() {
+            let condition = match self.state.preemption_condition(interrupt) {
+                Some(condition) => condition,
+                None => continue,
+            };
+            if condition.get_constant_bool().is_none()
+                && !self.state.constraints.is_sat_with_constraint(&condition)?
+            {
+                continue;
+            }
+
+            trace!("Save preempted path: interrupt={}", interrupt.name);
+            self.state
+                .notify(|observer, state| observer.fork(state, &condition));
+            let mut preempted = self.state.clone();
+            preempted.preempt(interrupt)?;
+            self.vm
+                .paths
+                .save_path(Path::new(preempted, Some(condition)));
+        }
+        Ok(())
+    }
+
     /// Creates smt expression from a dataword.
     fn get_dexpr_from_dataword(&mut self, data: DataWord) -> DExpr {
         match data {
@@ -126,13 +245,41 @@ impl<'vm> GAExecutor<'vm> {
         }
     }
 
+    /// Notify the observers that `instruction` has been executed and make it the last
+    /// instruction, its cycles are counted before the next instruction.
+    fn finish_instruction(&mut self, instruction: Instruction) {
+        self.state
+            .notify(|observer, state| observer.instruction_end(state, state.last_pc, &instruction));
+        self.state.current_instruction = None;
+        self.state.set_last_instruction(instruction);
+    }
+
     /// Retrieves a smt expression representing value stored at `address` in memory.
     fn get_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
+        let value = self.read_memory(address, bits)?;
+        self.state
+            .notify(|observer, state| observer.memory_read(state, address, &value, bits));
+        Ok(value)
+    }
+
+    /// Sets the memory at `address` to `data`.
+    fn set_memory(&mut self, data: DExpr, address: u64, bits: u32) -> Result<()> {
+        self.write_memory(data.clone(), address, bits)?;
+        let data = data.resize_unsigned(bits);
+        self.state
+            .notify(|observer, state| observer.memory_write(state, address, &data, bits));
+        Ok(())
+    }
+
+    fn read_memory(&mut self, address: u64, bits: u32) -> Result<DExpr> {
         trace!("Getting memmory addr: {:?}", address);
         // check for hook and return early
-        if let Some(hook) = self.project.get_memory_read_hook(address) {
-            return hook(&mut self.state, address);
+        if let Some(result) = self.state.run_memory_read_hook(address) {
+            return result;
         }
+        self.project
+            .get_memory_map()
+            .check_access(address, Access::Read)?;
 
         if self.project.address_in_range(address) {
             if bits == self.project.get_word_size() {
@@ -160,13 +307,18 @@ impl<'vm> GAExecutor<'vm> {
         }
     }
 
-    /// Sets the memory at `address` to `data`.
-    fn set_memory(&mut self, data: DExpr, address: u64, bits: u32) -> Result<()> {
+    fn write_memory(&mut self, data: DExpr, address: u64, bits: u32) -> Result<()> {
         trace!("Setting memmory addr: {:?}", address);
         // check for hook and return early
-        if let Some(hook) = self.project.get_memory_write_hook(address) {
-            return hook(&mut self.state, address, data, bits);
+        if let Some(result) = self
+            .state
+            .run_memory_write_hook(address, data.clone(), bits)
+        {
+            return result;
         }
+        self.project
+            .get_memory_map()
+            .check_access(address, Access::Write)?;
 
         if self.project.address_in_range(address) {
             Err(super::GAError::WritingToStaticMemoryProhibited)
@@ -182,6 +334,24 @@ impl<'vm> GAExecutor<'vm> {
         }
     }
 
+    /// Resolves the address `address` offset by the value in register `offset_reg`.
+    fn get_offset_address(
+        &mut self,
+        address: DataWord,
+        offset_reg: &str,
+        local: &HashMap<String, DExpr>,
+    ) -> Result<u64> {
+        let ptr_size = self.project.get_ptr_size();
+        let base = self
+            .get_dexpr_from_dataword(address)
+            .resize_unsigned(ptr_size);
+        let offset = self
+            .state
+            .get_register(offset_reg.to_owned())?
+            .resize_unsigned(ptr_size);
+        self.resolve_address(base.add(&offset), local)
+    }
+
     /// Get the smt expression for a operand.
     fn get_operand_value(
         &mut self,
@@ -197,10 +367,13 @@ impl<'vm> GAExecutor<'vm> {
                 self.get_memory(address, *width)
             }
             Operand::AddressWithOffset {
-                address: _,
-                offset_reg: _,
-                width: _,
-            } => todo!(),
+                address,
+                offset_reg,
+                width,
+            } => {
+                let address = self.get_offset_address(*address, offset_reg, local)?;
+                self.get_memory(address, *width)
+            }
             Operand::Local(k) => Ok((local.get(k).unwrap()).to_owned()),
             Operand::AddressInLocal(local_name, width) => {
                 let address =
@@ -219,6 +392,12 @@ impl<'vm> GAExecutor<'vm> {
         local: &mut HashMap<String, DExpr>,
     ) -> Result<()> {
         match operand {
+            // the Arm translators write to PC through the "PC+" write hook
+            Operand::Register(v) if v == "PC" || v == "PC+" => {
+                trace!("Setting register {} to {:?}", v, value);
+                let value = self.resolve_branch_target(value, local)?;
+                self.state.set_register(v.to_owned(), value)?
+            }
             Operand::Register(v) => {
                 trace!("Setting register {} to {:?}", v, value);
                 self.state.set_register(v.to_owned(), value)?
@@ -236,10 +415,13 @@ impl<'vm> GAExecutor<'vm> {
                 self.set_memory(value, address, *width)?;
             }
             Operand::AddressWithOffset {
-                address: _,
-                offset_reg: _,
-                width: _,
-            } => todo!(),
+                address,
+                offset_reg,
+                width,
+            } => {
+                let address = self.get_offset_address(*address, offset_reg, local)?;
+                self.set_memory(value, address, *width)?;
+            }
             Operand::Local(k) => {
                 local.insert(k.to_owned(), value);
             }
@@ -252,11 +434,17 @@ impl<'vm> GAExecutor<'vm> {
             Some(addr) => Ok(*addr),
             None => {
                 // find all possible addresses
-                let addresses = self.state.constraints.get_values(&address, 255)?;
+                let max_resolutions = self.project.get_config().max_memory_access_resolutions;
+                let addresses = self
+                    .state
+                    .constraints
+                    .get_values(&address, max_resolutions)?;
 
                 let addresses = match addresses {
                     crate::smt::Solutions::Exactly(a) => Ok(a),
-                    crate::smt::Solutions::AtLeast(_) => Err(SolverError::TooManySolutions),
+                    crate::smt::Solutions::AtLeast(_) => {
+                        Err(Limit::MemoryAccessResolutions(max_resolutions))
+                    }
                 }?;
 
                 if addresses.len() == 1 {
@@ -265,33 +453,18 @@ impl<'vm> GAExecutor<'vm> {
                     return Err(SolverError::Unsat.into());
                 }
 
-                // create paths for all but the first address
+                // create paths for all but the first address, they resume by executing the
+                // memory operation again with the address constrained to a single value
+                self.state.continue_in_instruction = Some(ContinueInsideInstruction {
+                    instruction: self.state.current_instruction.as_ref().unwrap().to_owned(),
+                    index: self.current_operation_index,
+                    local: local.clone(),
+                });
                 for addr in &addresses[1..] {
-                    if self.current_operation_index
-                        < self
-                            .state
-                            .current_instruction
-                            .as_ref()
-                            .unwrap()
-                            .operations
-                            .len()
-                            - 1
-                    {
-                        self.state.continue_in_instruction = Some(ContinueInsideInstruction {
-                            instruction: self
-                                .state
-                                .current_instruction
-                                .as_ref()
-                                .unwrap()
-                                .to_owned(),
-                            index: self.current_operation_index,
-                            local: local.clone(),
-                        })
-                    }
-
                     let constraint = address._eq(addr);
                     self.fork(constraint)?;
                 }
+                self.state.continue_in_instruction = None;
 
                 // assert first address and return concrete
                 let concrete_address = &addresses[0];
@@ -303,6 +476,49 @@ impl<'vm> GAExecutor<'vm> {
         }
     }
 
+    /// Resolve a symbolic branch target, such as a function pointer, to a concrete address.
+    ///
+    /// A path is forked for each possible target but the first, the forked paths write to PC
+    /// again with the target constrained to a single value.
+    fn resolve_branch_target(
+        &mut self,
+        target: DExpr,
+        local: &HashMap<String, DExpr>,
+    ) -> Result<DExpr> {
+        if target.get_constant().is_some() {
+            return Ok(target);
+        }
+
+        let max_resolutions = self.project.get_config().max_fn_ptr_resolutions;
+        let targets = match self
+            .state
+            .constraints
+            .get_values(&target, max_resolutions)?
+        {
+            crate::smt::Solutions::Exactly(t) => Ok(t),
+            crate::smt::Solutions::AtLeast(_) => Err(Limit::FnPtrResolutions(max_resolutions)),
+        }?;
+        let Some((first, rest)) = targets.split_first() else {
+            return Err(SolverError::Unsat.into());
+        };
+        trace!("{} possible branch targets", targets.len());
+
+        if !rest.is_empty() {
+            self.state.continue_in_instruction = Some(ContinueInsideInstruction {
+                instruction: self.state.current_instruction.as_ref().unwrap().to_owned(),
+                index: self.current_operation_index,
+                local: local.clone(),
+            });
+            for other in rest {
+                self.fork(target._eq(other))?;
+            }
+            self.state.continue_in_instruction = None;
+        }
+
+        self.state.constraints.assert(&target._eq(first));
+        Ok(first.to_owned())
+    }
+
     fn continue_executing_instruction(
         &mut self,
         inst_to_continue: &ContinueInsideInstruction,
@@ -314,7 +530,7 @@ impl<'vm> GAExecutor<'vm> {
             self.current_operation_index = i;
             self.execute_operation(operation, &mut local)?;
         }
-        Ok(())
+        self.update_calls(&inst_to_continue.instruction)
     }
 
     /// Execute a single instruction.
@@ -323,6 +539,17 @@ impl<'vm> GAExecutor<'vm> {
         let new_pc = self.state.get_register("PC".to_owned())?;
         self.state.last_pc = new_pc.get_constant().unwrap();
 
+        let max_iter_count = self.project.get_config().max_iter_count;
+        if self.state.visit(self.state.last_pc) > max_iter_count {
+            return Err(Limit::IterationCount(self.state.last_pc, max_iter_count).into());
+        }
+        if self.project.is_user_code(self.state.last_pc) {
+            self.state.last_user_pc = Some(self.state.last_pc);
+        }
+        self.state.record_function_span(self.state.last_pc);
+        self.state
+            .notify(|observer, state| observer.instruction_start(state, state.last_pc, i));
+
         // Always increment pc before executing the operations
         self.state.set_register(
             "PC".to_owned(),
@@ -343,6 +570,35 @@ impl<'vm> GAExecutor<'vm> {
 
         self.state.current_instruction = Some(i.to_owned());
 
+        // skip the instruction if it is in a conditional block and the condition does not hold
+        if let Some(condition) = self.state.next_conditional_execution() {
+            let c = self.state.get_expr(&condition)?.simplify();
+            trace!("conditional execution expr: {:?}", c);
+            let execute = match c.get_constant_bool() {
+                Some(constant_c) => constant_c,
+                None => {
+                    let true_possible = self.state.constraints.is_sat_with_constraint(&c)?;
+                    let false_possible = self.state.constraints.is_sat_with_constraint(&c.not())?;
+                    match (true_possible, false_possible) {
+                        (true, true) => {
+                            // the forked path skips this instruction but its cycles should
+                            // still be counted when resuming
+                            self.state.set_last_instruction(i.to_owned());
+                            self.fork(c.not())?;
+                            self.state.constraints.assert(&c);
+                            true
+                        }
+                        (true, false) => true,
+                        (false, true) => false,
+                        (false, false) => return Err(SolverError::Unsat.into()),
+                    }
+                }
+            };
+            if !execute {
+                return Ok(());
+            }
+        }
+
         // initiate local variable storage
         let mut local: HashMap<String, DExpr> = HashMap::new();
         for (n, operation) in i.operations.iter().enumerate() {
@@ -350,9 +606,89 @@ impl<'vm> GAExecutor<'vm> {
             self.execute_operation(operation, &mut local)?;
         }
 
+        self.update_calls(i)
+    }
+
+    /// Update the call stack after the operations of `i` at the last PC have been executed,
+    /// also when a forked path resumes inside the instruction.
+    fn update_calls(&mut self, i: &Instruction) -> Result<()> {
+        let return_address = self.state.last_pc + (i.instruction_size / 8) as u64;
+        self.state.last_call_stack = self.state.call_stack.clone();
+        self.state.update_call_stack(return_address);
+        let max_call_depth = self.project.get_config().max_call_depth;
+        if self.state.call_stack.len() > max_call_depth {
+            return Err(Limit::CallDepth(max_call_depth).into());
+        }
+
+        Ok(())
+    }
+
+    /// Jump to `dest_value` if `c` holds, forks the path if both outcomes are possible.
+    fn conditional_jump(
+        &mut self,
+        c: DExpr,
+        dest_value: DExpr,
+        local: &HashMap<String, DExpr>,
+    ) -> Result<()> {
+        // if constant just jump
+        if let Some(constant_c) = c.get_constant_bool() {
+            if constant_c {
+                self.state.set_has_jumped();
+                let destination = dest_value;
+                self.state.set_register("PC".to_owned(), destination)?;
+            }
+            return Ok(());
+        }
+
+        let true_possible = self.state.constraints.is_sat_with_constraint(&c)?;
+        let false_possible = self.state.constraints.is_sat_with_constraint(&c.not())?;
+        trace!(
+            "true possible: {} false possible: {}",
+            true_possible,
+            false_possible
+        );
+
+        let destination: DExpr = match (true_possible, false_possible) {
+            (true, true) => {
+                if self.current_operation_index
+                    < (self
+                        .state
+                        .current_instruction
+                        .as_ref()
+                        .unwrap()
+                        .operations
+                        .len()
+                        - 1)
+                {
+                    self.state.continue_in_instruction = Some(ContinueInsideInstruction {
+                        instruction: self.state.current_instruction.as_ref().unwrap().to_owned(),
+                        index: self.current_operation_index + 1,
+                        local: local.to_owned(),
+                    });
+                }
+                self.fork(c.not())?;
+                self.state.constraints.assert(&c);
+                self.state.set_has_jumped();
+                Ok(dest_value)
+            }
+            (true, false) => {
+                self.state.set_has_jumped();
+                Ok(dest_value)
+            }
+            (false, true) => Ok(self.state.get_register("PC".to_owned())?), // safe to asume PC exist
+            (false, false) => Err(SolverError::Unsat),
+        }?;
+
+        self.state.set_register("PC".to_owned(), destination)?;
         Ok(())
     }
 
+    /// Set the sticky saturation flag if `saturated` holds.
+    fn set_q_flag(&mut self, saturated: DExpr) {
+        let q = self.state.get_flag("Q".to_owned()).unwrap();
+        self.state.set_flag("Q".to_owned(), q.or(&saturated));
+    }
+
     /// Execute a single operation or all operations contained inside a operation.
     fn execute_operation(
         &mut self,
@@ -399,6 +735,57 @@ impl<'vm> GAExecutor<'vm> {
                 let result = op1.mul(&op2);
                 self.set_operand_value(destination, result, local)?;
             }
+            Operation::MulLong {
+                destination_high,
+                destination_low,
+                operand1,
+                operand2,
+                signed,
+                accumulate,
+            } => {
+                let word_size = self.project.get_word_size();
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let (op1, op2) = if *signed {
+                    (op1.sign_ext(2 * word_size), op2.sign_ext(2 * word_size))
+                } else {
+                    (op1.zero_ext(2 * word_size), op2.zero_ext(2 * word_size))
+                };
+                let mut result = op1.mul(&op2);
+                if *accumulate {
+                    let high = self.get_operand_value(destination_high, local)?;
+                    let low = self.get_operand_value(destination_low, local)?;
+                    result = result.add(&high.concat(&low));
+                }
+                self.set_operand_value(destination_low, result.slice(0, word_size - 1), local)?;
+                self.set_operand_value(
+                    destination_high,
+                    result.slice(word_size, 2 * word_size - 1),
+                    local,
+                )?;
+            }
+            Operation::UDiv {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let zero = self.state.ctx.zero(self.project.get_word_size());
+                let result = op2._eq(&zero).ite(&zero, &op1.udiv(&op2));
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::SDiv {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let zero = self.state.ctx.zero(self.project.get_word_size());
+                let result = op2._eq(&zero).ite(&zero, &op1.sdiv(&op2));
+                self.set_operand_value(destination, result, local)?;
+            }
             Operation::And {
                 destination,
                 operand1,
@@ -479,153 +866,298 @@ impl<'vm> GAExecutor<'vm> {
                 );
                 let value = self.get_operand_value(operand, &local)?;
                 let shift = self.get_operand_value(shift, &local)?.srem(&word_size);
-                let result = value.srl(&shift).or(&value.srl(&word_size).sub(&shift));
+                let result = value.srl(&shift).or(&value.sll(&word_size.sub(&shift)));
                 self.set_operand_value(destination, result, local)?;
             }
-            Operation::ConditionalJump {
+            Operation::CountLeadingZeroes {
                 destination,
-                condition,
+                operand,
             } => {
-                let dest_value = self.get_operand_value(destination, &local)?;
-                let c = self.state.get_expr(condition)?.simplify();
-                trace!("conditional expr: {:?}", c);
-
-                // if constant just jump
-                if let Some(constant_c) = c.get_constant_bool() {
-                    if constant_c {
-                        self.state.set_has_jumped();
-                        let destination = dest_value;
-                        self.state.set_register("PC".to_owned(), destination)?;
-                    }
-                    return Ok(());
+                let word_size = self.project.get_word_size();
+                let op = self.get_operand_value(operand, local)?;
+                let mut result = self.state.ctx.from_u64(word_size as u64, word_size);
+                // the highest set bit is checked last and decides the result
+                for i in 0..word_size {
+                    let leading_zeroes = self
+                        .state
+                        .ctx
+                        .from_u64((word_size - 1 - i) as u64, word_size);
+                    let bit_set = op.slice(i, i)._eq(&self.state.ctx.one(1));
+                    result = bit_set.ite(&leading_zeroes, &result);
                 }
-
-                let true_possible = self.state.constraints.is_sat_with_constraint(&c)?;
-                let false_possible = self.state.constraints.is_sat_with_constraint(&c.not())?;
-                trace!(
-                    "true possible: {} false possible: {}",
-                    true_possible,
-                    false_possible
-                );
-
-                let destination: DExpr = match (true_possible, false_possible) {
-                    (true, true) => {
-                        if self.current_operation_index
-                            < (self
-                                .state
-                                .current_instruction
-                                .as_ref()
-                                .unwrap()
-                                .operations
-                                .len()
-                                - 1)
-                        {
-                            self.state.continue_in_instruction = Some(ContinueInsideInstruction {
-                                instruction: self
-                                    .state
-                                    .current_instruction
-                                    .as_ref()
-                                    .unwrap()
-                                    .to_owned(),
-                                index: self.current_operation_index + 1,
-                                local: local.to_owned(),
-                            });
-                        }
-                        self.fork(c.not())?;
-                        self.state.constraints.assert(&c);
-                        self.state.set_has_jumped();
-                        Ok(dest_value)
-                    }
-                    (true, false) => {
-                        self.state.set_has_jumped();
-                        Ok(dest_value)
-                    }
-                    (false, true) => Ok(self.state.get_register("PC".to_owned())?), // safe to asume PC exist
-                    (false, false) => Err(SolverError::Unsat),
-                }?;
-
-                self.state.set_register("PC".to_owned(), destination)?;
+                self.set_operand_value(destination, result, local)?;
             }
-            Operation::SetNFlag(operand) => {
-                let value = self.get_operand_value(operand, &local)?;
-                let shift = self
-                    .state
-                    .ctx
-                    .from_u64((self.project.get_word_size() - 1) as u64, 32);
-                let result = value.srl(&shift).resize_unsigned(1);
-                self.state.set_flag("N".to_owned(), result);
+            Operation::ReverseBits {
+                destination,
+                operand,
+            } => {
+                let op = self.get_operand_value(operand, local)?;
+                let mut result = op.slice(0, 0);
+                for i in 1..self.project.get_word_size() {
+                    result = result.concat(&op.slice(i, i));
+                }
+                self.set_operand_value(destination, result, local)?;
             }
-            Operation::SetZFlag(operand) => {
-                let value = self.get_operand_value(operand, &local)?;
-                let result = value._eq(&self.state.ctx.zero(self.project.get_word_size()));
-                self.state.set_flag("Z".to_owned(), result);
+            Operation::Saturate {
+                destination,
+                operand,
+                bits,
+                signed,
+            } => {
+                let word_size = self.project.get_word_size();
+                let mask = u64::MAX >> (64 - word_size);
+                let op = self.get_operand_value(operand, local)?;
+                let (max, min) = if *signed {
+                    (
+                        (1u64 << (bits - 1)) - 1,
+                        (-(1i64 << (bits - 1))) as u64 & mask,
+                    )
+                } else {
+                    ((1u64 << bits) - 1, 0)
+                };
+                let max = self.state.ctx.from_u64(max, word_size);
+                let min = self.state.ctx.from_u64(min, word_size);
+                let above = op.sgt(&max);
+                let below = op.slt(&min);
+                let result = above.ite(&max, &below.ite(&min, &op));
+                self.set_q_flag(above.or(&below));
+                self.set_operand_value(destination, result, local)?;
             }
-            Operation::SetCFlag {
+            Operation::SaturatingAdd {
+                destination,
                 operand1,
                 operand2,
-                sub,
-                carry,
+                signed,
             } => {
-                let op1 = self.get_operand_value(operand1, &local)?;
-                let op2 = self.get_operand_value(operand2, &local)?;
-                let one = self.state.ctx.from_u64(1, self.project.get_word_size());
-
-                let result = match (sub, carry) {
-                    (true, true) => {
-                        // I do not now if this part is used in any ISA but it is here for completeness.
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
-                        let op2 = op2.not();
-
-                        // Check for carry on twos complement of op2
-                        // Fixes edgecase op2 = 0.
-                        let c2 = op2.uaddo(&one);
-
-                        add_with_carry(
-                            &op1,
-                            &op2.add(&one),
-                            &carry_in,
-                            self.project.get_word_size(),
-                        )
-                        .carry_out
-                        .or(&c2)
-                    }
-                    (true, false) => {
-                        add_with_carry(&op1, &op2.not(), &one, self.project.get_word_size())
-                            .carry_out
-                    }
-                    (false, true) => {
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
-                        add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size())
-                            .carry_out
-                    }
-                    (false, false) => op1.uaddo(&op2),
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let (result, saturated) = if *signed {
+                    (op1.sadds(&op2), op1.saddo(&op2))
+                } else {
+                    (op1.uadds(&op2), op1.uaddo(&op2))
                 };
-
-                self.state.set_flag("C".to_owned(), result);
+                self.set_q_flag(saturated);
+                self.set_operand_value(destination, result, local)?;
             }
-            Operation::SetVFlag {
+            Operation::SaturatingSub {
+                destination,
                 operand1,
                 operand2,
-                sub,
-                carry,
+                signed,
             } => {
-                let op1 = self.get_operand_value(operand1, &local)?;
-                let op2 = self.get_operand_value(operand2, &local)?;
-                let one = self.state.ctx.from_u64(1, self.project.get_word_size());
-
-                let result = match (sub, carry) {
-                    (true, true) => {
-                        // slightly wrong at op2 = 0
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
-                        let op2 = op2.not().add(&one);
-                        add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size()).overflow
-                    }
-                    (true, false) => {
-                        add_with_carry(&op1, &op2.not(), &one, self.project.get_word_size())
-                            .overflow
-                    }
-                    (false, true) => {
-                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let (result, saturated) = if *signed {
+                    (op1.ssubs(&op2), op1.ssubo(&op2))
+                } else {
+                    (op1.usubs(&op2), op1.usubo(&op2))
+                };
+                self.set_q_flag(saturated);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FAdd {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = op1.fp32_add(&op2);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FSub {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = op1.fp32_sub(&op2);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FMul {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = op1.fp32_mul(&op2);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FDiv {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let result = op1.fp32_div(&op2);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FCompare {
+                destination,
+                operand1,
+                operand2,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let less_than = op1.fp32_lt(&op2);
+                let equal = op1.fp32_eq(&op2);
+                let unordered = op1.fp32_is_nan().or(&op2.fp32_is_nan());
+                let flags = less_than
+                    .concat(&equal)
+                    .concat(&less_than.not())
+                    .concat(&unordered);
+                let result = flags
+                    .zero_ext(self.project.get_word_size())
+                    .sll(&self.state.ctx.from_u64(28, self.project.get_word_size()));
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::FloatToInt {
+                destination,
+                operand,
+                signed,
+                round_to_zero,
+            } => {
+                let value = self.get_operand_value(operand, local)?;
+                let result = value.fp32_to_int(*signed, *round_to_zero);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::IntToFloat {
+                destination,
+                operand,
+                signed,
+            } => {
+                let value = self.get_operand_value(operand, local)?;
+                let result = value.fp32_from_int(*signed);
+                self.set_operand_value(destination, result, local)?;
+            }
+            Operation::ConditionalJump {
+                destination,
+                condition,
+            } => {
+                let dest_value = self.get_operand_value(destination, &local)?;
+                let c = self.state.get_expr(condition)?.simplify();
+                trace!("conditional expr: {:?}", c);
+                self.conditional_jump(c, dest_value, local)?;
+            }
+            Operation::CompareAndJump {
+                destination,
+                operand1,
+                operand2,
+                comparison,
+            } => {
+                let dest_value = self.get_operand_value(destination, local)?;
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let c = compare(&op1, &op2, comparison);
+                trace!("compare and jump expr: {:?}", c);
+                self.conditional_jump(c, dest_value, local)?;
+            }
+            Operation::SetOnComparison {
+                destination,
+                operand1,
+                operand2,
+                comparison,
+            } => {
+                let op1 = self.get_operand_value(operand1, local)?;
+                let op2 = self.get_operand_value(operand2, local)?;
+                let c = compare(&op1, &op2, comparison);
+                let one = self.state.ctx.from_u64(1, self.project.get_word_size());
+                let zero = self.state.ctx.zero(self.project.get_word_size());
+                self.set_operand_value(destination, c.ite(&one, &zero), local)?;
+            }
+            Operation::ConditionalExecution { conditions } => {
+                self.state.set_conditional_execution(conditions.to_owned());
+            }
+            Operation::IfNotInConditionalBlock { operations } => {
+                if !self.state.in_conditional_block() {
+                    for operation in operations {
+                        self.execute_operation(operation, local)?;
+                    }
+                }
+            }
+            Operation::SetNFlag(operand) => {
+                let value = self.get_operand_value(operand, &local)?;
+                let shift = self
+                    .state
+                    .ctx
+                    .from_u64((self.project.get_word_size() - 1) as u64, 32);
+                let result = value.srl(&shift).resize_unsigned(1);
+                self.state.set_flag("N".to_owned(), result);
+            }
+            Operation::SetZFlag(operand) => {
+                let value = self.get_operand_value(operand, &local)?;
+                let result = value._eq(&self.state.ctx.zero(self.project.get_word_size()));
+                self.state.set_flag("Z".to_owned(), result);
+            }
+            Operation::SetCFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            } => {
+                let op1 = self.get_operand_value(operand1, &local)?;
+                let op2 = self.get_operand_value(operand2, &local)?;
+                let one = self.state.ctx.from_u64(1, self.project.get_word_size());
+
+                let result = match (sub, carry) {
+                    (true, true) => {
+                        // I do not now if this part is used in any ISA but it is here for completeness.
+                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let op2 = op2.not();
+
+                        // Check for carry on twos complement of op2
+                        // Fixes edgecase op2 = 0.
+                        let c2 = op2.uaddo(&one);
+
+                        add_with_carry(
+                            &op1,
+                            &op2.add(&one),
+                            &carry_in,
+                            self.project.get_word_size(),
+                        )
+                        .carry_out
+                        .or(&c2)
+                    }
+                    (true, false) => {
+                        add_with_carry(&op1, &op2.not(), &one, self.project.get_word_size())
+                            .carry_out
+                    }
+                    (false, true) => {
+                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size())
+                            .carry_out
+                    }
+                    (false, false) => op1.uaddo(&op2),
+                };
+
+                self.state.set_flag("C".to_owned(), result);
+            }
+            Operation::SetVFlag {
+                operand1,
+                operand2,
+                sub,
+                carry,
+            } => {
+                let op1 = self.get_operand_value(operand1, &local)?;
+                let op2 = self.get_operand_value(operand2, &local)?;
+                let one = self.state.ctx.from_u64(1, self.project.get_word_size());
+
+                let result = match (sub, carry) {
+                    (true, true) => {
+                        // slightly wrong at op2 = 0
+                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
+                        let op2 = op2.not().add(&one);
+                        add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size()).overflow
+                    }
+                    (true, false) => {
+                        add_with_carry(&op1, &op2.not(), &one, self.project.get_word_size())
+                            .overflow
+                    }
+                    (false, true) => {
+                        let carry_in = self.state.get_flag("C".to_owned()).unwrap();
                         add_with_carry(&op1, &op2, &carry_in, self.project.get_word_size()).overflow
                     }
                     (false, false) => op1.saddo(&op2),
@@ -633,12 +1165,39 @@ impl<'vm> GAExecutor<'vm> {
 
                 self.state.set_flag("V".to_owned(), result);
             }
+            Operation::SetFlag { flag, operand } => {
+                let value = self.get_operand_value(operand, local)?;
+                self.state
+                    .set_flag(flag.to_owned(), value.resize_unsigned(1));
+            }
             Operation::ForEach {
                 operands: _,
                 operations: _,
             } => {
                 todo!()
             }
+            Operation::Semihosting => self.semihosting()?,
+            Operation::Special(instruction) => {
+                let behaviour = self.project.get_instruction_behaviour(*instruction);
+                debug!("Executing {:?} as {:?}", instruction, behaviour);
+                match behaviour {
+                    InstructionBehaviour::Nop => (),
+                    InstructionBehaviour::EndSuccess => {
+                        self.end_path = Some(PathResult::Success(None));
+                    }
+                    InstructionBehaviour::EndFaliure => {
+                        self.end_path = Some(PathResult::Faliure(format!(
+                            "{} at {:#010x}",
+                            instruction.description(),
+                            self.state.last_pc
+                        )));
+                    }
+                    InstructionBehaviour::Sleep => {
+                        self.sleeping = true;
+                        self.end_path = Some(PathResult::Success(None));
+                    }
+                }
+            }
             Operation::ZeroExtend {
                 destination,
                 operand,
@@ -690,6 +1249,9 @@ impl<'vm> GAExecutor<'vm> {
                         self.project.get_word_size() + 1,
                     ))
                     .resize_unsigned(1);
+                // a shift by zero leaves the carry unchanged
+                let no_shift = shift._eq(&self.state.ctx.zero(1 + self.project.get_word_size()));
+                let carry = no_shift.ite(&self.state.get_flag("C".to_owned()).unwrap(), &carry);
                 self.state.set_flag("C".to_owned(), carry);
             }
             Operation::SetCFlagSrl { operand, shift } => {
@@ -702,6 +1264,9 @@ impl<'vm> GAExecutor<'vm> {
                     .zero_ext(1 + self.project.get_word_size());
                 let result = op.srl(&shift);
                 let carry = result.resize_unsigned(1);
+                // a shift by zero leaves the carry unchanged
+                let no_shift = shift._eq(&self.state.ctx.zero(1 + self.project.get_word_size()));
+                let carry = no_shift.ite(&self.state.get_flag("C".to_owned()).unwrap(), &carry);
                 self.state.set_flag("C".to_owned(), carry);
             }
             Operation::SetCFlagSra { operand, shift } => {
@@ -714,17 +1279,24 @@ impl<'vm> GAExecutor<'vm> {
                     .zero_ext(1 + self.project.get_word_size());
                 let result = op.sra(&shift);
                 let carry = result.resize_unsigned(1);
+                // a shift by zero leaves the carry unchanged
+                let no_shift = shift._eq(&self.state.ctx.zero(1 + self.project.get_word_size()));
+                let carry = no_shift.ite(&self.state.get_flag("C".to_owned()).unwrap(), &carry);
                 self.state.set_flag("C".to_owned(), carry);
             }
-            Operation::SetCFlagRor(operand) => {
+            Operation::SetCFlagRor { result, shift } => {
                 // this is right for armv6-m but may be wrong for other architectures
-                let result = self.get_operand_value(operand, local)?;
+                let result = self.get_operand_value(result, local)?;
+                let shift = self.get_operand_value(shift, local)?;
                 let word_size_minus_one = self.state.ctx.from_u64(
                     self.project.get_word_size() as u64 - 1,
                     self.project.get_word_size(),
                 );
                 // result = srl(op, shift) OR sll(op, word_size - shift)
                 let c = result.srl(&word_size_minus_one).resize_unsigned(1);
+                // a rotation by zero leaves the carry unchanged
+                let no_shift = shift._eq(&self.state.ctx.zero(self.project.get_word_size()));
+                let c = no_shift.ite(&self.state.get_flag("C".to_owned()).unwrap(), &c);
                 self.state.set_flag("C".to_owned(), c);
             }
         }
@@ -732,6 +1304,19 @@ impl<'vm> GAExecutor<'vm> {
     }
 }
 
+/// Creates the expression for `comparison` between `op1` and `op2`.
+fn compare(op1: &DExpr, op2: &DExpr, comparison: &Comparison) -> DExpr {
+    match comparison {
+        Comparison::EQ => op1._eq(op2),
+        Comparison::NE => op1._ne(op2),
+        Comparison::LT => op1.slt(op2),
+        Comparison::GE => op1.sgte(op2),
+        Comparison::LTU => op1.ult(op2),
+        Comparison::GEU => op1.ugte(op2),
+    }
+    .simplify()
+}
+
 /// Does a add with carry and returns result, carry out and overflow like a hw adder.
 fn add_with_carry(
     op1: &DExpr,
@@ -758,14 +1343,27 @@ mod test {
 
     use crate::{
         general_assembly::{
+            executor::PathResult,
             executor::{add_with_carry, GAExecutor},
-            instruction::{Operand, Operation},
-            project::Project,
-            state::GAState,
+            instruction::{
+                Comparison, CycleCount, Instruction, Operand, Operation, SpecialInstruction,
+            },
+            memory_map::MemoryMap,
+            observer::Observer,
+            project::target_config::ArmMCore,
+            project::{
+                HookResult, InterruptHandler, LineTable, MemoryHookAddress, MemoryRead, PCHook,
+                Project, SourceLocation,
+            },
+            run_config::RunConfig,
+            state::{ContinueInsideInstruction, GAState},
+            timing::{TimingEvent, TimingEventKind},
+            translator::Translatable,
+            translators::armv7_m::{decoder, Armv7MInstruction},
             vm::VM,
-            DataWord, Endianness, WordSize,
+            Config, DataWord, Endianness, GAError, Limit, Result, Statics, WordSize,
         },
-        smt::{DContext, DSolver},
+        smt::{DContext, DExpr, DSolver},
     };
 
     #[test]
@@ -833,8 +1431,22 @@ mod test {
     }
 
     fn setup_test_vm() -> VM {
-        // create an empty project
-        let project = Box::new(Project::manual_project(
+        setup_test_vm_with_project(test_project())
+    }
+
+    fn setup_test_vm_with_project(project: Project) -> VM {
+        let project = Box::leak(Box::new(project));
+        let context = Box::new(DContext::new());
+        let context = Box::leak(context);
+        let solver = DSolver::new(context);
+        let state = GAState::create_test_state(project, context, solver, 0, u32::MAX as u64);
+        let vm = VM::new_with_state(project, state);
+        vm
+    }
+
+    /// Create an empty project.
+    fn test_project() -> Project {
+        Project::manual_project(
             vec![],
             0,
             0,
@@ -849,14 +1461,7 @@ mod test {
             vec![],
             HashMap::new(),
             vec![],
-        ));
-        let project = Box::leak(project);
-        let context = Box::new(DContext::new());
-        let context = Box::leak(context);
-        let solver = DSolver::new(context);
-        let state = GAState::create_test_state(project, context, solver, 0, u32::MAX as u64);
-        let vm = VM::new_with_state(project, state);
-        vm
+        )
     }
 
     #[test]
@@ -930,6 +1535,44 @@ mod test {
             .unwrap();
 
         assert_eq!(local_value, 23);
+
+        // move immidiate to memmory addr offset by register
+        let imm = Operand::Immidiate(DataWord::Word32(77));
+        let offset_op = Operand::AddressWithOffset {
+            address: DataWord::Word32(0x100),
+            offset_reg: "R0".to_owned(),
+            width: 32,
+        };
+        let operation = Operation::Move {
+            destination: offset_op.clone(),
+            source: imm,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let dexpr_addr = executor.get_dexpr_from_dataword(DataWord::Word32(0x100 + 42));
+        let in_memmory_value = executor
+            .state
+            .read_word_from_memory(&dexpr_addr)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+
+        assert_eq!(in_memmory_value, 77);
+
+        // move from memmory addr offset by register to a local
+        let operation = Operation::Move {
+            destination: local_r0.clone(),
+            source: offset_op,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let local_value = executor
+            .get_operand_value(&local_r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+
+        assert_eq!(local_value, 77);
     }
 
     #[test]
@@ -1289,4 +1932,1511 @@ mod test {
             .unwrap();
         assert!(v_flag);
     }
+
+    #[test]
+    fn test_set_c_flag_shift() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let imm = |value: u32| Operand::Immidiate(DataWord::Word32(value));
+        let mut carry_after = |carry: bool, operation: Operation| {
+            let carry = executor.state.ctx.from_bool(carry);
+            executor.state.set_flag("C".to_owned(), carry);
+            executor.execute_operation(&operation, &mut local).unwrap();
+            executor
+                .state
+                .get_flag("C".to_owned())
+                .unwrap()
+                .get_constant_bool()
+                .unwrap()
+        };
+
+        // a shift by zero leaves the carry unchanged
+        for carry in [false, true] {
+            let sll = Operation::SetCFlagShiftLeft {
+                operand: imm(0x8000_0000),
+                shift: imm(0),
+            };
+            assert_eq!(carry_after(carry, sll), carry);
+            let srl = Operation::SetCFlagSrl {
+                operand: imm(1),
+                shift: imm(0),
+            };
+            assert_eq!(carry_after(carry, srl), carry);
+            let sra = Operation::SetCFlagSra {
+                operand: imm(0x8000_0001),
+                shift: imm(0),
+            };
+            assert_eq!(carry_after(carry, sra), carry);
+            let ror = Operation::SetCFlagRor {
+                result: imm(0x8000_0000),
+                shift: imm(0),
+            };
+            assert_eq!(carry_after(carry, ror), carry);
+        }
+
+        // otherwise the carry is the last bit shifted out
+        let sll = Operation::SetCFlagShiftLeft {
+            operand: imm(0x8000_0000),
+            shift: imm(1),
+        };
+        assert!(carry_after(false, sll));
+        let sll = Operation::SetCFlagShiftLeft {
+            operand: imm(0x4000_0000),
+            shift: imm(1),
+        };
+        assert!(!carry_after(true, sll));
+        let srl = Operation::SetCFlagSrl {
+            operand: imm(2),
+            shift: imm(2),
+        };
+        assert!(carry_after(false, srl));
+        let srl = Operation::SetCFlagSrl {
+            operand: imm(2),
+            shift: imm(1),
+        };
+        assert!(!carry_after(true, srl));
+        let sra = Operation::SetCFlagSra {
+            operand: imm(0x8000_0000),
+            shift: imm(32),
+        };
+        assert!(carry_after(false, sra));
+        let ror = Operation::SetCFlagRor {
+            result: imm(0x8000_0000),
+            shift: imm(32),
+        };
+        assert!(carry_after(false, ror));
+    }
+
+    #[test]
+    fn test_div() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let imm_42 = Operand::Immidiate(DataWord::Word32(42));
+        let imm_minus42 = Operand::Immidiate(DataWord::Word32(-42i32 as u32));
+        let imm_5 = Operand::Immidiate(DataWord::Word32(5));
+        let imm_0 = Operand::Immidiate(DataWord::Word32(0));
+
+        // unsigned division
+        let operation = Operation::UDiv {
+            destination: r0.clone(),
+            operand1: imm_42.clone(),
+            operand2: imm_5.clone(),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, 8);
+
+        // signed division rounds towards zero
+        let operation = Operation::SDiv {
+            destination: r0.clone(),
+            operand1: imm_minus42.clone(),
+            operand2: imm_5.clone(),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, -8i32 as u32 as u64);
+
+        // division by zero gives zero
+        let operation = Operation::UDiv {
+            destination: r0.clone(),
+            operand1: imm_42.clone(),
+            operand2: imm_0.clone(),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, 0);
+    }
+
+    #[test]
+    fn test_mul_long() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let r1 = Operand::Register("R1".to_owned());
+        let imm_umax = Operand::Immidiate(DataWord::Word32(u32::MAX));
+        let imm_2 = Operand::Immidiate(DataWord::Word32(2));
+
+        // unsigned
+        let operation = Operation::MulLong {
+            destination_high: r1.clone(),
+            destination_low: r0.clone(),
+            operand1: imm_umax.clone(),
+            operand2: imm_2.clone(),
+            signed: false,
+            accumulate: false,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let low = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        let high = executor
+            .get_operand_value(&r1, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(low, 0xfffffffe);
+        assert_eq!(high, 1);
+
+        // signed, -1 * 2 = -2
+        let operation = Operation::MulLong {
+            destination_high: r1.clone(),
+            destination_low: r0.clone(),
+            operand1: imm_umax.clone(),
+            operand2: imm_2.clone(),
+            signed: true,
+            accumulate: false,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let low = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        let high = executor
+            .get_operand_value(&r1, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(low, 0xfffffffe);
+        assert_eq!(high, 0xffffffff);
+
+        // accumulate, -2 + 2 * 2 = 2
+        let operation = Operation::MulLong {
+            destination_high: r1.clone(),
+            destination_low: r0.clone(),
+            operand1: imm_2.clone(),
+            operand2: imm_2.clone(),
+            signed: true,
+            accumulate: true,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let low = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        let high = executor
+            .get_operand_value(&r1, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(low, 2);
+        assert_eq!(high, 0);
+    }
+
+    #[test]
+    fn test_saturate() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let imm_300 = Operand::Immidiate(DataWord::Word32(300));
+        let imm_minus300 = Operand::Immidiate(DataWord::Word32(-300i32 as u32));
+        let imm_100 = Operand::Immidiate(DataWord::Word32(100));
+
+        // signed saturation to 8 bits
+        let operation = Operation::Saturate {
+            destination: r0.clone(),
+            operand: imm_minus300.clone(),
+            bits: 8,
+            signed: true,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, -128i32 as u32 as u64);
+        let q_flag = executor
+            .state
+            .get_flag("Q".to_owned())
+            .unwrap()
+            .get_constant_bool()
+            .unwrap();
+        assert!(q_flag);
+
+        // unsigned saturation to 8 bits
+        let operation = Operation::Saturate {
+            destination: r0.clone(),
+            operand: imm_300.clone(),
+            bits: 8,
+            signed: false,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, 255);
+
+        // no saturation
+        let operation = Operation::Saturate {
+            destination: r0.clone(),
+            operand: imm_100.clone(),
+            bits: 8,
+            signed: true,
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let r0_value = executor
+            .get_operand_value(&r0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(r0_value, 100);
+    }
+
+    #[test]
+    fn test_count_leading_zeroes() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+
+        for (value, expected) in [(0u32, 32u64), (1, 31), (0x00f0_0000, 8), (u32::MAX, 0)] {
+            let operation = Operation::CountLeadingZeroes {
+                destination: r0.clone(),
+                operand: Operand::Immidiate(DataWord::Word32(value)),
+            };
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let r0_value = executor
+                .get_operand_value(&r0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(r0_value, expected);
+        }
+    }
+
+    #[test]
+    fn test_set_on_comparison() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let imm_minus1 = Operand::Immidiate(DataWord::Word32(-1i32 as u32));
+        let imm_1 = Operand::Immidiate(DataWord::Word32(1));
+
+        for (comparison, expected) in [
+            (Comparison::LT, 1),
+            (Comparison::LTU, 0),
+            (Comparison::GE, 0),
+            (Comparison::GEU, 1),
+            (Comparison::EQ, 0),
+            (Comparison::NE, 1),
+        ] {
+            let operation = Operation::SetOnComparison {
+                destination: r0.clone(),
+                operand1: imm_minus1.clone(),
+                operand2: imm_1.clone(),
+                comparison,
+            };
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let r0_value = executor
+                .get_operand_value(&r0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(r0_value, expected);
+        }
+    }
+
+    #[test]
+    fn test_float_arith() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let s0 = Operand::Register("S0".to_owned());
+        let float = |value: f32| Operand::Immidiate(DataWord::Word32(value.to_bits()));
+
+        let cases = [
+            (
+                Operation::FAdd {
+                    destination: s0.clone(),
+                    operand1: float(1.5),
+                    operand2: float(0.1),
+                },
+                1.5f32 + 0.1,
+            ),
+            (
+                Operation::FSub {
+                    destination: s0.clone(),
+                    operand1: float(1.0),
+                    operand2: float(1.0e-8),
+                },
+                1.0f32 - 1.0e-8,
+            ),
+            (
+                Operation::FMul {
+                    destination: s0.clone(),
+                    operand1: float(3.3),
+                    operand2: float(-7.1),
+                },
+                3.3f32 * -7.1,
+            ),
+            (
+                Operation::FDiv {
+                    destination: s0.clone(),
+                    operand1: float(1.0),
+                    operand2: float(3.0),
+                },
+                1.0f32 / 3.0,
+            ),
+            // subnormal result
+            (
+                Operation::FMul {
+                    destination: s0.clone(),
+                    operand1: float(1.0e-20),
+                    operand2: float(1.0e-20),
+                },
+                1.0e-20f32 * 1.0e-20,
+            ),
+            // overflow to infinity
+            (
+                Operation::FAdd {
+                    destination: s0.clone(),
+                    operand1: float(f32::MAX),
+                    operand2: float(f32::MAX),
+                },
+                f32::INFINITY,
+            ),
+        ];
+
+        for (operation, expected) in cases {
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let s0_value = executor
+                .get_operand_value(&s0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(s0_value, expected.to_bits() as u64);
+        }
+
+        // infinity minus infinity gives the default NaN
+        let operation = Operation::FSub {
+            destination: s0.clone(),
+            operand1: float(f32::INFINITY),
+            operand2: float(f32::INFINITY),
+        };
+        executor.execute_operation(&operation, &mut local).ok();
+
+        let s0_value = executor
+            .get_operand_value(&s0, &local)
+            .unwrap()
+            .get_constant()
+            .unwrap();
+        assert_eq!(s0_value, 0x7fc0_0000);
+    }
+
+    #[test]
+    fn test_float_compare() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let float = |value: f32| Operand::Immidiate(DataWord::Word32(value.to_bits()));
+
+        for (operand1, operand2, expected) in [
+            (-1.0, 2.0, 0b1000),
+            (0.0, -0.0, 0b0110),
+            (2.0, 1.0, 0b0010),
+            (f32::NAN, 1.0, 0b0011),
+        ] {
+            let operation = Operation::FCompare {
+                destination: r0.clone(),
+                operand1: float(operand1),
+                operand2: float(operand2),
+            };
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let r0_value = executor
+                .get_operand_value(&r0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(r0_value, expected << 28);
+        }
+    }
+
+    #[test]
+    fn test_float_convert() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+
+        let r0 = Operand::Register("R0".to_owned());
+        let float = |value: f32| Operand::Immidiate(DataWord::Word32(value.to_bits()));
+
+        for (value, signed, round_to_zero, expected) in [
+            (-2.5, true, true, -2i32 as u32),
+            (-2.5, true, false, -2i32 as u32),
+            (3.5, false, false, 4),
+            (-1.0, false, true, 0),
+            (1.0e10, true, true, i32::MAX as u32),
+            (f32::NAN, true, true, 0),
+        ] {
+            let operation = Operation::FloatToInt {
+                destination: r0.clone(),
+                operand: float(value),
+                signed,
+                round_to_zero,
+            };
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let r0_value = executor
+                .get_operand_value(&r0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(r0_value, expected as u64);
+        }
+
+        for (value, signed, expected) in [
+            (-7i32 as u32, true, -7.0f32),
+            (-7i32 as u32, false, 4294967289.0),
+            (16777217, true, 16777216.0),
+        ] {
+            let operation = Operation::IntToFloat {
+                destination: r0.clone(),
+                operand: Operand::Immidiate(DataWord::Word32(value)),
+                signed,
+            };
+            executor.execute_operation(&operation, &mut local).ok();
+
+            let r0_value = executor
+                .get_operand_value(&r0, &local)
+                .unwrap()
+                .get_constant()
+                .unwrap();
+            assert_eq!(r0_value, expected.to_bits() as u64);
+        }
+    }
+
+    #[test]
+    fn test_exception_entry_and_return() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let ctx = vm.paths.get_path().unwrap().state.ctx;
+        let solver = DSolver::new(ctx);
+        let mut state = GAState::create_test_state(project, ctx, solver, 0x100, 0x2000_1004);
+        let word = |value: u64| ctx.from_u64(value, 32);
+
+        state.set_register("R0".to_owned(), word(5)).unwrap();
+        state.set_register("LR".to_owned(), word(0x201)).unwrap();
+        state.set_flag("N".to_owned(), ctx.from_bool(true));
+        state.set_flag("Z".to_owned(), ctx.from_bool(false));
+        state.set_flag("C".to_owned(), ctx.from_bool(false));
+        state.set_flag("V".to_owned(), ctx.from_bool(false));
+
+        state.enter_exception(15, 0x400).unwrap();
+
+        // The stack pointer is realigned to 8 bytes before the frame is pushed.
+        let sp = state.get_register("SP".to_owned()).unwrap();
+        assert_eq!(sp.get_constant().unwrap(), 0x2000_1000 - 32);
+        let lr = state.get_register("LR".to_owned()).unwrap();
+        assert_eq!(lr.get_constant().unwrap(), 0xFFFF_FFF9);
+        let ipsr = state.get_register("IPSR".to_owned()).unwrap();
+        assert_eq!(ipsr.get_constant().unwrap(), 15);
+        let stacked_pc = state.read_word_from_memory(&sp.add(&word(24))).unwrap();
+        assert_eq!(stacked_pc.get_constant().unwrap(), 0x100);
+        let stacked_xpsr = state.read_word_from_memory(&sp.add(&word(28))).unwrap();
+        assert_eq!(stacked_xpsr.get_constant().unwrap(), 0x8100_0200);
+        assert_eq!(state.cycle_count, 15);
+
+        // Clobber the caller saved state in the handler and return with BX LR.
+        state.set_register("R0".to_owned(), word(0)).unwrap();
+        state.set_flag("N".to_owned(), ctx.from_bool(false));
+        state.set_register("PC".to_owned(), lr).unwrap();
+        assert!(state.is_exception_return());
+        state.exception_return().unwrap();
+
+        let r0 = state.get_register("R0".to_owned()).unwrap();
+        assert_eq!(r0.get_constant().unwrap(), 5);
+        let lr = state.get_register("LR".to_owned()).unwrap();
+        assert_eq!(lr.get_constant().unwrap(), 0x201);
+        let sp = state.get_register("SP".to_owned()).unwrap();
+        assert_eq!(sp.get_constant().unwrap(), 0x2000_1004);
+        let pc = state.get_register("PC".to_owned()).unwrap();
+        assert_eq!(pc.get_constant().unwrap(), 0x100);
+        let n = state.get_flag("N".to_owned()).unwrap();
+        assert!(n.get_constant_bool().unwrap());
+        assert!(!state.is_exception_return());
+        assert_eq!(state.cycle_count, 30);
+    }
+
+    #[test]
+    fn test_preemption() {
+        let mut project = test_project();
+        for (name, exception_number, priority) in [("high", 17, 1), ("low", 18, 3)] {
+            project.add_interrupt(InterruptHandler {
+                name: name.to_owned(),
+                address: 0x1000 + exception_number as u64 * 0x100,
+                exception_number,
+                priority,
+            });
+        }
+        let project = Box::leak(Box::new(project));
+        let ctx = Box::leak(Box::new(DContext::new()));
+        let solver = DSolver::new(ctx);
+        let mut state = GAState::create_test_state(project, ctx, solver, 0x100, 0x2000_1000);
+        let word = |value: u64| ctx.from_u64(value, 32);
+        let high = project.get_interrupts()[0].clone();
+        let low = project.get_interrupts()[1].clone();
+
+        // Thread mode can be preempted by both interrupts unless they are masked.
+        assert!(state.preemption_condition(&high).is_some());
+        state.set_register("PRIMASK".to_owned(), word(1)).unwrap();
+        assert!(state.preemption_condition(&high).is_none());
+        state.set_register("PRIMASK".to_owned(), word(0)).unwrap();
+        state.set_register("BASEPRI".to_owned(), word(2)).unwrap();
+        assert!(state.preemption_condition(&high).is_some());
+        assert!(state.preemption_condition(&low).is_none());
+        state.set_register("BASEPRI".to_owned(), word(0)).unwrap();
+
+        // Only a higher priority interrupt can preempt a handler and only once per path.
+        state.preempt(&low).unwrap();
+        assert_eq!(
+            state.get_register("PC".to_owned()).unwrap().get_constant(),
+            Some(0x2200)
+        );
+        assert!(state.preemption_condition(&low).is_none());
+        assert!(state.preemption_condition(&high).is_some());
+        state.preempt(&high).unwrap();
+        assert!(state.preemption_condition(&high).is_none());
+
+        let preempted: Vec<(&str, u64)> = state
+            .preemptions
+            .iter()
+            .map(|p| (p.handler.as_str(), p.pc))
+            .collect();
+        assert_eq!(preempted, vec![("low", 0x100), ("high", 0x2200)]);
+    }
+
+    #[test]
+    fn test_stateful_hooks() {
+        #[derive(Clone)]
+        struct Counter {
+            reads: u64,
+        }
+
+        impl MemoryRead for Counter {
+            fn read(&mut self, state: &mut GAState, _address: u64) -> Result<DExpr> {
+                self.reads += 1;
+                Ok(state.ctx.from_u64(self.reads, 32))
+            }
+        }
+
+        let project = Box::leak(Box::new(test_project()));
+        let ctx = Box::leak(Box::new(DContext::new()));
+        let solver = DSolver::new(ctx);
+        let mut state = GAState::create_test_state(project, ctx, solver, 0x100, 0x2000_1000);
+        state.hooks.memory_read.add(
+            MemoryHookAddress::Single(0x4000_0000),
+            Box::new(Counter { reads: 0 }),
+        );
+        let read = |state: &mut GAState| {
+            state
+                .run_memory_read_hook(0x4000_0000)
+                .unwrap()
+                .unwrap()
+                .get_constant()
+                .unwrap()
+        };
+
+        assert_eq!(read(&mut state), 1);
+
+        // the hook is cloned with the state, so each path counts its own reads
+        let mut forked = state.clone();
+        assert_eq!(read(&mut state), 2);
+        assert_eq!(read(&mut forked), 2);
+        assert_eq!(read(&mut forked), 3);
+        assert_eq!(read(&mut state), 3);
+    }
+
+    #[test]
+    fn test_hook_results() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut state = vm.paths.get_path().unwrap().state;
+        let ctx = state.ctx;
+
+        // a model that may or may not succeed, returning the status in R0
+        let fork_hook = |state: &mut GAState| -> Result<HookResult> {
+            let status = state.get_register("R0".to_owned())?;
+            let ok = status._eq(&state.ctx.from_u64(0, 32));
+            state.set_register("PC".to_owned(), state.ctx.from_u64(0x300, 32))?;
+            Ok(HookResult::Fork(ok))
+        };
+        let fail_hook = |_: &mut GAState| Ok(HookResult::EndFaliure("model failed".to_owned()));
+        state.hooks.pc.insert(0x200, PCHook::intrinsic(fork_hook));
+        state.hooks.pc.insert(0x300, PCHook::intrinsic(fail_hook));
+        state
+            .set_register("PC".to_owned(), ctx.from_u64(0x200, 32))
+            .unwrap();
+
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+        let result = executor.resume_execution().unwrap();
+        assert!(matches!(result, PathResult::Faliure(reason) if reason == "model failed"));
+
+        // the path where the condition does not hold is saved
+        assert_eq!(vm.paths.waiting_paths(), 1);
+        let path = vm.paths.get_path().unwrap();
+        assert_eq!(path.constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_observers() {
+        #[derive(Clone)]
+        struct EventLog {
+            events: Vec<String>,
+        }
+
+        impl Observer for EventLog {
+            fn name(&self) -> String {
+                "Event log".to_owned()
+            }
+
+            fn instruction_start(&mut self, _: &GAState, pc: u64, _: &Instruction) {
+                self.events.push(format!("start {pc:#x}"));
+            }
+
+            fn instruction_end(&mut self, _: &GAState, pc: u64, _: &Instruction) {
+                self.events.push(format!("end {pc:#x}"));
+            }
+
+            fn memory_write(&mut self, _: &GAState, address: u64, value: &DExpr, bits: u32) {
+                let value = value.get_constant().unwrap();
+                self.events
+                    .push(format!("write {address:#x} = {value} ({bits} bits)"));
+            }
+
+            fn register_write(&mut self, _: &GAState, register: &str, value: &DExpr) {
+                let value = value.get_constant().unwrap();
+                self.events
+                    .push(format!("register {register} = {value:#x}"));
+            }
+
+            fn fork(&mut self, _: &GAState, _: &DExpr) {
+                self.events.push("fork".to_owned());
+            }
+        }
+
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut state = vm.paths.get_path().unwrap().state;
+        state.observers.push(Box::new(EventLog { events: vec![] }));
+        let mut executor = GAExecutor::from_state(state, &mut vm, project);
+
+        let store = Instruction {
+            instruction_size: 16,
+            operations: vec![Operation::Move {
+                destination: Operand::Address(DataWord::Word32(0x2000_0000), 16),
+                source: Operand::Immidiate(DataWord::Word32(42)),
+            }],
+            max_cycle: CycleCount::Value(1),
+        };
+        executor.execute_instruction(&store).unwrap();
+        executor.finish_instruction(store);
+        // the observers see the value stored in the register
+        let three = executor.state.ctx.from_u64(3, 32);
+        executor
+            .state
+            .set_register("PRIMASK".to_owned(), three)
+            .unwrap();
+        let symbolic = executor.state.get_register("R0".to_owned()).unwrap();
+        let zero = executor.state.ctx.from_u64(0, 32);
+        executor
+            .handle_hook_result(HookResult::Fork(symbolic._eq(&zero)))
+            .unwrap();
+
+        let log = executor.state.observers[0]
+            .as_any()
+            .downcast_ref::<EventLog>()
+            .unwrap();
+        assert_eq!(
+            log.events,
+            vec![
+                "start 0x0",
+                "register PC = 0x2",
+                "write 0x20000000 = 42 (16 bits)",
+                "end 0x0",
+                "register PRIMASK = 0x1",
+                "fork"
+            ]
+        );
+
+        // the forked path has its own copy of the observer
+        let forked = vm.paths.get_path().unwrap();
+        assert_eq!(forked.state.observers.len(), 1);
+    }
+
+    #[test]
+    fn test_special_registers() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let ctx = vm.paths.get_path().unwrap().state.ctx;
+        let solver = DSolver::new(ctx);
+        let mut state = GAState::create_test_state(project, ctx, solver, 0x100, 0x2000_1000);
+        let word = |value: u64| ctx.from_u64(value, 32);
+        let read = |state: &mut GAState, register: &str| {
+            state
+                .get_register(register.to_owned())
+                .unwrap()
+                .get_constant()
+                .unwrap()
+        };
+
+        state.set_register("PRIMASK".to_owned(), word(3)).unwrap();
+        assert_eq!(read(&mut state, "PRIMASK"), 1);
+        state
+            .set_register("APSR".to_owned(), word(0x6000_0000))
+            .unwrap();
+        assert_eq!(read(&mut state, "APSR"), 0x6000_0000);
+        assert!(state
+            .get_flag("Z".to_owned())
+            .unwrap()
+            .get_constant_bool()
+            .unwrap());
+
+        // Select the process stack pointer in thread mode.
+        state
+            .set_register("PSP".to_owned(), word(0x2000_0800))
+            .unwrap();
+        assert_eq!(read(&mut state, "SP"), 0x2000_1000);
+        state
+            .set_register("CONTROL".to_owned(), word(0b10))
+            .unwrap();
+        assert_eq!(read(&mut state, "SP"), 0x2000_0800);
+        assert_eq!(read(&mut state, "MSP"), 0x2000_1000);
+
+        // Handlers use the main stack and return to the process stack.
+        state.enter_exception(15, 0x400).unwrap();
+        assert_eq!(read(&mut state, "LR"), 0xFFFF_FFFD);
+        assert_eq!(read(&mut state, "SP"), 0x2000_1000);
+        assert_eq!(read(&mut state, "PSP"), 0x2000_0800 - 32);
+        assert_eq!(read(&mut state, "CONTROL"), 0);
+        assert_eq!(read(&mut state, "IPSR"), 15);
+        state
+            .set_register("PC".to_owned(), word(0xFFFF_FFFD))
+            .unwrap();
+        state.exception_return().unwrap();
+        assert_eq!(read(&mut state, "SP"), 0x2000_0800);
+        assert_eq!(read(&mut state, "CONTROL"), 0b10);
+        assert_eq!(read(&mut state, "IPSR"), 0);
+
+        // Unprivileged thread mode can not change the interrupt mask.
+        state
+            .set_register("CONTROL".to_owned(), word(0b11))
+            .unwrap();
+        state.set_register("PRIMASK".to_owned(), word(0)).unwrap();
+        assert_eq!(read(&mut state, "PRIMASK"), 1);
+    }
+
+    #[test]
+    fn test_special_instructions() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        executor.state.last_pc = 0x1234;
+
+        let operation = Operation::Special(SpecialInstruction::Bkpt);
+        executor.execute_operation(&operation, &mut local).unwrap();
+        assert!(executor.end_path.is_none());
+
+        let operation = Operation::Special(SpecialInstruction::Udf);
+        executor.execute_operation(&operation, &mut local).unwrap();
+        match executor.end_path.take() {
+            Some(PathResult::Faliure(reason)) => {
+                assert_eq!(reason, "undefined instruction at 0x00001234")
+            }
+            _ => panic!("undefined instruction should end the path with a failure"),
+        }
+
+        let operation = Operation::Special(SpecialInstruction::Wfi);
+        executor.execute_operation(&operation, &mut local).unwrap();
+        assert!(matches!(executor.end_path, Some(PathResult::Success(None))));
+        assert!(executor.sleeping);
+    }
+
+    #[test]
+    fn test_semihosting() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let mut local = HashMap::new();
+        let ctx = executor.state.ctx;
+        let word = |value: u64| ctx.from_u64(value, 32);
+
+        for (i, byte) in b"hello\0".iter().enumerate() {
+            executor
+                .set_memory(ctx.from_u64(*byte as u64, 8), 0x2000_0000 + i as u64, 8)
+                .unwrap();
+        }
+        executor
+            .state
+            .set_register("R0".to_owned(), word(0x04))
+            .unwrap();
+        executor
+            .state
+            .set_register("R1".to_owned(), word(0x2000_0000))
+            .unwrap();
+        executor
+            .execute_operation(&Operation::Semihosting, &mut local)
+            .unwrap();
+        assert_eq!(executor.state.output, "hello");
+        assert!(executor.end_path.is_none());
+
+        // SYS_EXIT_EXTENDED with a application exit and status 3.
+        executor.set_memory(word(0x20026), 0x2000_0100, 32).unwrap();
+        executor.set_memory(word(3), 0x2000_0104, 32).unwrap();
+        executor
+            .state
+            .set_register("R0".to_owned(), word(0x20))
+            .unwrap();
+        executor
+            .state
+            .set_register("R1".to_owned(), word(0x2000_0100))
+            .unwrap();
+        executor
+            .execute_operation(&Operation::Semihosting, &mut local)
+            .unwrap();
+        assert_eq!(executor.state.exit_status, Some(3));
+        assert!(matches!(executor.end_path, Some(PathResult::Faliure(_))));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut project = test_project();
+        project.set_config(Config {
+            max_iter_count: 1000,
+            ..Config::default()
+        });
+        let mut vm = setup_test_vm_with_project(project);
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let jump = |operations: Vec<Operation>| Instruction {
+            instruction_size: 32,
+            operations,
+            max_cycle: CycleCount::Value(1),
+        };
+        let pc = || Operand::Register("PC".to_owned());
+        let lr = || Operand::Register("LR".to_owned());
+
+        // a branch with link to 0x100 is a call
+        let call = jump(vec![
+            Operation::Or {
+                destination: lr(),
+                operand1: pc(),
+                operand2: Operand::Immidiate(DataWord::Word32(1)),
+            },
+            Operation::Move {
+                destination: pc(),
+                source: Operand::Immidiate(DataWord::Word32(0x100)),
+            },
+        ]);
+        executor.execute_instruction(&call).unwrap();
+        assert_eq!(executor.state.call_stack, vec![4]);
+
+        // a branch to the return address is a return
+        let ret = jump(vec![Operation::Move {
+            destination: pc(),
+            source: lr(),
+        }]);
+        executor.execute_instruction(&ret).unwrap();
+        assert!(executor.state.call_stack.is_empty());
+
+        // a loop ends when the instruction has been executed too many times
+        let branch_to_self = jump(vec![Operation::Move {
+            destination: pc(),
+            source: Operand::Immidiate(DataWord::Word32(4)),
+        }]);
+        let max_iter_count = project.get_config().max_iter_count;
+        // the first iteration starts at the thumb return address 0x5
+        for _ in 0..=max_iter_count {
+            executor.execute_instruction(&branch_to_self).unwrap();
+        }
+        assert_eq!(
+            executor.execute_instruction(&branch_to_self),
+            Err(GAError::LimitReached(Limit::IterationCount(
+                4,
+                max_iter_count
+            )))
+        );
+    }
+
+    /// Create a project with the functions `test::main` at 0x0 and `test::inner` at 0x100.
+    fn test_project_with_functions() -> Project {
+        let location = |line| SourceLocation {
+            file: "src/main.rs".to_owned(),
+            line,
+            column: None,
+        };
+        let mut project = test_project();
+        project.add_function_symbol(0x0, 0x10, "_ZN4test4main17h0123456789abcdefE");
+        project.add_function_symbol(0x100, 0x10, "_ZN4test5inner17h0123456789abcdefE");
+        let mut line_table = LineTable::default();
+        line_table.insert(0x0, 0x10, location(10));
+        line_table.insert(0x100, 0x110, location(20));
+        project.set_line_table(line_table);
+        project
+    }
+
+    #[test]
+    fn test_hooked_calls() {
+        let mut project = test_project();
+        project.set_config(Config {
+            max_call_depth: 3,
+            ..Config::default()
+        });
+        let mut vm = setup_test_vm_with_project(project);
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let call = Instruction {
+            instruction_size: 32,
+            operations: vec![
+                Operation::Or {
+                    destination: Operand::Register("LR".to_owned()),
+                    operand1: Operand::Register("PC".to_owned()),
+                    operand2: Operand::Immidiate(DataWord::Word32(1)),
+                },
+                Operation::Move {
+                    destination: Operand::Register("PC".to_owned()),
+                    source: Operand::Immidiate(DataWord::Word32(0x100)),
+                },
+            ],
+            max_cycle: CycleCount::Value(1),
+        };
+        let return_to_caller = |state: &mut GAState| {
+            let lr = state.get_register("LR".to_owned())?;
+            state.set_register("PC".to_owned(), lr)
+        };
+
+        // hooks return without executing a instruction, the calls to the hooked functions are
+        // still popped so calling them in a loop does not exceed the call depth
+        for i in 0..10 {
+            let hook = match i % 2 {
+                0 => PCHook::Continue,
+                _ => PCHook::intrinsic(return_to_caller),
+            };
+            executor.state.hooks.pc.insert(0x100, hook.clone());
+            executor.execute_instruction(&call).unwrap();
+            assert_eq!(executor.state.call_stack.len(), 1);
+            assert!(executor.run_pc_hook(hook).unwrap().is_none());
+            assert!(executor.state.call_stack.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_continue_call() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let call = Instruction {
+            instruction_size: 32,
+            operations: vec![
+                Operation::Or {
+                    destination: Operand::Register("LR".to_owned()),
+                    operand1: Operand::Register("PC".to_owned()),
+                    operand2: Operand::Immidiate(DataWord::Word32(1)),
+                },
+                Operation::Move {
+                    destination: Operand::Register("PC".to_owned()),
+                    source: Operand::Immidiate(DataWord::Word32(0x100)),
+                },
+            ],
+            max_cycle: CycleCount::Value(1),
+        };
+
+        // a path forked at the branch of a call resumes with the branch and makes the call
+        let ctx = executor.state.ctx;
+        executor
+            .state
+            .set_register("PC".to_owned(), ctx.from_u64(4, 32))
+            .unwrap();
+        executor
+            .state
+            .set_register("LR".to_owned(), ctx.from_u64(5, 32))
+            .unwrap();
+        let continuation = ContinueInsideInstruction {
+            instruction: call,
+            index: 1,
+            local: HashMap::new(),
+        };
+        executor
+            .continue_executing_instruction(&continuation)
+            .unwrap();
+        assert_eq!(executor.state.call_stack, vec![4]);
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let mut vm = setup_test_vm_with_project(test_project_with_functions());
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let backtrace = |state: &GAState| -> Vec<(String, Option<String>)> {
+            state
+                .backtrace()
+                .into_iter()
+                .map(|line| (line.function_name, line.line))
+                .collect()
+        };
+
+        // a branch with link from main to inner
+        let call = Instruction {
+            instruction_size: 32,
+            operations: vec![
+                Operation::Or {
+                    destination: Operand::Register("LR".to_owned()),
+                    operand1: Operand::Register("PC".to_owned()),
+                    operand2: Operand::Immidiate(DataWord::Word32(1)),
+                },
+                Operation::Move {
+                    destination: Operand::Register("PC".to_owned()),
+                    source: Operand::Immidiate(DataWord::Word32(0x100)),
+                },
+            ],
+            max_cycle: CycleCount::Value(1),
+        };
+        executor.execute_instruction(&call).unwrap();
+
+        // a path ended by a hook on the called function is in the function, called from the
+        // instruction before the return address
+        executor
+            .state
+            .hooks
+            .pc
+            .insert(0x100, PCHook::EndFaliure("panic".to_owned()));
+        let expected = vec![
+            ("test::inner".to_owned(), Some("src/main.rs:20".to_owned())),
+            ("test::main".to_owned(), Some("src/main.rs:10".to_owned())),
+        ];
+        assert_eq!(backtrace(&executor.state), expected);
+        executor.state.hooks.pc.remove(&0x100);
+
+        // the innermost frame is the last executed instruction
+        let nop = Instruction {
+            instruction_size: 16,
+            operations: vec![],
+            max_cycle: CycleCount::Value(1),
+        };
+        executor.execute_instruction(&nop).unwrap();
+        assert_eq!(backtrace(&executor.state), expected);
+
+        // addresses outside of the functions and the line table have no name or location
+        executor.state.call_stack = vec![0x201];
+        assert_eq!(
+            backtrace(&executor.state),
+            vec![
+                ("test::inner".to_owned(), Some("src/main.rs:20".to_owned())),
+                ("0x200".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_call_and_return() {
+        let mut vm = setup_test_vm_with_project(test_project_with_functions());
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let instruction = |operations: Vec<Operation>, cycles| Instruction {
+            instruction_size: 16,
+            operations,
+            max_cycle: CycleCount::Value(cycles),
+        };
+        let pc = || Operand::Register("PC".to_owned());
+        let lr = || Operand::Register("LR".to_owned());
+
+        // main calls inner, which returns to main
+        let instructions = [
+            instruction(
+                vec![
+                    Operation::Or {
+                        destination: lr(),
+                        operand1: pc(),
+                        operand2: Operand::Immidiate(DataWord::Word32(1)),
+                    },
+                    Operation::Move {
+                        destination: pc(),
+                        source: Operand::Immidiate(DataWord::Word32(0x100)),
+                    },
+                ],
+                2,
+            ),
+            instruction(vec![], 1),
+            instruction(
+                vec![Operation::Move {
+                    destination: pc(),
+                    source: lr(),
+                }],
+                3,
+            ),
+            instruction(vec![], 1),
+        ];
+        for i in instructions {
+            executor.state.increment_cycle_count();
+            executor.execute_instruction(&i).unwrap();
+            executor.finish_instruction(i);
+        }
+        executor.state.increment_cycle_count();
+
+        // the call and the return are charged to the functions they were executed in
+        let profile = &executor.state.profile;
+        let main = profile.get("test::main").unwrap();
+        let inner = profile.get("test::inner").unwrap();
+        assert_eq!(executor.state.cycle_count, 7);
+        assert_eq!(main.inclusive.cycles, executor.state.cycle_count);
+        assert_eq!(main.exclusive.cycles, 3);
+        assert_eq!(inner.inclusive.cycles, 4);
+        assert_eq!(inner.exclusive.cycles, 4);
+    }
+
+    #[test]
+    fn test_symbolic_branch_target() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let ctx = executor.state.ctx;
+        let target = ctx.unconstrained(32, "target");
+        executor
+            .state
+            .set_register("R0".to_owned(), target)
+            .unwrap();
+
+        // a branch to a target with more possible values than the limit ends the path
+        let branch = Instruction {
+            instruction_size: 16,
+            operations: vec![Operation::Move {
+                destination: Operand::Register("PC".to_owned()),
+                source: Operand::Register("R0".to_owned()),
+            }],
+            max_cycle: CycleCount::Value(1),
+        };
+        let max_resolutions = project.get_config().max_fn_ptr_resolutions;
+        assert_eq!(
+            executor.execute_instruction(&branch),
+            Err(GAError::LimitReached(Limit::FnPtrResolutions(
+                max_resolutions
+            )))
+        );
+    }
+
+    #[test]
+    fn test_symbolic_offset_store() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let ctx = executor.state.ctx;
+        let select = ctx.unconstrained(1, "select");
+        let offset = select.ite(&ctx.from_u64(4, 32), &ctx.from_u64(8, 32));
+        executor
+            .state
+            .set_register("R3".to_owned(), offset)
+            .unwrap();
+        let read = |executor: &mut GAExecutor, address: u64| {
+            let address = ctx.from_u64(address, 32);
+            let value = executor.state.read_word_from_memory(&address).unwrap();
+            value.get_constant()
+        };
+
+        // a store to a symbolic address as the last operation of the instruction
+        let store = Instruction {
+            instruction_size: 16,
+            operations: vec![Operation::Move {
+                destination: Operand::AddressWithOffset {
+                    address: DataWord::Word32(0x2000_0000),
+                    offset_reg: "R3".to_owned(),
+                    width: 32,
+                },
+                source: Operand::Immidiate(DataWord::Word32(42)),
+            }],
+            max_cycle: CycleCount::Value(1),
+        };
+        executor.execute_instruction(&store).unwrap();
+        let (stored, other) = match read(&mut executor, 0x2000_0004) {
+            Some(42) => (0x2000_0004, 0x2000_0008),
+            _ => (0x2000_0008, 0x2000_0004),
+        };
+        assert_eq!(read(&mut executor, stored), Some(42));
+        assert!(executor.state.continue_in_instruction.is_none());
+
+        // the forked path makes the store to the other address
+        let forked = executor.vm.paths.get_path().unwrap();
+        let mut executor = GAExecutor::from_state(forked.state, executor.vm, project);
+        for constraint in forked.constraints {
+            executor.state.constraints.assert(&constraint);
+        }
+        let continuation = executor.state.continue_in_instruction.take().unwrap();
+        executor
+            .continue_executing_instruction(&continuation)
+            .unwrap();
+        assert_eq!(read(&mut executor, other), Some(42));
+    }
+
+    #[test]
+    fn test_symbolic_function_pointer() {
+        let mut cfg = RunConfig {
+            show_path_results: false,
+            pc_hooks: vec![],
+            register_read_hooks: vec![],
+            register_write_hooks: vec![],
+            memory_write_hooks: vec![],
+            memory_read_hooks: vec![],
+            exception_handler: None,
+            interrupts: vec![],
+            special_instructions: vec![],
+            clock_frequency: None,
+            config: Config::default(),
+            statics: Statics::default(),
+            memory_map: MemoryMap::default(),
+            peripherals: vec![],
+            observers: vec![],
+        };
+        Armv7MInstruction::add_hooks(&mut cfg);
+        let mut project = test_project();
+        project.set_config(Config {
+            max_fn_ptr_resolutions: 2,
+            ..Config::default()
+        });
+        let mut vm = setup_test_vm_with_project(project);
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let hooks = &mut executor.state.hooks;
+        hooks.register_read.extend(cfg.register_read_hooks);
+        hooks.register_write.extend(cfg.register_write_hooks);
+
+        // a function pointer to one of two Thumb functions
+        let ctx = executor.state.ctx;
+        let select = ctx.unconstrained(1, "select");
+        let pointer = select.ite(&ctx.from_u64(0x101, 32), &ctx.from_u64(0x201, 32));
+        executor
+            .state
+            .set_register("R2".to_owned(), pointer)
+            .unwrap();
+        let get = |executor: &mut GAExecutor, register: &str| {
+            let value = executor.state.get_register(register.to_owned()).unwrap();
+            value.get_constant().unwrap()
+        };
+
+        // blx r2
+        let blx = Armv7MInstruction::new(
+            decoder::parse(&0x4790u16.to_le_bytes()).unwrap(),
+            ArmMCore::ArmM4,
+        )
+        .unwrap()
+        .translate();
+        executor.execute_instruction(&blx).unwrap();
+        let target = get(&mut executor, "PC");
+        assert!(target == 0x100 || target == 0x200);
+        assert_eq!(get(&mut executor, "LR"), 0x3);
+        assert_eq!(executor.state.call_stack, vec![2]);
+
+        // the other target is explored by a forked path that writes to PC again
+        let forked = executor.vm.paths.get_path().unwrap();
+        let mut executor = GAExecutor::from_state(forked.state, executor.vm, project);
+        for constraint in forked.constraints {
+            executor.state.constraints.assert(&constraint);
+        }
+        let continuation = executor.state.continue_in_instruction.take().unwrap();
+        executor
+            .continue_executing_instruction(&continuation)
+            .unwrap();
+        assert_eq!(get(&mut executor, "PC"), 0x300 - target);
+        assert_eq!(executor.state.call_stack, vec![2]);
+    }
+
+    #[test]
+    fn test_marker_hooks() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let ctx = executor.state.ctx;
+        for (offset, byte) in b"io".iter().enumerate() {
+            executor
+                .state
+                .memory
+                .write(
+                    &ctx.from_u64(0x2000_0000 + offset as u64, 32),
+                    ctx.from_u64(*byte as u64, 8),
+                )
+                .unwrap();
+        }
+        let call = Instruction {
+            instruction_size: 32,
+            operations: vec![],
+            max_cycle: CycleCount::Value(3),
+        };
+        let hooks: [fn(&mut GAState) -> Result<()>; 3] = [
+            crate::run_elf::cycle_mark,
+            crate::run_elf::cycle_span_begin,
+            crate::run_elf::cycle_span_end,
+        ];
+
+        // the label is passed as a pointer and a length and the hook returns to the caller
+        executor.state.hooks.pc.insert(0x200, PCHook::EndSuccess);
+        for (i, hook) in hooks.into_iter().enumerate() {
+            executor
+                .state
+                .hooks
+                .pc
+                .insert(0x100, PCHook::intrinsic(hook));
+            let registers = [("R0", 0x2000_0000), ("R1", 2), ("LR", 0x201), ("PC", 0x100)];
+            for (register, value) in registers {
+                executor
+                    .state
+                    .set_register(register.to_owned(), ctx.from_u64(value, 32))
+                    .unwrap();
+            }
+            executor.state.cycle_count = 10 * (i + 1);
+            // the call to the marker function is not counted
+            executor.state.last_instruction = Some(call.clone());
+
+            let result = executor.resume_execution().unwrap();
+            assert!(matches!(result, PathResult::Success(None)));
+            assert_eq!(executor.state.cycle_count, 10 * (i + 1));
+            let pc = executor.state.get_register("PC".to_owned()).unwrap();
+            let lr = executor.state.get_register("LR".to_owned()).unwrap();
+            assert_eq!(pc.get_constant(), lr.get_constant());
+        }
+
+        assert_eq!(
+            executor.state.timeline.events(),
+            &[
+                TimingEvent {
+                    cycle: 10,
+                    kind: TimingEventKind::Instant("io".to_owned())
+                },
+                TimingEvent {
+                    cycle: 20,
+                    kind: TimingEventKind::SpanBegin("io".to_owned())
+                },
+                TimingEvent {
+                    cycle: 30,
+                    kind: TimingEventKind::SpanEnd("io".to_owned())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timing_events() {
+        let mut vm = setup_test_vm();
+        let project = vm.project;
+        let mut executor =
+            GAExecutor::from_state(vm.paths.get_path().unwrap().state, &mut vm, project);
+        let nop = Instruction {
+            instruction_size: 16,
+            operations: vec![],
+            max_cycle: CycleCount::Value(3),
+        };
+
+        // a span begins at the start and ends at the end of the instruction executing the hook
+        executor.state.cycle_count = 10;
+        executor.state.current_instruction = Some(nop.clone());
+        executor.state.span_begin("lock");
+        executor.state.span_end("lock");
+        executor.finish_instruction(nop);
+
+        // between instructions the current cycle count is used
+        executor.state.span_end("lock");
+
+        let cycles: Vec<usize> = executor
+            .state
+            .timeline
+            .events()
+            .iter()
+            .map(|event| event.cycle)
+            .collect();
+        assert_eq!(cycles, vec![10, 13, 10]);
+        let spans = executor.state.timeline.spans(executor.state.cycle_count);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].cycles(), 3);
+
+        // labels of timing markers are read from memory, symbolic bytes are read as ?
+        let ctx = executor.state.ctx;
+        for (address, byte) in [(0x2000_0000, b'o'), (0x2000_0001, b'k')] {
+            executor
+                .state
+                .memory
+                .write(&ctx.from_u64(address, 32), ctx.from_u64(byte as u64, 8))
+                .unwrap();
+        }
+        executor
+            .state
+            .memory
+            .write(&ctx.from_u64(0x2000_0002, 32), ctx.unconstrained(8, "byte"))
+            .unwrap();
+        assert_eq!(
+            executor.state.read_string(0x2000_0000, 3).unwrap(),
+            "ok?".to_owned()
+        );
+    }
 }
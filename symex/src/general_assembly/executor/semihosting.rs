@@ -0,0 +1,165 @@
+//! Arm semihosting, where the target requests services from the host with `BKPT 0xAB`.
+//!
+//! The operation number is passed in R0 and its parameter in R1, the result is returned in R0.
+//! Symbolic arguments are concretized to a single value.
+
+use tracing::debug;
+
+use super::{GAExecutor, PathResult};
+use crate::{general_assembly::Result, smt::DExpr};
+
+const SYS_OPEN: u64 = 0x01;
+const SYS_CLOSE: u64 = 0x02;
+const SYS_WRITEC: u64 = 0x03;
+const SYS_WRITE0: u64 = 0x04;
+const SYS_WRITE: u64 = 0x05;
+const SYS_CLOCK: u64 = 0x10;
+const SYS_EXIT: u64 = 0x18;
+const SYS_EXIT_EXTENDED: u64 = 0x20;
+
+/// Reason code for a normal exit of the application.
+const ADP_STOPPED_APPLICATION_EXIT: u64 = 0x20026;
+
+/// Handles used for the standard streams opened with the special file name ":tt".
+const STDIN: u64 = 1;
+const STDOUT: u64 = 2;
+const STDERR: u64 = 3;
+
+/// Upper limit of the length of a null terminated string.
+const MAX_STRING_LENGTH: u64 = 4096;
+
+impl<'vm> GAExecutor<'vm> {
+    /// Carry out the semihosting operation requested in R0.
+    pub(super) fn semihosting(&mut self) -> Result<()> {
+        let operation = self.semihosting_register("R0")?;
+        let parameter = self.semihosting_register("R1")?;
+        debug!(
+            "Semihosting operation {:#x} with parameter {:#x}",
+            operation, parameter
+        );
+
+        let result = match operation {
+            SYS_OPEN => {
+                let name = self.semihosting_word(parameter)?;
+                let mode = self.semihosting_word(parameter + 4)?;
+                let length = self.semihosting_word(parameter + 8)?;
+                let name = self.semihosting_string(name, Some(length))?;
+                match (name.as_str(), mode) {
+                    (":tt", 0..=3) => STDIN,
+                    (":tt", 4..=7) => STDOUT,
+                    (":tt", 8..=11) => STDERR,
+                    _ => u32::MAX as u64,
+                }
+            }
+            SYS_CLOSE => 0,
+            SYS_WRITEC => {
+                let character = self.semihosting_byte(parameter)?;
+                self.state.output.push(character as char);
+                // R0 is corrupted by the call, leave it unchanged.
+                operation
+            }
+            SYS_WRITE0 => {
+                let string = self.semihosting_string(parameter, None)?;
+                self.state.output.push_str(&string);
+                operation
+            }
+            SYS_WRITE => {
+                let handle = self.semihosting_word(parameter)?;
+                let data = self.semihosting_word(parameter + 4)?;
+                let length = self.semihosting_word(parameter + 8)?;
+                if handle == STDOUT || handle == STDERR {
+                    let string = self.semihosting_string(data, Some(length))?;
+                    self.state.output.push_str(&string);
+                    0
+                } else {
+                    // Nothing was written.
+                    length
+                }
+            }
+            SYS_CLOCK => match self.project.get_clock_frequency() {
+                Some(frequency) => (self.state.cycle_count as u64 * 100) / frequency,
+                None => u32::MAX as u64,
+            },
+            SYS_EXIT => {
+                // The 32 bit version can only report if the application exited normally.
+                let status = match parameter {
+                    ADP_STOPPED_APPLICATION_EXIT => 0,
+                    _ => 1,
+                };
+                self.semihosting_exit(status);
+                operation
+            }
+            SYS_EXIT_EXTENDED => {
+                let reason = self.semihosting_word(parameter)?;
+                let subcode = self.semihosting_word(parameter + 4)?;
+                let status = match reason {
+                    ADP_STOPPED_APPLICATION_EXIT => subcode,
+                    _ => 1,
+                };
+                self.semihosting_exit(status);
+                operation
+            }
+            _ => {
+                self.end_path = Some(PathResult::Faliure(format!(
+                    "unsupported semihosting operation {:#x} at {:#010x}",
+                    operation, self.state.last_pc
+                )));
+                operation
+            }
+        };
+
+        let word_size = self.project.get_word_size();
+        let result = self.state.ctx.from_u64(result, word_size);
+        self.state.set_register("R0".to_owned(), result)
+    }
+
+    /// End the path with the exit status, a non zero status is a failure.
+    fn semihosting_exit(&mut self, status: u64) {
+        debug!("Semihosting exit with status {}", status);
+        self.state.exit_status = Some(status);
+        self.end_path = Some(match status {
+            0 => PathResult::Success(None),
+            _ => PathResult::Faliure(format!("semihosting exit with status {}", status)),
+        });
+    }
+
+    fn semihosting_register(&mut self, register: &str) -> Result<u64> {
+        let value = self.state.get_register(register.to_owned())?;
+        self.concretize(value)
+    }
+
+    fn semihosting_word(&mut self, address: u64) -> Result<u64> {
+        let value = self.get_memory(address, self.project.get_word_size())?;
+        self.concretize(value)
+    }
+
+    fn semihosting_byte(&mut self, address: u64) -> Result<u8> {
+        let value = self.get_memory(address, 8)?;
+        Ok(self.concretize(value)? as u8)
+    }
+
+    /// Read a string of `length` bytes, or until a null byte if no length is given.
+    fn semihosting_string(&mut self, address: u64, length: Option<u64>) -> Result<String> {
+        let mut bytes = vec![];
+        for offset in 0..length.unwrap_or(MAX_STRING_LENGTH) {
+            let byte = self.semihosting_byte(address + offset)?;
+            if length.is_none() && byte == 0 {
+                break;
+            }
+            bytes.push(byte);
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Get a concrete value for `value` and constrain the path to it.
+    fn concretize(&mut self, value: DExpr) -> Result<u64> {
+        match value.get_constant() {
+            Some(constant) => Ok(constant),
+            None => {
+                let constant = self.state.constraints.get_value(&value)?;
+                self.state.constraints.assert(&value._eq(&constant));
+                Ok(constant.get_constant().unwrap())
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Describes a general assembly instruction.
 
-use super::{state::GAState, DataWord};
+use super::{state::GAState, DataWord, InstructionBehaviour};
 
 /// Representing a cycle count for a instruction.
 #[derive(Debug, Clone)]
@@ -73,6 +73,35 @@ pub enum Operation {
         operand2: Operand,
     },
 
+    /// Multiply with a result twice the word size
+    /// destination_high:destination_low = operand1 * operand2
+    /// If `accumulate` is set the previous value in destination_high:destination_low
+    /// is added to the product.
+    MulLong {
+        destination_high: Operand,
+        destination_low: Operand,
+        operand1: Operand,
+        operand2: Operand,
+        signed: bool,
+        accumulate: bool,
+    },
+
+    /// Unsigned division, the result is zero if operand2 is zero.
+    /// destination = operand1 / operand2
+    UDiv {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Signed division rounding towards zero, the result is zero if operand2 is zero.
+    /// destination = operand1 / operand2
+    SDiv {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
     /// And
     /// destination = operand1 & operand2
     And {
@@ -137,6 +166,105 @@ pub enum Operation {
         shift: Operand,
     },
 
+    /// Count the number of leading zero bits in `operand`.
+    CountLeadingZeroes {
+        destination: Operand,
+        operand: Operand,
+    },
+
+    /// Reverse the bit order of `operand`.
+    ReverseBits {
+        destination: Operand,
+        operand: Operand,
+    },
+
+    /// Saturate `operand` to a `bits` wide signed or unsigned value.
+    /// Sets the Q flag if the value was saturated.
+    Saturate {
+        destination: Operand,
+        operand: Operand,
+        bits: u32,
+        signed: bool,
+    },
+
+    /// Saturating add
+    /// destination = operand1 + operand2 clamped to the range of a word.
+    /// Sets the Q flag if the value was saturated.
+    SaturatingAdd {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+        signed: bool,
+    },
+
+    /// Saturating subtract
+    /// destination = operand1 - operand2 clamped to the range of a word.
+    /// Sets the Q flag if the value was saturated.
+    SaturatingSub {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+        signed: bool,
+    },
+
+    /// Single precision floating point addition
+    /// destination = operand1 + operand2
+    FAdd {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Single precision floating point subtraction
+    /// destination = operand1 - operand2
+    FSub {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Single precision floating point multiplication
+    /// destination = operand1 * operand2
+    FMul {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Single precision floating point division
+    /// destination = operand1 / operand2
+    FDiv {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Compare two single precision values and store the result as the N, Z, C and V
+    /// flags in bit 31 to 28 of destination, the same layout as in the ARM FPSCR.
+    /// Less than gives `1000`, equal `0110`, greater than `0010` and unordered `0011`.
+    FCompare {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+    },
+
+    /// Convert a single precision value to a signed or unsigned word.
+    /// Values out of range saturate and NaN gives zero. Rounds towards zero if
+    /// `round_to_zero` is set otherwise to nearest.
+    FloatToInt {
+        destination: Operand,
+        operand: Operand,
+        signed: bool,
+        round_to_zero: bool,
+    },
+
+    /// Convert a signed or unsigned word to a single precision value rounding to nearest.
+    IntToFloat {
+        destination: Operand,
+        operand: Operand,
+        signed: bool,
+    },
+
     /// Zero extend
     /// Zero extends `bits` bits from operand and stores it in destination..
     ZeroExtend {
@@ -158,6 +286,33 @@ pub enum Operation {
         condition: Condition,
     },
 
+    /// Jump to destination if the comparison between operand1 and operand2 holds.
+    /// Does not read or update any flags.
+    CompareAndJump {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+        comparison: Comparison,
+    },
+
+    /// Set destination to one if the comparison between operand1 and operand2 holds
+    /// otherwise zero. Does not read or update any flags.
+    SetOnComparison {
+        destination: Operand,
+        operand1: Operand,
+        operand2: Operand,
+        comparison: Comparison,
+    },
+
+    /// Make the following instructions conditional, the first condition applies
+    /// to the next instruction, the second to the one after that and so on.
+    /// An instruction whose condition does not hold is skipped.
+    ConditionalExecution { conditions: Vec<Condition> },
+
+    /// Only execute `operations` if the current instruction is not part of a
+    /// conditional execution block.
+    IfNotInConditionalBlock { operations: Vec<Operation> },
+
     /// Set the negative flag
     SetNFlag(Operand),
 
@@ -181,8 +336,8 @@ pub enum Operation {
     /// Set the carry flag based on a right shift arithemtic
     SetCFlagSra { operand: Operand, shift: Operand },
 
-    /// Set the carry flag based on a bit rotation
-    SetCFlagRor(Operand),
+    /// Set the carry flag based on a bit rotation, `result` is the rotated value
+    SetCFlagRor { result: Operand, shift: Operand },
 
     /// Set overfolow flag
     SetVFlag {
@@ -192,12 +347,69 @@ pub enum Operation {
         carry: bool,
     },
 
+    /// Set the flag to the least significant bit of operand.
+    SetFlag { flag: String, operand: Operand },
+
     /// Do all the operations in operations for each operand.
     /// The current operand is stored in local as CurrentOperand.
     ForEach {
         operands: Vec<Operand>,
         operations: Vec<Operation>,
     },
+
+    /// A instruction where the behaviour is configured in the run configuration.
+    Special(SpecialInstruction),
+
+    /// A Arm semihosting call with the operation in R0 and the parameter in R1.
+    Semihosting,
+}
+
+/// Instructions where the behaviour is configured by the user, see
+/// [`InstructionBehaviour`](super::InstructionBehaviour).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SpecialInstruction {
+    /// Wait for interrupt.
+    Wfi,
+
+    /// Wait for event.
+    Wfe,
+
+    /// Send event.
+    Sev,
+
+    /// Yield hint.
+    Yield,
+
+    /// Permanently undefined instruction.
+    Udf,
+
+    /// Breakpoint.
+    Bkpt,
+}
+
+impl SpecialInstruction {
+    /// Description of the instruction used in failure messages.
+    pub fn description(&self) -> &'static str {
+        match self {
+            SpecialInstruction::Wfi => "wait for interrupt",
+            SpecialInstruction::Wfe => "wait for event",
+            SpecialInstruction::Sev => "send event",
+            SpecialInstruction::Yield => "yield",
+            SpecialInstruction::Udf => "undefined instruction",
+            SpecialInstruction::Bkpt => "breakpoint",
+        }
+    }
+
+    /// The behaviour used if none is configured.
+    pub fn default_behaviour(&self) -> InstructionBehaviour {
+        match self {
+            SpecialInstruction::Wfi | SpecialInstruction::Wfe => InstructionBehaviour::Sleep,
+            SpecialInstruction::Sev | SpecialInstruction::Yield | SpecialInstruction::Bkpt => {
+                InstructionBehaviour::Nop
+            }
+            SpecialInstruction::Udf => InstructionBehaviour::EndFaliure,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -248,6 +460,28 @@ pub enum Condition {
     None,
 }
 
+/// A comparison between two values that does not use the flags.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    /// Equal
+    EQ,
+
+    /// Not equal
+    NE,
+
+    /// Signed lower than
+    LT,
+
+    /// Signed greater than or equal
+    GE,
+
+    /// Unsigned lower than
+    LTU,
+
+    /// Unsigned greater than or equal
+    GEU,
+}
+
 /// A operand representing some value.
 #[derive(Debug, Clone)]
 pub enum Operand {
@@ -266,8 +500,8 @@ pub enum Operand {
     Address(DataWord, u32),
 
     /// Representing the value stored in memory
-    /// at the address stored in a register offset
-    /// by an constant value.
+    /// at a constant address offset by the value
+    /// stored in a register.
     AddressWithOffset {
         address: DataWord,
         offset_reg: String,
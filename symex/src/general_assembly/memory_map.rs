@@ -0,0 +1,342 @@
+//! Description of the memory map of the target.
+//!
+//! The memory map lists the regions of the address space together with the accesses they
+//! permit and the number of wait states a load or store to them takes. A access to a address
+//! outside all regions or a access the region does not permit ends the path with a bus fault.
+//!
+//! A empty memory map permits all accesses without any wait states.
+
+use std::fmt;
+
+/// A kind of memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+            Access::Execute => write!(f, "execute"),
+        }
+    }
+}
+
+/// The accesses permitted in a memory region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Permissions {
+    /// Read only data.
+    pub const R: Permissions = Permissions {
+        read: true,
+        write: false,
+        execute: false,
+    };
+
+    /// Read only memory that code can execute from, such as flash.
+    pub const RX: Permissions = Permissions {
+        read: true,
+        write: false,
+        execute: true,
+    };
+
+    /// Read and write memory that code can not execute from, such as peripherals.
+    pub const RW: Permissions = Permissions {
+        read: true,
+        write: true,
+        execute: false,
+    };
+
+    /// Memory that permits all accesses, such as RAM.
+    pub const RWX: Permissions = Permissions {
+        read: true,
+        write: true,
+        execute: true,
+    };
+
+    /// Check if `access` is permitted.
+    pub fn permits(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Execute => self.execute,
+        }
+    }
+}
+
+/// A region of the address space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Name of the region, used when reporting bus faults.
+    pub name: String,
+
+    /// First address of the region.
+    pub start: u64,
+
+    /// Size of the region in bytes.
+    pub size: u64,
+
+    /// Accesses permitted in the region.
+    pub permissions: Permissions,
+
+    /// Number of wait states added to a load from the region.
+    pub load_wait_states: usize,
+
+    /// Number of wait states added to a store to the region.
+    pub store_wait_states: usize,
+
+    /// The region contains memory mapped peripherals.
+    pub peripheral: bool,
+
+    /// The region is accessed through the single cycle I/O port of the Cortex-M0+, loads and
+    /// stores to it complete in one cycle.
+    pub io_port: bool,
+}
+
+impl MemoryRegion {
+    /// Create a region with no wait states.
+    pub fn new(name: &str, start: u64, size: u64, permissions: Permissions) -> Self {
+        Self {
+            name: name.to_owned(),
+            start,
+            size,
+            permissions,
+            load_wait_states: 0,
+            store_wait_states: 0,
+            peripheral: false,
+            io_port: false,
+        }
+    }
+
+    /// Set the number of wait states for loads and stores.
+    pub fn wait_states(mut self, load: usize, store: usize) -> Self {
+        self.load_wait_states = load;
+        self.store_wait_states = store;
+        self
+    }
+
+    /// Mark the region as containing memory mapped peripherals.
+    pub fn peripheral(mut self) -> Self {
+        self.peripheral = true;
+        self
+    }
+
+    /// Mark the region as accessed through the single cycle I/O port.
+    pub fn io_port(mut self) -> Self {
+        self.io_port = true;
+        self
+    }
+
+    /// Check if `address` is inside the region.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.start && address - self.start < self.size
+    }
+
+    /// Get the number of wait states for `access`.
+    pub fn wait_states_for(&self, access: Access) -> usize {
+        match access {
+            Access::Read | Access::Execute => self.load_wait_states,
+            Access::Write => self.store_wait_states,
+        }
+    }
+}
+
+/// A access that the memory map does not permit.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BusFault {
+    #[error("bus fault on {access} of unmapped address {address:#010x}")]
+    Unmapped { address: u64, access: Access },
+
+    #[error("bus fault on {access} of address {address:#010x}, not permitted in {region}")]
+    NotPermitted {
+        address: u64,
+        access: Access,
+        region: String,
+    },
+}
+
+/// The memory map of the target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+    regions: Vec<MemoryRegion>,
+}
+
+impl MemoryMap {
+    /// Create a empty memory map that permits all accesses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a region to the memory map. Regions added first take precedence if they overlap.
+    pub fn region(mut self, region: MemoryRegion) -> Self {
+        self.regions.push(region);
+        self
+    }
+
+    /// Default memory map of Cortex-M0+ cores, it permits all accesses without wait states but
+    /// accesses to `0xd0000000..0xe0000000` go through the single cycle I/O port as on the
+    /// RP2040.
+    pub fn cortex_m0_plus() -> Self {
+        Self::new()
+            .region(
+                MemoryRegion::new("SIO", 0xd000_0000, 0x1000_0000, Permissions::RW)
+                    .peripheral()
+                    .io_port(),
+            )
+            .region(MemoryRegion::new(
+                "Memory",
+                0x0000_0000,
+                0x1_0000_0000,
+                Permissions::RWX,
+            ))
+    }
+
+    /// Memory map of the RP2040, the single cycle I/O block (SIO) is accessed through the I/O
+    /// port of the cores.
+    pub fn rp2040() -> Self {
+        Self::new()
+            .region(MemoryRegion::new(
+                "ROM",
+                0x0000_0000,
+                0x4000,
+                Permissions::RX,
+            ))
+            .region(MemoryRegion::new(
+                "XIP",
+                0x1000_0000,
+                0x0100_0000,
+                Permissions::RX,
+            ))
+            .region(MemoryRegion::new(
+                "SRAM",
+                0x2000_0000,
+                0x0004_2000,
+                Permissions::RWX,
+            ))
+            .region(
+                MemoryRegion::new("APB", 0x4000_0000, 0x1000_0000, Permissions::RW).peripheral(),
+            )
+            .region(
+                MemoryRegion::new("AHB-Lite", 0x5000_0000, 0x1000_0000, Permissions::RW)
+                    .peripheral(),
+            )
+            .region(
+                MemoryRegion::new("SIO", 0xd000_0000, 0x1000_0000, Permissions::RW)
+                    .peripheral()
+                    .io_port(),
+            )
+            .region(
+                MemoryRegion::new("PPB", 0xe000_0000, 0x1000_0000, Permissions::RW).peripheral(),
+            )
+    }
+
+    /// Get the regions of the memory map.
+    pub fn regions(&self) -> &[MemoryRegion] {
+        &self.regions
+    }
+
+    /// Get the region containing `address`.
+    pub fn get_region(&self, address: u64) -> Option<&MemoryRegion> {
+        self.regions.iter().find(|region| region.contains(address))
+    }
+
+    /// Check that `access` to `address` is permitted.
+    pub fn check_access(&self, address: u64, access: Access) -> Result<(), BusFault> {
+        if self.regions.is_empty() {
+            return Ok(());
+        }
+
+        match self.get_region(address) {
+            Some(region) if region.permissions.permits(access) => Ok(()),
+            Some(region) => Err(BusFault::NotPermitted {
+                address,
+                access,
+                region: region.name.to_owned(),
+            }),
+            None => Err(BusFault::Unmapped { address, access }),
+        }
+    }
+
+    /// Get the number of wait states for `access` to `address`, or the largest number of wait
+    /// states of any region if the address is not known.
+    pub fn wait_states(&self, address: Option<u64>, access: Access) -> usize {
+        match address {
+            Some(address) => self
+                .get_region(address)
+                .map_or(0, |region| region.wait_states_for(access)),
+            None => self
+                .regions
+                .iter()
+                .map(|region| region.wait_states_for(access))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Check if `address` is accessed through the single cycle I/O port. A unknown address is
+    /// assumed not to be.
+    pub fn is_io_port(&self, address: Option<u64>) -> bool {
+        address
+            .and_then(|address| self.get_region(address))
+            .is_some_and(|region| region.io_port)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_access() {
+        let map = MemoryMap::new()
+            .region(MemoryRegion::new("FLASH", 0x0, 0x1000, Permissions::RX).wait_states(2, 0))
+            .region(MemoryRegion::new(
+                "RAM",
+                0x2000_0000,
+                0x1000,
+                Permissions::RWX,
+            ));
+
+        assert_eq!(map.check_access(0x10, Access::Execute), Ok(()));
+        assert_eq!(map.check_access(0x2000_0ffc, Access::Write), Ok(()));
+        assert_eq!(
+            map.check_access(0x10, Access::Write),
+            Err(BusFault::NotPermitted {
+                address: 0x10,
+                access: Access::Write,
+                region: "FLASH".to_owned()
+            })
+        );
+        assert_eq!(
+            map.check_access(0x2000_1000, Access::Read),
+            Err(BusFault::Unmapped {
+                address: 0x2000_1000,
+                access: Access::Read
+            })
+        );
+
+        assert_eq!(map.wait_states(Some(0x10), Access::Read), 2);
+        assert_eq!(map.wait_states(Some(0x2000_0000), Access::Read), 0);
+        assert_eq!(map.wait_states(None, Access::Read), 2);
+
+        // a empty memory map permits everything
+        assert_eq!(MemoryMap::new().check_access(0x10, Access::Write), Ok(()));
+
+        // the default Cortex-M0+ memory map permits everything but keeps the I/O port
+        let map = MemoryMap::cortex_m0_plus();
+        assert_eq!(map.check_access(0x0800_0000, Access::Execute), Ok(()));
+        assert_eq!(map.check_access(0xffff_fffc, Access::Write), Ok(()));
+        assert!(map.is_io_port(Some(0xd000_0014)));
+        assert!(!map.is_io_port(Some(0x4000_0000)));
+        assert_eq!(map.wait_states(None, Access::Read), 0);
+    }
+}
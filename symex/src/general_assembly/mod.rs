@@ -1,13 +1,18 @@
 use crate::{memory::MemoryError, smt::SolverError};
 
-use self::project::ProjectError;
+use self::{memory_map::BusFault, project::ProjectError};
 
 pub mod executor;
 pub mod instruction;
+pub mod memory_map;
+pub mod observer;
 pub mod path_selection;
+pub mod profile;
 pub mod project;
 pub mod run_config;
 pub mod state;
+pub mod svd;
+pub mod timing;
 pub mod translator;
 pub mod translators;
 pub mod vm;
@@ -32,6 +37,12 @@ pub enum GAError {
 
     #[error("Solver error.")]
     SolverError(#[from] SolverError),
+
+    #[error("Limit reached: {0}")]
+    LimitReached(#[from] Limit),
+
+    #[error("{0}")]
+    BusFault(#[from] BusFault),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -97,10 +108,13 @@ pub struct Config {
     /// Maximum call stack depth. Default is `1000`.
     pub max_call_depth: usize,
 
-    /// Maximum iteration count. Default is `1000`.
+    /// Maximum number of times a instruction may be executed on a path. Default is unbounded
+    /// (`usize::MAX`), concrete loops such as delay loops may run any number of times and
+    /// cutting them short would underestimate the cycle count.
     pub max_iter_count: usize,
 
-    /// Maximum amount of concretizations for function pointers. Default is `1`.
+    /// Maximum amount of concretizations for function pointers and other symbolic branch
+    /// targets, a path is forked for each target. Default is `1`.
     pub max_fn_ptr_resolutions: usize,
 
     /// Maximum amount of concretizations for a memory address. This does not apply for e.g.
@@ -111,3 +125,31 @@ pub struct Config {
     /// Default is `100`.
     pub max_intrinsic_concretizations: usize,
 }
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_call_depth: 1000,
+            max_iter_count: usize::MAX,
+            max_fn_ptr_resolutions: 1,
+            max_memory_access_resolutions: 100,
+            max_intrinsic_concretizations: 100,
+        }
+    }
+}
+
+/// A [`Config`] limit that was exceeded on a path.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum Limit {
+    #[error("call depth exceeded the maximum of {0}")]
+    CallDepth(usize),
+
+    #[error("instruction at {0:#010x} executed more than {1} times")]
+    IterationCount(u64, usize),
+
+    #[error("memory address has more than {0} possible values")]
+    MemoryAccessResolutions(usize),
+
+    #[error("branch target has more than {0} possible values")]
+    FnPtrResolutions(usize),
+}
@@ -0,0 +1,78 @@
+//! Observers of the symbolic execution.
+//!
+//! A observer is called by the executor on events such as the start and end of each instruction,
+//! memory and register accesses, forks and the end of the path. Observers can be used for
+//! analyses such as coverage, traces or stack usage without changing the executor.
+//!
+//! Like hooks every path has its own copy of the observers that is cloned when the path forks,
+//! so a observer collects the events of exactly one path. The observers of a finished path are
+//! available in its [`VisualPathResult`](crate::elf_util::VisualPathResult).
+
+use std::{any::Any, fmt};
+
+use crate::smt::DExpr;
+
+use super::{executor::PathResult, instruction::Instruction, state::GAState};
+
+/// Observes the execution of a path. All methods do nothing by default.
+pub trait Observer: ObserverClone {
+    /// Name of the observer, used when displaying its result.
+    fn name(&self) -> String;
+
+    /// Called before the instruction at `pc` is executed.
+    fn instruction_start(&mut self, _state: &GAState, _pc: u64, _instruction: &Instruction) {}
+
+    /// Called after the instruction at `pc` has been executed.
+    fn instruction_end(&mut self, _state: &GAState, _pc: u64, _instruction: &Instruction) {}
+
+    /// Called after `bits` bits were read from memory at `address`.
+    fn memory_read(&mut self, _state: &GAState, _address: u64, _value: &DExpr, _bits: u32) {}
+
+    /// Called after `bits` bits were written to memory at `address`.
+    fn memory_write(&mut self, _state: &GAState, _address: u64, _value: &DExpr, _bits: u32) {}
+
+    /// Called after a register is written, `value` is the value stored in the register. Writes
+    /// through a register write hook are observed as the writes made by the hook.
+    fn register_write(&mut self, _state: &GAState, _register: &str, _value: &DExpr) {}
+
+    /// Called before the path forks, `constraint` is the constraint of the new path.
+    fn fork(&mut self, _state: &GAState, _constraint: &DExpr) {}
+
+    /// Called when the path has ended.
+    fn path_end(&mut self, _state: &GAState, _result: &PathResult) {}
+
+    /// Result of the observer for the path, displayed with the path result.
+    fn result(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Clones a boxed observer and gives access to its concrete type, implemented for all observers
+/// that implement [`Clone`].
+pub trait ObserverClone {
+    fn clone_box(&self) -> Box<dyn Observer>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Observer + Clone + 'static> ObserverClone for T {
+    fn clone_box(&self) -> Box<dyn Observer> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Observer> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl fmt::Debug for dyn Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observer({})", self.name())
+    }
+}
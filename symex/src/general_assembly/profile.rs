@@ -0,0 +1,189 @@
+//! Per function cycle and instruction profile of a path.
+//!
+//! The cycles and instructions of every executed instruction are attributed to the function
+//! containing it (exclusive) and to that function and all functions on the call stack
+//! (inclusive). Recursive calls are only counted once in the inclusive totals.
+
+use std::{collections::HashMap, fmt, ops::AddAssign};
+
+/// Name used for instructions outside all known functions.
+pub const UNKNOWN_FUNCTION: &str = "<unknown>";
+
+/// Cycles and instructions spent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub cycles: usize,
+    pub instructions: usize,
+}
+
+impl Cost {
+    /// The largest cycles and instructions of both costs.
+    pub fn max(self, other: Cost) -> Cost {
+        Cost {
+            cycles: self.cycles.max(other.cycles),
+            instructions: self.instructions.max(other.instructions),
+        }
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, other: Cost) {
+        self.cycles += other.cycles;
+        self.instructions += other.instructions;
+    }
+}
+
+/// Cost of a function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FunctionProfile {
+    /// Cost of the function and all functions it called.
+    pub inclusive: Cost,
+
+    /// Cost of the instructions in the function.
+    pub exclusive: Cost,
+}
+
+/// Cost of each executed function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    functions: HashMap<String, FunctionProfile>,
+}
+
+impl Profile {
+    /// Add the cost of a instruction in `function`, `callers` are the functions on the call
+    /// stack from the innermost to the outermost.
+    pub fn add<'a>(
+        &mut self,
+        function: &'a str,
+        callers: impl IntoIterator<Item = &'a str>,
+        cost: Cost,
+    ) {
+        self.entry(function).exclusive += cost;
+
+        let mut counted: Vec<&str> = vec![];
+        for function in std::iter::once(function).chain(callers) {
+            if !counted.contains(&function) {
+                self.entry(function).inclusive += cost;
+                counted.push(function);
+            }
+        }
+    }
+
+    fn entry(&mut self, function: &str) -> &mut FunctionProfile {
+        if !self.functions.contains_key(function) {
+            self.functions
+                .insert(function.to_owned(), FunctionProfile::default());
+        }
+        self.functions.get_mut(function).unwrap()
+    }
+
+    /// Get the cost of `function`.
+    pub fn get(&self, function: &str) -> Option<&FunctionProfile> {
+        self.functions.get(function)
+    }
+
+    /// Get all functions sorted by decreasing inclusive cycles.
+    pub fn functions(&self) -> Vec<(&str, &FunctionProfile)> {
+        let mut functions: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile))
+            .collect();
+        functions.sort_by(|(a_name, a), (b_name, b)| {
+            b.inclusive
+                .cycles
+                .cmp(&a.inclusive.cycles)
+                .then(a_name.cmp(b_name))
+        });
+        functions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Merge the profiles of several paths into the worst case profile, where every total is the
+    /// largest total of the function in any of the paths.
+    pub fn worst_case<'a>(profiles: impl IntoIterator<Item = &'a Profile>) -> Profile {
+        let mut worst_case = Profile::default();
+        for profile in profiles {
+            for (name, function) in profile.functions.iter() {
+                let worst = worst_case.entry(name);
+                worst.inclusive = worst.inclusive.max(function.inclusive);
+                worst.exclusive = worst.exclusive.max(function.exclusive);
+            }
+        }
+        worst_case
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} {:>12} {:>12} {:>12}  function",
+            "incl cycles", "excl cycles", "incl instr", "excl instr"
+        )?;
+        for (name, function) in self.functions() {
+            writeln!(
+                f,
+                "{:>12} {:>12} {:>12} {:>12}  {}",
+                function.inclusive.cycles,
+                function.exclusive.cycles,
+                function.inclusive.instructions,
+                function.exclusive.instructions,
+                name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cost(cycles: usize) -> Cost {
+        Cost {
+            cycles,
+            instructions: 1,
+        }
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut profile = Profile::default();
+        profile.add("main", [], cost(1));
+        profile.add("f", ["main"], cost(2));
+        // a recursive call is only counted once
+        profile.add("f", ["f", "main"], cost(3));
+
+        let main = profile.get("main").unwrap();
+        assert_eq!(main.exclusive, cost(1));
+        assert_eq!(
+            main.inclusive,
+            Cost {
+                cycles: 6,
+                instructions: 3
+            }
+        );
+        let f = profile.get("f").unwrap();
+        assert_eq!(f.inclusive, f.exclusive);
+        assert_eq!(f.inclusive.cycles, 5);
+        assert_eq!(profile.functions()[0].0, "main");
+
+        let mut other = Profile::default();
+        other.add("f", ["main"], cost(10));
+        let worst_case = Profile::worst_case([&profile, &other]);
+        // every total is the largest of any path, here from different paths
+        assert_eq!(
+            worst_case.get("f").unwrap().exclusive,
+            Cost {
+                cycles: 10,
+                instructions: 2
+            }
+        );
+        assert_eq!(worst_case.get("main").unwrap().inclusive.cycles, 10);
+        assert_eq!(worst_case.get("main").unwrap().exclusive, cost(1));
+    }
+}
@@ -1,24 +1,57 @@
-use std::{collections::HashMap, fmt::Debug, fs};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    fs,
+};
 
 use armv6_m_instruction_parser::parse;
 use gimli::{DebugAbbrev, DebugInfo, DebugStr};
-use object::{Architecture, Object, ObjectSection, ObjectSymbol};
-use tracing::{debug, trace};
-
-use crate::{general_assembly::translator::Translatable, memory::MemoryError, smt::DExpr};
+use object::{
+    elf::EF_ARM_BE8, Architecture, FileFlags, Object, ObjectSection, ObjectSymbol, SymbolKind,
+};
+use regex::Regex;
+use rustc_demangle::demangle;
+use tracing::{debug, trace, warn};
+
+use crate::{
+    general_assembly::{
+        translator::Translatable,
+        translators::{self, armv7_m::Armv7MInstruction, riscv::RiscVInstruction},
+    },
+    memory::MemoryError,
+};
 
-use self::segments::Segments;
+use self::{
+    segments::Segments,
+    target_config::{ArmMCore, CoreFamily, RiscVCore, TargetConfiguration},
+};
 
 use super::{
-    instruction::Instruction, state::GAState, DataHalfWord, DataWord, Endianness, RawDataWord,
-    Result as SuperResult, RunConfig, WordSize,
+    instruction::{Instruction, SpecialInstruction},
+    memory_map::MemoryMap,
+    observer::Observer,
+    state::GAState,
+    svd::PeripheralRegister,
+    Config, DataHalfWord, DataWord, Endianness, InstructionBehaviour, RawDataWord, RunConfig,
+    Statics, WordSize,
 };
 
 mod dwarf_helper;
 use dwarf_helper::*;
 
+mod symtab_helper;
+use symtab_helper::*;
+
 mod segments;
 
+mod hooks;
+pub use hooks::*;
+
+mod line_table;
+pub use line_table::*;
+
+pub mod target_config;
+
 type Result<T> = std::result::Result<T, ProjectError>;
 
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
@@ -31,26 +64,16 @@ pub enum ProjectError {
 
     #[error("Unavalable operation")]
     UnabvalableOperation,
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum PCHook {
-    Continue,
-    EndSuccess,
-    EndFaliure(&'static str),
-    Intrinsic(fn(state: &mut GAState) -> SuperResult<()>),
-    Suppress,
-}
+    #[error("Unable to decode instruction: {0}")]
+    UnableToDecodeInstruction(String),
 
-pub type PCHooks = HashMap<u64, PCHook>;
+    #[error("Unsupported architecture: {0}")]
+    UnsupportedArchitecture(String),
 
-/// Hook for a register read.
-pub type RegisterReadHook = fn(state: &mut GAState) -> SuperResult<DExpr>;
-pub type RegisterReadHooks = HashMap<String, RegisterReadHook>;
-
-/// Hook for a register write.
-pub type RegisterWriteHook = fn(state: &mut GAState, value: DExpr) -> SuperResult<()>;
-pub type RegisterWriteHooks = HashMap<String, RegisterWriteHook>;
+    #[error("Interrupt handler {0} not found")]
+    InterruptHandlerNotFound(String),
+}
 
 #[derive(Debug, Clone)]
 pub enum MemoryHookAddress {
@@ -58,16 +81,14 @@ pub enum MemoryHookAddress {
     Range(u64, u64),
 }
 
-/// Hook for a memory write.
-pub type MemoryWriteHook =
-    fn(state: &mut GAState, address: u64, value: DExpr, bits: u32) -> SuperResult<()>;
-pub type SingleMemoryWriteHooks = HashMap<u64, MemoryWriteHook>;
-pub type RangeMemoryWriteHooks = Vec<((u64, u64), MemoryWriteHook)>;
-
-/// Hook for a memory read.
-pub type MemoryReadHook = fn(state: &mut GAState, address: u64) -> SuperResult<DExpr>;
-pub type SingleMemoryReadHooks = HashMap<u64, MemoryReadHook>;
-pub type RangeMemoryReadHooks = Vec<((u64, u64), MemoryReadHook)>;
+/// A interrupt handler resolved to its address in the ELF file.
+#[derive(Debug, Clone)]
+pub struct InterruptHandler {
+    pub name: String,
+    pub address: u64,
+    pub exception_number: u32,
+    pub priority: u8,
+}
 
 /// Holds all data read from the ELF file.
 // Add all read only memmory here later to handle global constants.
@@ -75,18 +96,25 @@ pub struct Project {
     segments: Segments,
     word_size: WordSize,
     endianness: Endianness,
+    instruction_endianness: Endianness,
     architecture: object::Architecture,
+    target: TargetConfiguration,
     symtab: HashMap<String, u64>,
-    pc_hooks: PCHooks,
-    reg_read_hooks: RegisterReadHooks,
-    reg_write_hooks: RegisterWriteHooks,
-    single_memory_read_hooks: SingleMemoryReadHooks,
-    range_memory_read_hooks: RangeMemoryReadHooks,
-    single_memory_write_hooks: SingleMemoryWriteHooks,
-    range_memory_write_hooks: RangeMemoryWriteHooks,
+    hooks: Hooks,
+    interrupts: Vec<InterruptHandler>,
+    special_instructions: HashMap<SpecialInstruction, InstructionBehaviour>,
+    clock_frequency: Option<u64>,
+    config: Config,
+    statics: Statics,
+    memory_map: MemoryMap,
+    unmatched_pc_hooks: Vec<String>,
+    peripherals: BTreeMap<u64, PeripheralRegister>,
+    observers: Vec<Box<dyn Observer>>,
+    line_table: LineTable,
+    function_symbols: BTreeMap<u64, (u64, String)>,
 }
 
-fn construct_register_read_hooks(hooks: Vec<(String, RegisterReadHook)>) -> RegisterReadHooks {
+fn construct_register_read_hooks(hooks: Vec<(String, Box<dyn RegisterRead>)>) -> RegisterReadHooks {
     let mut ret = HashMap::new();
     for (register, hook) in hooks {
         ret.insert(register, hook);
@@ -94,7 +122,9 @@ fn construct_register_read_hooks(hooks: Vec<(String, RegisterReadHook)>) -> Regi
     ret
 }
 
-fn construct_register_write_hooks(hooks: Vec<(String, RegisterWriteHook)>) -> RegisterWriteHooks {
+fn construct_register_write_hooks(
+    hooks: Vec<(String, Box<dyn RegisterWrite>)>,
+) -> RegisterWriteHooks {
     let mut ret = HashMap::new();
 
     for (register, hook) in hooks {
@@ -104,46 +134,6 @@ fn construct_register_write_hooks(hooks: Vec<(String, RegisterWriteHook)>) -> Re
     ret
 }
 
-fn construct_memory_write(
-    hooks: Vec<(MemoryHookAddress, MemoryWriteHook)>,
-) -> (SingleMemoryWriteHooks, RangeMemoryWriteHooks) {
-    let mut single_hooks = HashMap::new();
-    let mut range_hooks = vec![];
-
-    for (address, hook) in hooks {
-        match address {
-            MemoryHookAddress::Single(addr) => {
-                single_hooks.insert(addr, hook);
-            }
-            MemoryHookAddress::Range(start, end) => {
-                range_hooks.push(((start, end), hook));
-            }
-        }
-    }
-
-    (single_hooks, range_hooks)
-}
-
-fn construct_memory_read_hooks(
-    hooks: Vec<(MemoryHookAddress, MemoryReadHook)>,
-) -> (SingleMemoryReadHooks, RangeMemoryReadHooks) {
-    let mut single_hooks = HashMap::new();
-    let mut range_hooks = vec![];
-
-    for (address, hook) in hooks {
-        match address {
-            MemoryHookAddress::Single(addr) => {
-                single_hooks.insert(addr, hook);
-            }
-            MemoryHookAddress::Range(start, end) => {
-                range_hooks.push(((start, end), hook));
-            }
-        }
-    }
-
-    (single_hooks, range_hooks)
-}
-
 impl Project {
     pub fn manual_project(
         program_memory: Vec<u8>,
@@ -164,16 +154,29 @@ impl Project {
         Project {
             segments: Segments::from_single_segment(program_memory, start_addr, end_addr),
             word_size,
+            instruction_endianness: endianness.clone(),
             endianness,
             architecture,
+            target: TargetConfiguration::default(),
             symtab,
-            pc_hooks,
-            reg_read_hooks,
-            reg_write_hooks,
-            single_memory_read_hooks,
-            range_memory_read_hooks,
-            single_memory_write_hooks,
-            range_memory_write_hooks,
+            hooks: Hooks {
+                pc: pc_hooks,
+                register_read: reg_read_hooks,
+                register_write: reg_write_hooks,
+                memory_read: MemoryHooks::new(single_memory_read_hooks, range_memory_read_hooks),
+                memory_write: MemoryHooks::new(single_memory_write_hooks, range_memory_write_hooks),
+            },
+            interrupts: vec![],
+            special_instructions: HashMap::new(),
+            clock_frequency: None,
+            config: Config::default(),
+            statics: Statics::default(),
+            memory_map: MemoryMap::default(),
+            unmatched_pc_hooks: vec![],
+            peripherals: BTreeMap::new(),
+            observers: vec![],
+            line_table: LineTable::default(),
+            function_symbols: BTreeMap::new(),
         }
     }
 
@@ -188,7 +191,10 @@ impl Project {
             }
         };
 
-        let segments = Segments::from_file(&obj_file);
+        let segments = match Segments::from_file(&obj_file) {
+            Some(segments) => segments,
+            None => return Err(ProjectError::UnableToParseElf(path.to_owned())),
+        };
 
         let endianness = if obj_file.is_little_endian() {
             Endianness::Little
@@ -198,6 +204,14 @@ impl Project {
 
         let architecture = obj_file.architecture();
 
+        // Arm BE8 images and all RISC-V images store instructions little endian.
+        let instruction_endianness = match (architecture, obj_file.flags()) {
+            (Architecture::Arm, FileFlags::Elf { e_flags, .. }) if e_flags & EF_ARM_BE8 == 0 => {
+                endianness.clone()
+            }
+            _ => Endianness::Little,
+        };
+
         // Do not catch 16 or 8 bit architectures but will do for now.
         let word_size = if obj_file.is_64() {
             WordSize::Bit64
@@ -206,14 +220,25 @@ impl Project {
         };
 
         let mut symtab = HashMap::new();
+        let mut functions = vec![];
+        let mut function_symbols = BTreeMap::new();
         for symbol in obj_file.symbols() {
-            symtab.insert(
-                match symbol.name() {
-                    Ok(name) => name.to_owned(),
-                    Err(_) => continue, // ignore entry if name can not be read
-                },
-                symbol.address(),
-            );
+            let name = match symbol.name() {
+                Ok(name) => name.to_owned(),
+                Err(_) => continue, // ignore entry if name can not be read
+            };
+            if symbol.kind() == SymbolKind::Text {
+                // the lowest bit of a Thumb function address is set
+                let address = match architecture {
+                    Architecture::Arm => symbol.address() & !1,
+                    _ => symbol.address(),
+                };
+                functions.push((name.to_owned(), address));
+                function_symbols
+                    .entry(address)
+                    .or_insert((symbol.size(), format!("{:#}", demangle(&name))));
+            }
+            symtab.insert(name, symbol.address());
         }
 
         let gimli_endian = match endianness {
@@ -221,94 +246,281 @@ impl Project {
             Endianness::Big => gimli::RunTimeEndian::Big,
         };
 
-        let debug_info = obj_file.section_by_name(".debug_info").unwrap();
-        let debug_info = DebugInfo::new(debug_info.data().unwrap(), gimli_endian);
+        let section_data = |name| {
+            obj_file
+                .section_by_name(name)
+                .and_then(|section| section.data().ok())
+        };
+        let dwarf = match (
+            section_data(".debug_info"),
+            section_data(".debug_abbrev"),
+            section_data(".debug_str"),
+        ) {
+            (Some(debug_info), Some(debug_abbrev), Some(debug_str)) => Some((
+                DebugInfo::new(debug_info, gimli_endian),
+                DebugAbbrev::new(debug_abbrev, gimli_endian),
+                DebugStr::new(debug_str, gimli_endian),
+            )),
+            _ => {
+                debug!("No dwarf debug data found, using the symbol table.");
+                None
+            }
+        };
 
-        let debug_abbrev = obj_file.section_by_name(".debug_abbrev").unwrap();
-        let debug_abbrev = DebugAbbrev::new(debug_abbrev.data().unwrap(), gimli_endian);
+        let line_table = gimli::Dwarf::load(|id| {
+            Ok::<_, gimli::Error>(gimli::EndianSlice::new(
+                section_data(id.name()).unwrap_or(&[]),
+                gimli_endian,
+            ))
+        })
+        .and_then(|dwarf| LineTable::from_dwarf(&dwarf))
+        .unwrap_or_else(|error| {
+            warn!("Unable to read the dwarf line table: {}", error);
+            LineTable::default()
+        });
+        if line_table.is_empty() {
+            debug!("No dwarf line table found, source locations are not available.");
+        }
 
-        let debug_str = obj_file.section_by_name(".debug_str").unwrap();
-        let debug_str = DebugStr::new(debug_str.data().unwrap(), gimli_endian);
+        let target = match (architecture, obj_file.section_by_name(".ARM.attributes")) {
+            (Architecture::Arm, Some(section)) => TargetConfiguration::from_arm_attributes(
+                section.data().unwrap_or(&[]),
+                endianness.clone(),
+            )
+            .unwrap_or_default(),
+            (Architecture::Arm, None) => TargetConfiguration::default(),
+            (Architecture::Riscv32, _) => {
+                TargetConfiguration::new(CoreFamily::RiscV(RiscVCore::Rv32imc))
+            }
+            _ => {
+                return Err(ProjectError::UnsupportedArchitecture(format!(
+                    "{:?}",
+                    architecture
+                )))
+            }
+        };
+        debug!("Target: {:?}", target);
 
-        match architecture {
-            Architecture::Arm => {
+        match target.core() {
+            CoreFamily::ArmM(ArmMCore::ArmM0 | ArmMCore::ArmM0Plus) => {
                 armv6_m_instruction_parser::instructons::Instruction::add_hooks(cfg)
             }
-            _ => todo!(),
+            CoreFamily::ArmM(ArmMCore::ArmM3 | ArmMCore::ArmM4 | ArmMCore::ArmM23) => {
+                Armv7MInstruction::add_hooks(cfg)
+            }
+            CoreFamily::RiscV(_) => RiscVInstruction::add_hooks(cfg),
         }
-        let pc_hooks = cfg.pc_hooks.clone();
+        let (mut pc_hooks, mut found_hooks, no_address) = match &dwarf {
+            Some((debug_info, debug_abbrev, debug_str)) => {
+                construct_pc_hooks_no_index(&cfg.pc_hooks, debug_info, debug_abbrev, debug_str)
+            }
+            None => (HashMap::new(), HashSet::new(), vec![]),
+        };
 
-        let pc_hooks =
-            construct_pc_hooks_no_index(pc_hooks, &debug_info, &debug_abbrev, &debug_str);
+        // Fall back to the symbol table for hooks that were not found in the dwarf data and for
+        // matched subprograms without a address, their symbols are matched by exact name and
+        // count as a match of the original pattern.
+        let mut symtab_patterns: HashMap<String, String> = HashMap::new();
+        let mut symtab_hooks: Vec<(Regex, PCHook)> = cfg
+            .pc_hooks
+            .iter()
+            .filter(|(name, _)| !found_hooks.contains(name.as_str()))
+            .cloned()
+            .collect();
+        for (subprogram, pattern, hook) in no_address {
+            let name = Regex::new(&format!("^{}$", regex::escape(&subprogram))).unwrap();
+            if symtab_patterns
+                .insert(name.as_str().to_owned(), pattern)
+                .is_none()
+            {
+                symtab_hooks.push((name, hook));
+            }
+        }
+        let (symtab_pc_hooks, symtab_found_hooks) =
+            construct_pc_hooks_from_symtab(&symtab_hooks, &functions);
+        for (address, hook) in symtab_pc_hooks {
+            pc_hooks.entry(address).or_insert(hook);
+        }
+        found_hooks.extend(
+            symtab_found_hooks
+                .into_iter()
+                .map(|name| symtab_patterns.remove(&name).unwrap_or(name)),
+        );
+
+        let mut unmatched_pc_hooks = vec![];
+        for (name, _) in cfg.pc_hooks.iter() {
+            if !found_hooks.contains(name.as_str())
+                && !unmatched_pc_hooks.contains(&name.as_str().to_owned())
+            {
+                warn!("No function matched the PC hook {}", name);
+                unmatched_pc_hooks.push(name.as_str().to_owned());
+            }
+        }
 
         debug!("Created pc hooks: {:?}", pc_hooks);
 
         let reg_read_hooks = construct_register_read_hooks(cfg.register_read_hooks.clone());
         let reg_write_hooks = construct_register_write_hooks(cfg.register_write_hooks.clone());
 
-        let (single_memory_write_hooks, range_memory_write_hooks) =
-            construct_memory_write(cfg.memory_write_hooks.clone());
-        let (single_memory_read_hooks, range_memory_read_hooks) =
-            construct_memory_read_hooks(cfg.memory_read_hooks.clone());
+        let memory_read_hooks = MemoryHooks::from_hooks(cfg.memory_read_hooks.clone());
+        let memory_write_hooks = MemoryHooks::from_hooks(cfg.memory_write_hooks.clone());
+
+        let mut interrupts = vec![];
+        for interrupt in cfg.interrupts.iter() {
+            let address = match symtab.get(&interrupt.handler) {
+                Some(address) => *address,
+                None => {
+                    return Err(ProjectError::InterruptHandlerNotFound(
+                        interrupt.handler.to_owned(),
+                    ))
+                }
+            };
+            interrupts.push(InterruptHandler {
+                name: interrupt.handler.to_owned(),
+                address,
+                exception_number: interrupt.exception_number,
+                priority: interrupt.priority,
+            });
+        }
+        debug!("Interrupts: {:?}", interrupts);
+
+        let special_instructions = cfg.special_instructions.iter().copied().collect();
+
+        // accesses through the I/O port of a Cortex-M0+ are single cycle even without a memory map
+        let memory_map = match (target.core(), cfg.memory_map.regions().is_empty()) {
+            (CoreFamily::ArmM(ArmMCore::ArmM0Plus), true) => MemoryMap::cortex_m0_plus(),
+            _ => cfg.memory_map.clone(),
+        };
 
         Ok(Project {
             segments,
             word_size,
             endianness,
+            instruction_endianness,
             architecture,
+            target,
             symtab,
-            pc_hooks,
-            reg_read_hooks,
-            reg_write_hooks,
-            single_memory_read_hooks,
-            range_memory_read_hooks,
-            single_memory_write_hooks,
-            range_memory_write_hooks,
+            hooks: Hooks {
+                pc: pc_hooks,
+                register_read: reg_read_hooks,
+                register_write: reg_write_hooks,
+                memory_read: memory_read_hooks,
+                memory_write: memory_write_hooks,
+            },
+            interrupts,
+            special_instructions,
+            clock_frequency: cfg.clock_frequency,
+            config: cfg.config.clone(),
+            statics: cfg.statics,
+            memory_map,
+            unmatched_pc_hooks,
+            peripherals: cfg
+                .peripherals
+                .iter()
+                .map(|register| (register.address, register.clone()))
+                .collect(),
+            observers: cfg.observers.clone(),
+            line_table,
+            function_symbols,
         })
     }
 
-    pub fn get_pc_hook(&self, pc: u64) -> Option<PCHook> {
-        self.pc_hooks.get(&pc).copied()
+    /// Get the target the ELF file was built for.
+    pub fn get_target(&self) -> TargetConfiguration {
+        self.target
+    }
+
+    /// Get the hooks that every path starts with.
+    pub fn get_hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// Get the observers that every path starts with.
+    pub fn get_observers(&self) -> &[Box<dyn Observer>] {
+        &self.observers
+    }
+
+    /// Get the source location of the instruction at `address` from the dwarf line table.
+    pub fn get_source_location(&self, address: u64) -> Option<&SourceLocation> {
+        self.line_table.get(address)
+    }
+
+    /// Check if the instruction at `address` is in the user code, see
+    /// [`SourceLocation::is_user_code`].
+    pub fn is_user_code(&self, address: u64) -> bool {
+        self.get_source_location(address)
+            .is_some_and(SourceLocation::is_user_code)
+    }
+
+    /// Get the patterns of the configured PC hooks that did not match any function.
+    pub fn get_unmatched_pc_hooks(&self) -> &[String] {
+        &self.unmatched_pc_hooks
     }
 
     pub fn add_pc_hook(&mut self, pc: u64, hook: PCHook) {
-        self.pc_hooks.insert(pc, hook);
+        self.hooks.pc.insert(pc, hook);
     }
 
-    pub fn get_register_read_hook(&self, register: &str) -> Option<RegisterReadHook> {
-        self.reg_read_hooks.get(register).copied()
+    /// Get the interrupts that may preempt the execution.
+    pub fn get_interrupts(&self) -> &[InterruptHandler] {
+        &self.interrupts
     }
 
-    pub fn get_register_write_hook(&self, register: &str) -> Option<RegisterWriteHook> {
-        self.reg_write_hooks.get(register).copied()
+    pub fn add_interrupt(&mut self, interrupt: InterruptHandler) {
+        self.interrupts.push(interrupt);
     }
 
-    pub fn get_memory_write_hook(&self, address: u64) -> Option<MemoryWriteHook> {
-        match self.single_memory_write_hooks.get(&address) {
-            Some(hook) => Some(hook.clone()),
-            None => {
-                for ((start, end), hook) in &self.range_memory_write_hooks {
-                    if address >= *start && address < *end {
-                        return Some(hook.to_owned());
-                    }
-                }
-                None
-            }
+    /// Get the configured behaviour of a special instruction.
+    pub fn get_instruction_behaviour(
+        &self,
+        instruction: SpecialInstruction,
+    ) -> InstructionBehaviour {
+        match self.special_instructions.get(&instruction) {
+            Some(behaviour) => *behaviour,
+            None => instruction.default_behaviour(),
         }
     }
 
-    pub fn get_memory_read_hook(&self, address: u64) -> Option<MemoryReadHook> {
-        match self.single_memory_read_hooks.get(&address) {
-            Some(hook) => Some(hook.clone()),
-            None => {
-                for ((start, end), hook) in &self.range_memory_read_hooks {
-                    if address >= *start && address < *end {
-                        return Some(hook.to_owned());
-                    }
-                }
-                None
-            }
-        }
+    /// Get the core clock frequency in Hz if it is known.
+    pub fn get_clock_frequency(&self) -> Option<u64> {
+        self.clock_frequency
+    }
+
+    /// Get the limits of the exploration.
+    pub fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Set the exploration limits.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Get the memory map of the target.
+    pub fn get_memory_map(&self) -> &MemoryMap {
+        &self.memory_map
+    }
+
+    /// Get the peripheral register containing `address`.
+    pub fn get_peripheral_register(&self, address: u64) -> Option<&PeripheralRegister> {
+        self.peripherals
+            .range(..=address)
+            .next_back()
+            .map(|(_, register)| register)
+            .filter(|register| register.contains(address))
+    }
+
+    /// Get how the RAM resident statics are initialised.
+    pub fn get_statics(&self) -> Statics {
+        self.statics
+    }
+
+    /// Get the start address and initial contents of each RAM resident segment.
+    pub fn get_ram_segments(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.segments
+            .ram_segments()
+            .iter()
+            .map(|segment| (segment.start_address(), segment.data()))
     }
 
     pub fn address_in_range(&self, address: u64) -> bool {
@@ -343,26 +555,99 @@ impl Project {
         self.symtab.get(symbol).copied()
     }
 
+    /// Add a function of `size` bytes at `address`, `name` is demangled.
+    pub fn add_function_symbol(&mut self, address: u64, size: u64, name: &str) {
+        self.function_symbols
+            .insert(address, (size, format!("{:#}", demangle(name))));
+    }
+
+    /// Set the line table used to find the source location of instructions.
+    pub fn set_line_table(&mut self, line_table: LineTable) {
+        self.line_table = line_table;
+    }
+
+    /// Get the demangled name, without the hash, of the function containing `address`.
+    pub fn get_function_name(&self, address: u64) -> Option<&str> {
+        let (start, (size, name)) = self.function_symbols.range(..=address).next_back()?;
+        // symbols without a size, such as assembly functions, cover everything up to the next
+        (*size == 0 || address < start + size).then_some(name.as_str())
+    }
+
     /// Get the instruction att a address
     pub fn get_instruction(&self, address: u64) -> Result<Instruction> {
         trace!("Reading instruction from address: {:#010X}", address);
-        match self.get_raw_word(address)? {
-            RawDataWord::Word64(d) => self.instruction_from_array_ptr(&d),
-            RawDataWord::Word32(d) => self.instruction_from_array_ptr(&d),
-            RawDataWord::Word16(d) => self.instruction_from_array_ptr(&d),
-            RawDataWord::Word8(_) => todo!(),
+        let data = match self
+            .segments
+            .read_raw_bytes_up_to(address, self.instruction_fetch_width())
+        {
+            Some(data) => data,
+            None => return Err(MemoryError::OutOfBounds.into()),
+        };
+
+        match self.instruction_endianness {
+            Endianness::Little => self.instruction_from_array_ptr(data),
+            Endianness::Big => {
+                // Thumb instructions are made of halfwords, swap them to the decoders byte order.
+                let mut data = data.to_owned();
+                for halfword in data.chunks_exact_mut(2) {
+                    halfword.swap(0, 1);
+                }
+                self.instruction_from_array_ptr(&data)
+            }
         }
     }
 
-    fn instruction_from_array_ptr(&self, data: &[u8]) -> Result<Instruction> {
+    /// Number of bytes fetched when decoding a instruction, enough to hold the longest
+    /// instruction of the architecture.
+    fn instruction_fetch_width(&self) -> usize {
         match self.architecture {
-            object::Architecture::Arm => {
+            Architecture::Arm
+            | Architecture::Aarch64
+            | Architecture::Riscv32
+            | Architecture::Riscv64 => 4,
+            _ => self.get_word_size() as usize / 8,
+        }
+    }
+
+    fn instruction_from_array_ptr(&self, data: &[u8]) -> Result<Instruction> {
+        match (self.architecture, self.target.core()) {
+            (
+                object::Architecture::Arm,
+                CoreFamily::ArmM(core @ (ArmMCore::ArmM3 | ArmMCore::ArmM4 | ArmMCore::ArmM23)),
+            ) => {
+                let instruction = match core {
+                    ArmMCore::ArmM23 => translators::armv7_m::decoder::parse_armv8m_baseline(data),
+                    ArmMCore::ArmM3 => translators::armv7_m::decoder::parse_armv7m_no_fpu(data),
+                    _ => translators::armv7_m::decoder::parse(data),
+                };
+                let instruction = match instruction {
+                    Ok(instruction) => instruction,
+                    Err(e) => return Err(ProjectError::UnableToDecodeInstruction(e.to_string())),
+                };
+                trace!("instruction read: {:?}", instruction);
+                match Armv7MInstruction::new(instruction, core) {
+                    Ok(instruction) => Ok(instruction.translate()),
+                    Err(e) => Err(ProjectError::UnableToDecodeInstruction(e.to_string())),
+                }
+            }
+            (object::Architecture::Arm, _) => {
                 // probobly right add more cheks later or custom enum etc.
                 let arm_instruction = parse(data).unwrap();
                 trace!("instruction read: {:?}", arm_instruction);
                 Ok(arm_instruction.translate())
             }
-            _ => todo!(),
+            (object::Architecture::Riscv32, CoreFamily::RiscV(core)) => {
+                let instruction = match translators::riscv::decoder::parse(data) {
+                    Ok(instruction) => instruction,
+                    Err(e) => return Err(ProjectError::UnableToDecodeInstruction(e.to_string())),
+                };
+                trace!("instruction read: {:?}", instruction);
+                Ok(RiscVInstruction { instruction, core }.translate())
+            }
+            (architecture, _) => Err(ProjectError::UnsupportedArchitecture(format!(
+                "{:?}",
+                architecture
+            ))),
         }
     }
 
@@ -47,7 +47,7 @@ pub fn construct_pc_hooks<R: Reader>(
 
                     if let AttributeValue::Addr(addr_value) = addr {
                         trace!("found hook for {} att addr: {:#X}", name, addr_value);
-                        ret.insert(addr_value, *hook);
+                        ret.insert(addr_value, hook.clone());
                     }
                 }
             }
@@ -59,22 +59,28 @@ pub fn construct_pc_hooks<R: Reader>(
     ret
 }
 
+/// Constructs a list of address hook pairs by searching all subprograms in the dwarf debug data.
+///
+/// Returns the hooks together with the patterns that matched a subprogram with a address and the
+/// matched subprograms without a address (inlined or abstract instances) as (subprogram name,
+/// pattern, hook), these have to be looked up in the symbol table.
 pub fn construct_pc_hooks_no_index<R: Reader>(
-    hooks: Vec<(Regex, PCHook)>,
+    hooks: &[(Regex, PCHook)],
     debug_info: &DebugInfo<R>,
     debug_abbrev: &DebugAbbrev<R>,
     debug_str: &DebugStr<R>,
-) -> PCHooks {
+) -> (PCHooks, HashSet<String>, Vec<(String, String, PCHook)>) {
     trace!("Constructing PC hooks");
     let mut ret: PCHooks = HashMap::new();
     let mut found_hooks = HashSet::new();
+    let mut no_address = vec![];
 
     let mut units = debug_info.units();
     while let Some(unit) = units.next().unwrap() {
         let abbrev = unit.abbreviations(debug_abbrev).unwrap();
         let mut cursor = unit.entries(&abbrev);
 
-        'inner: while let Some((_dept, entry)) = cursor.next_dfs().unwrap() {
+        while let Some((_dept, entry)) = cursor.next_dfs().unwrap() {
             let tag = entry.tag();
             if tag != gimli::DW_TAG_subprogram {
                 // is not a function continue the search
@@ -92,25 +98,32 @@ pub fn construct_pc_hooks_no_index<R: Reader>(
             let entry_name = debug_str.get_str(entry_name).unwrap();
             let name_str = entry_name.to_string().unwrap();
 
-            for (name, hook) in &hooks {
+            for (name, hook) in hooks {
                 if name.is_match(name_str.as_ref()) {
                     let addr = match entry.attr_value(DW_AT_low_pc).unwrap() {
                         Some(v) => v,
-                        None => continue 'inner,
+                        None => {
+                            no_address.push((
+                                name_str.to_string(),
+                                name.as_str().to_owned(),
+                                hook.clone(),
+                            ));
+                            continue;
+                        }
                     };
-                    found_hooks.insert(name.as_str());
+                    found_hooks.insert(name.as_str().to_owned());
 
                     if let AttributeValue::Addr(addr_value) = addr {
                         trace!("found hook for {} att addr: {:#X}", name, addr_value);
-                        ret.insert(addr_value, *hook);
+                        ret.insert(addr_value, hook.clone());
                     }
                 }
             }
         }
     }
     if found_hooks.len() < hooks.len() {
-        debug!("Did not find addresses for all hooks.") // fix a proper error here later
+        debug!("Did not find addresses for all hooks in the dwarf data.")
     }
 
-    ret
+    (ret, found_hooks, no_address)
 }
@@ -0,0 +1,272 @@
+//! Hooks that replace instructions, register accesses and memory accesses.
+//!
+//! A hook is any type implementing one of the hook traits. Hooks can carry their own data and
+//! are called with a mutable reference to themselves, every path has its own copy of all hooks
+//! that is cloned when the path is forked. Changes a hook makes to itself are therefore only
+//! seen by the path making them and the paths forked from it.
+//!
+//! Plain functions and closures with the matching signature implement the hook traits, so a `fn`
+//! hook such as a [`MemoryReadHook`] can be used directly.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{general_assembly::Result, smt::DExpr};
+
+use super::{GAState, MemoryHookAddress};
+
+macro_rules! hook_trait {
+    ($(#[$doc:meta])* $name:ident, $clone:ident, $method:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        $(#[$doc])*
+        pub trait $name: $clone {
+            fn $method(&mut self, state: &mut GAState, $($arg: $ty),*) -> Result<$ret>;
+        }
+
+        /// Clones a boxed hook, implemented for all hooks that implement [`Clone`].
+        pub trait $clone {
+            fn clone_box(&self) -> Box<dyn $name>;
+        }
+
+        impl<T: $name + Clone + 'static> $clone for T {
+            fn clone_box(&self) -> Box<dyn $name> {
+                Box::new(self.clone())
+            }
+        }
+
+        impl<F, R> $name for F
+        where
+            F: FnMut(&mut GAState, $($ty),*) -> Result<R> + Clone + 'static,
+            R: Into<$ret>,
+        {
+            fn $method(&mut self, state: &mut GAState, $($arg: $ty),*) -> Result<$ret> {
+                self(state, $($arg),*).map(Into::into)
+            }
+        }
+
+        impl Clone for Box<dyn $name> {
+            fn clone(&self) -> Self {
+                self.clone_box()
+            }
+        }
+
+        impl fmt::Debug for dyn $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, stringify!($name))
+            }
+        }
+    };
+}
+
+hook_trait!(
+    /// A hook run instead of the function at a address. The returned [`HookResult`] decides how
+    /// the path continues, a function returning `Result<()>` always continues the path.
+    IntrinsicHook, IntrinsicHookClone, run() -> HookResult
+);
+
+hook_trait!(
+    /// A hook run instead of reading a register.
+    RegisterRead, RegisterReadClone, read() -> DExpr
+);
+
+hook_trait!(
+    /// A hook run instead of writing a register.
+    RegisterWrite, RegisterWriteClone, write(value: DExpr) -> ()
+);
+
+hook_trait!(
+    /// A hook run instead of reading memory.
+    MemoryRead, MemoryReadClone, read(address: u64) -> DExpr
+);
+
+hook_trait!(
+    /// A hook run instead of writing memory.
+    MemoryWrite, MemoryWriteClone, write(address: u64, value: DExpr, bits: u32) -> ()
+);
+
+/// How the path continues after a [`IntrinsicHook`] has run.
+///
+/// The hook is responsible for setting the PC to where the execution continues, usually the
+/// return address, unless it ends the path.
+#[derive(Debug, Clone)]
+pub enum HookResult {
+    /// Continue the path.
+    Continue,
+
+    /// Continue in two paths, one where the condition holds and one where it does not.
+    /// Paths where the condition can not hold or can not fail are not created.
+    Fork(DExpr),
+
+    /// Add the condition to the constraints of the path and continue. The path ends with
+    /// [`PathResult::AssumptionUnsat`](crate::general_assembly::executor::PathResult::AssumptionUnsat)
+    /// if the condition can not hold.
+    Assume(DExpr),
+
+    /// End the path successfully.
+    EndSuccess,
+
+    /// End the path with a failure.
+    EndFaliure(String),
+
+    /// End the path without reporting it.
+    Suppress,
+}
+
+impl From<()> for HookResult {
+    fn from(_: ()) -> Self {
+        HookResult::Continue
+    }
+}
+
+/// What to do when the PC reaches a hooked address.
+#[derive(Debug, Clone)]
+pub enum PCHook {
+    Continue,
+    EndSuccess,
+    EndFaliure(String),
+    Intrinsic(Box<dyn IntrinsicHook>),
+    Suppress,
+}
+
+impl PCHook {
+    /// Create a hook that runs `hook` instead of the function.
+    pub fn intrinsic(hook: impl IntrinsicHook + 'static) -> Self {
+        PCHook::Intrinsic(Box::new(hook))
+    }
+}
+
+pub type PCHooks = HashMap<u64, PCHook>;
+
+/// Function hook for a register read.
+pub type RegisterReadHook = fn(state: &mut GAState) -> Result<DExpr>;
+pub type RegisterReadHooks = HashMap<String, Box<dyn RegisterRead>>;
+
+/// Function hook for a register write.
+pub type RegisterWriteHook = fn(state: &mut GAState, value: DExpr) -> Result<()>;
+pub type RegisterWriteHooks = HashMap<String, Box<dyn RegisterWrite>>;
+
+/// Function hook for a memory write.
+pub type MemoryWriteHook =
+    fn(state: &mut GAState, address: u64, value: DExpr, bits: u32) -> Result<()>;
+pub type SingleMemoryWriteHooks = HashMap<u64, Box<dyn MemoryWrite>>;
+pub type RangeMemoryWriteHooks = Vec<((u64, u64), Box<dyn MemoryWrite>)>;
+
+/// Function hook for a memory read.
+pub type MemoryReadHook = fn(state: &mut GAState, address: u64) -> Result<DExpr>;
+pub type SingleMemoryReadHooks = HashMap<u64, Box<dyn MemoryRead>>;
+pub type RangeMemoryReadHooks = Vec<((u64, u64), Box<dyn MemoryRead>)>;
+
+/// Hooks on single addresses and address ranges.
+#[derive(Debug, Clone)]
+pub struct MemoryHooks<H> {
+    single: HashMap<u64, H>,
+    range: Vec<((u64, u64), H)>,
+}
+
+/// Where a hook taken out of [`MemoryHooks`] is put back.
+#[derive(Debug, Clone, Copy)]
+pub enum MemoryHookSlot {
+    Single(u64),
+    Range(usize, (u64, u64)),
+}
+
+impl<H> MemoryHooks<H> {
+    pub fn new(single: HashMap<u64, H>, range: Vec<((u64, u64), H)>) -> Self {
+        Self { single, range }
+    }
+
+    /// Construct from a list of hooks, a later hook on the same single address replaces a
+    /// earlier one.
+    pub fn from_hooks(hooks: Vec<(MemoryHookAddress, H)>) -> Self {
+        let mut memory_hooks = Self::new(HashMap::new(), vec![]);
+        for (address, hook) in hooks {
+            memory_hooks.add(address, hook);
+        }
+        memory_hooks
+    }
+
+    /// Add a hook, replacing the hook on the same single address if there is one.
+    pub fn add(&mut self, address: MemoryHookAddress, hook: H) {
+        match address {
+            MemoryHookAddress::Single(addr) => {
+                self.single.insert(addr, hook);
+            }
+            MemoryHookAddress::Range(start, end) => {
+                self.range.push(((start, end), hook));
+            }
+        }
+    }
+
+    /// Check if there is a hook on `address`.
+    pub fn contains(&self, address: u64) -> bool {
+        self.single.contains_key(&address)
+            || self
+                .range
+                .iter()
+                .any(|((start, end), _)| address >= *start && address < *end)
+    }
+
+    /// Take the hook on `address` out so it can be run, the hook on the single address is
+    /// preferred over the first matching range.
+    pub fn take(&mut self, address: u64) -> Option<(MemoryHookSlot, H)> {
+        if let Some(hook) = self.single.remove(&address) {
+            return Some((MemoryHookSlot::Single(address), hook));
+        }
+        let index = self
+            .range
+            .iter()
+            .position(|((start, end), _)| address >= *start && address < *end)?;
+        let (range, hook) = self.range.remove(index);
+        Some((MemoryHookSlot::Range(index, range), hook))
+    }
+
+    /// Put back a hook taken with [`MemoryHooks::take`].
+    pub fn restore(&mut self, slot: MemoryHookSlot, hook: H) {
+        match slot {
+            MemoryHookSlot::Single(address) => {
+                self.single.insert(address, hook);
+            }
+            MemoryHookSlot::Range(index, range) => self.range.insert(index, (range, hook)),
+        }
+    }
+}
+
+/// All hooks of a path.
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    pub pc: PCHooks,
+    pub register_read: RegisterReadHooks,
+    pub register_write: RegisterWriteHooks,
+    pub memory_read: MemoryHooks<Box<dyn MemoryRead>>,
+    pub memory_write: MemoryHooks<Box<dyn MemoryWrite>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory_hooks() {
+        let function_hook: MemoryReadHook = |state, address| Ok(state.ctx.from_u64(address, 32));
+        let closure_hook = |state: &mut GAState, _address| Ok(state.ctx.from_u64(0, 32));
+        let mut hooks: MemoryHooks<Box<dyn MemoryRead>> = MemoryHooks::from_hooks(vec![
+            (
+                MemoryHookAddress::Range(0x100, 0x200),
+                Box::new(function_hook),
+            ),
+            (MemoryHookAddress::Single(0x104), Box::new(closure_hook)),
+        ]);
+
+        assert!(hooks.contains(0x1fc));
+        assert!(!hooks.contains(0x200));
+
+        // the single address hook is preferred over the range
+        let (slot, hook) = hooks.take(0x104).unwrap();
+        assert!(matches!(slot, MemoryHookSlot::Single(0x104)));
+        hooks.restore(slot, hook);
+
+        let (slot, hook) = hooks.take(0x108).unwrap();
+        assert!(matches!(slot, MemoryHookSlot::Range(0, (0x100, 0x200))));
+        assert!(!hooks.contains(0x108));
+        hooks.restore(slot, hook);
+        assert!(hooks.contains(0x108));
+    }
+}
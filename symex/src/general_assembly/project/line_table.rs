@@ -0,0 +1,166 @@
+//! Maps addresses to source lines using the dwarf line table (`.debug_line`).
+
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use gimli::{ColumnType, Dwarf, FileEntry, LineProgramHeader, Reader, Unit};
+
+/// A line in the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+    pub column: Option<u64>,
+}
+
+impl SourceLocation {
+    /// Check if the location is in the user code, that is not in the standard library or in a
+    /// dependency.
+    pub fn is_user_code(&self) -> bool {
+        !(self.file.starts_with("/rustc/")
+            || self.file.contains("/.cargo/registry/")
+            || self.file.contains("/.cargo/git/")
+            || self.file.contains("/.rustup/"))
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        Ok(())
+    }
+}
+
+/// The rows of the line tables of all compilation units.
+#[derive(Debug, Clone, Default)]
+pub struct LineTable {
+    /// Start address of each row mapped to the end address (exclusive) and the location.
+    rows: BTreeMap<u64, (u64, SourceLocation)>,
+}
+
+impl LineTable {
+    /// Read the line tables of all compilation units.
+    pub fn from_dwarf<R: Reader>(dwarf: &Dwarf<R>) -> gimli::Result<Self> {
+        let mut rows = BTreeMap::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+
+            let mut sequence: Vec<(u64, SourceLocation)> = vec![];
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row()? {
+                if row.end_sequence() {
+                    // each row covers the addresses up to the next row in the sequence
+                    let mut end = row.address();
+                    for (start, location) in sequence.drain(..).rev() {
+                        if start < end {
+                            // sequences of functions removed by the linker overlap, keep the first
+                            rows.entry(start).or_insert((end, location));
+                        }
+                        end = start;
+                    }
+                    continue;
+                }
+
+                let file = match row.file(header) {
+                    Some(file) => file_path(dwarf, &unit, header, file)?,
+                    None => continue,
+                };
+                let column = match row.column() {
+                    ColumnType::LeftEdge => None,
+                    ColumnType::Column(column) => Some(column.get()),
+                };
+                sequence.push((
+                    row.address(),
+                    SourceLocation {
+                        file,
+                        line: row.line().map(|line| line.get()).unwrap_or(0),
+                        column,
+                    },
+                ));
+            }
+        }
+        Ok(Self { rows })
+    }
+
+    /// Get the source location of the instruction at `address`.
+    pub fn get(&self, address: u64) -> Option<&SourceLocation> {
+        let (_, (end, location)) = self.rows.range(..=address).next_back()?;
+        (address < *end).then_some(location)
+    }
+
+    /// Add a row for the instructions from `start` up to `end` (exclusive).
+    pub fn insert(&mut self, start: u64, end: u64, location: SourceLocation) {
+        self.rows.insert(start, (end, location));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Get the full path of a file in the line table.
+fn file_path<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    header: &LineProgramHeader<R>,
+    file: &FileEntry<R>,
+) -> gimli::Result<String> {
+    let mut path = PathBuf::new();
+    if let Some(comp_dir) = &unit.comp_dir {
+        path.push(comp_dir.to_string_lossy()?.as_ref());
+    }
+    if let Some(directory) = file.directory(header) {
+        path.push(
+            dwarf
+                .attr_string(unit, directory)?
+                .to_string_lossy()?
+                .as_ref(),
+        );
+    }
+    path.push(
+        dwarf
+            .attr_string(unit, file.path_name())?
+            .to_string_lossy()?
+            .as_ref(),
+    );
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn location(file: &str, line: u64) -> SourceLocation {
+        SourceLocation {
+            file: file.to_owned(),
+            line,
+            column: None,
+        }
+    }
+
+    #[test]
+    fn test_line_table_lookup() {
+        let panicking = "/rustc/abc/library/core/src/panicking.rs";
+        let table = LineTable {
+            rows: BTreeMap::from([
+                (0x100, (0x104, location("src/main.rs", 10))),
+                (0x104, (0x108, location("src/main.rs", 11))),
+                (0x200, (0x202, location(panicking, 5))),
+            ]),
+        };
+
+        assert_eq!(table.get(0x102), Some(&location("src/main.rs", 10)));
+        assert_eq!(table.get(0x104), Some(&location("src/main.rs", 11)));
+        assert_eq!(table.get(0x108), None);
+        assert_eq!(table.get(0xff), None);
+        assert!(!table.get(0x200).unwrap().is_user_code());
+        assert_eq!(table.get(0x104).unwrap().to_string(), "src/main.rs:11");
+    }
+}
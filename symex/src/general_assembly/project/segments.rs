@@ -1,58 +1,134 @@
 //! A loader that can load all segments from a elf file properly.
 
-use object::{read::elf::ProgramHeader, File, Object};
+use object::{
+    read::elf::{ElfFile, FileHeader, ProgramHeader},
+    File, ReadRef,
+};
+
+/// Type of a loadable program header.
+const PT_LOAD: u32 = 1;
+
+/// Program header flag set for segments that are writable at run time.
+const PF_W: u32 = 0x2;
+
 pub struct Segment {
     data: Vec<u8>,
     start_address: u64,
     end_address: u64,
 }
 
-pub struct Segments(Vec<Segment>);
+impl Segment {
+    /// Address of the first byte in the segment.
+    pub fn start_address(&self) -> u64 {
+        self.start_address
+    }
+
+    /// Contents of the segment.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// The loadable segments of a program.
+///
+/// Read-only segments (flash) are kept as static memory. Writable segments (`.data` and `.bss`)
+/// are RAM resident and are kept separately with their initial contents at their run time
+/// address (VMA), zero filled up to their memory size. The load image of a writable segment at
+/// its load address (LMA) is kept as static memory as the startup code may copy it from there.
+pub struct Segments {
+    read_only: Vec<Segment>,
+    ram: Vec<Segment>,
+}
 
 impl Segments {
     pub fn from_single_segment(data: Vec<u8>, start_addr: u64, end_addr: u64) -> Self {
-        Segments(vec![Segment {
-            data,
-            start_address: start_addr,
-            end_address: end_addr,
-        }])
+        Segments {
+            read_only: vec![Segment {
+                data,
+                start_address: start_addr,
+                end_address: end_addr,
+            }],
+            ram: vec![],
+        }
+    }
+
+    pub fn from_file(file: &File) -> Option<Self> {
+        match file {
+            File::Elf32(elf_file) => Some(Self::from_elf(elf_file)),
+            File::Elf64(elf_file) => Some(Self::from_elf(elf_file)),
+            _ => None,
+        }
     }
 
-    pub fn from_file(file: &File) -> Self {
-        let elf_file = match file {
-            File::Elf32(elf_file) => elf_file,
-            File::Elf64(_elf_file) => todo!(),
-            _ => todo!(),
-        };
+    fn from_elf<'data, Elf: FileHeader, R: ReadRef<'data>>(
+        elf_file: &ElfFile<'data, Elf, R>,
+    ) -> Self {
+        let endian = elf_file.endian();
 
-        let mut ret = vec![];
+        let mut read_only = vec![];
+        let mut ram = vec![];
         for segment in elf_file.raw_segments() {
-            let segment_type = segment.p_type.get(file.endianness());
-            if segment_type == 1 {
-                // if it is a LOAD segment
-                let addr_start = segment.p_vaddr.get(file.endianness()) as u64;
-                //let size = segment.p_memsz.get(file.endianness());
-                let data = segment.data(file.endianness(), elf_file.data()).unwrap();
-
-                ret.push(Segment {
+            if segment.p_type(endian) != PT_LOAD {
+                continue;
+            }
+
+            let virtual_address: u64 = segment.p_vaddr(endian).into();
+            let load_address: u64 = segment.p_paddr(endian).into();
+            let memory_size: u64 = segment.p_memsz(endian).into();
+            let writable = segment.p_flags(endian) & PF_W != 0;
+            let data = segment.data(endian, elf_file.data()).unwrap();
+
+            if !writable {
+                read_only.push(Segment {
+                    data: data.to_owned(),
+                    start_address: virtual_address,
+                    end_address: virtual_address + data.len() as u64,
+                });
+                continue;
+            }
+
+            if load_address != virtual_address && !data.is_empty() {
+                read_only.push(Segment {
                     data: data.to_owned(),
-                    start_address: addr_start,
-                    end_address: addr_start + data.len() as u64,
-                })
+                    start_address: load_address,
+                    end_address: load_address + data.len() as u64,
+                });
             }
+
+            // the part of the segment not in the file (.bss) is zero initialised
+            let mut ram_data = data.to_owned();
+            ram_data.resize((memory_size as usize).max(data.len()), 0);
+            ram.push(Segment {
+                start_address: virtual_address,
+                end_address: virtual_address + ram_data.len() as u64,
+                data: ram_data,
+            });
         }
-        Segments(ret)
+        Segments { read_only, ram }
     }
 
     pub fn read_raw_bytes(&self, address: u64, bytes: usize) -> Option<&[u8]> {
-        for segment in &self.0 {
+        match self.read_raw_bytes_up_to(address, bytes) {
+            Some(data) if data.len() == bytes => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Read at most `bytes` bytes, fewer if the segment ends before that.
+    pub fn read_raw_bytes_up_to(&self, address: u64, bytes: usize) -> Option<&[u8]> {
+        for segment in &self.read_only {
             if address >= segment.start_address && address < segment.end_address {
                 let offset = (address - segment.start_address) as usize;
-                let data_slice = &segment.data[offset..(offset + bytes)];
-                return Some(data_slice);
+                let end = (offset + bytes).min(segment.data.len());
+                return Some(&segment.data[offset..end]);
             }
         }
 
         None
     }
+
+    /// Get the RAM resident segments with their initial contents.
+    pub fn ram_segments(&self) -> &[Segment] {
+        &self.ram
+    }
 }
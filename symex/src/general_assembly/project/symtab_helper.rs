@@ -0,0 +1,114 @@
+//! Helper functions to resolve symbols from the ELF symbol table when there is no dwarf debug data.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use rustc_demangle::demangle;
+use tracing::trace;
+
+use super::{PCHook, PCHooks};
+
+/// Constructs a list of address hook pairs by matching the hook patterns against the function
+/// symbols in the symbol table.
+///
+/// A pattern matches a symbol if it matches the mangled name, the demangled name with or without
+/// the hash or the bare function name as it is named in the dwarf data.
+///
+/// Returns the hooks together with the patterns that matched a symbol.
+pub fn construct_pc_hooks_from_symtab(
+    hooks: &[(Regex, PCHook)],
+    functions: &[(String, u64)],
+) -> (PCHooks, HashSet<String>) {
+    trace!("Constructing PC hooks from the symbol table");
+    let mut ret: PCHooks = HashMap::new();
+    let mut found_hooks = HashSet::new();
+
+    for (symbol, address) in functions {
+        let demangled = demangle(symbol);
+        let demangled_name = demangled.to_string();
+        let demangled_name_no_hash = format!("{demangled:#}");
+        let function_name = function_name(&demangled_name_no_hash);
+
+        for (name, hook) in hooks {
+            let matches = [
+                symbol.as_str(),
+                &demangled_name,
+                &demangled_name_no_hash,
+                &function_name,
+            ]
+            .iter()
+            .any(|candidate| name.is_match(candidate));
+
+            if matches {
+                trace!("found hook for {} att addr: {:#X}", name, address);
+                found_hooks.insert(name.as_str().to_owned());
+                ret.insert(*address, hook.clone());
+            }
+        }
+    }
+
+    (ret, found_hooks)
+}
+
+/// Get the name of a function without its path, `core::panicking::panic` becomes `panic` and
+/// `symex_lib::symbolic_size::<u8>` becomes `symbolic_size<u8>` as in the dwarf data.
+fn function_name(path: &str) -> String {
+    let path = path.replace("::<", "<");
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if !path[..i].ends_with('-') => depth -= 1,
+            ':' if depth == 0 && path[i..].starts_with("::") => start = i + 2,
+            _ => (),
+        }
+    }
+    path[start..].to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_function_name() {
+        assert_eq!(function_name("core::panicking::panic"), "panic");
+        assert_eq!(
+            function_name("symex_lib::symbolic_size::<u8>"),
+            "symbolic_size<u8>"
+        );
+        assert_eq!(
+            function_name("app::call<app::Foo as core::ops::Fn<()>>"),
+            "call<app::Foo as core::ops::Fn<()>>"
+        );
+        assert_eq!(function_name("main"), "main");
+    }
+
+    #[test]
+    fn test_symtab_pc_hooks() {
+        let hooks = vec![
+            (
+                Regex::new(r"^panic$").unwrap(),
+                PCHook::EndFaliure("panic".to_owned()),
+            ),
+            (Regex::new(r"^suppress_path$").unwrap(), PCHook::Suppress),
+        ];
+        let functions = vec![
+            (
+                "_ZN4core9panicking5panic17h0123456789abcdefE".to_owned(),
+                0x1000,
+            ),
+            ("panic_bounds_check".to_owned(), 0x2000),
+        ];
+
+        let (pc_hooks, found_hooks) = construct_pc_hooks_from_symtab(&hooks, &functions);
+        assert!(matches!(
+            pc_hooks.get(&0x1000),
+            Some(PCHook::EndFaliure(reason)) if reason == "panic"
+        ));
+        assert_eq!(pc_hooks.len(), 1);
+        assert!(found_hooks.contains("^panic$"));
+        assert!(!found_hooks.contains("^suppress_path$"));
+    }
+}
@@ -1,14 +1,299 @@
 //! Information about the specific target hardware supplied by the user.
 
+use tracing::debug;
+
+use crate::general_assembly::{project::ProjectError, Endianness};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TargetConfiguration {
     core: CoreFamily,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CoreFamily {
     ArmM(ArmMCore),
+    RiscV(RiscVCore),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArmMCore {
     ArmM0,
     ArmM0Plus,
-}
\ No newline at end of file
+    ArmM3,
+    ArmM4,
+    /// A armv8-m baseline core.
+    ArmM23,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiscVCore {
+    /// A single issue in-order core implementing RV32IMC, such as the one in the ESP32-C3.
+    Rv32imc,
+}
+
+// Values of the Tag_CPU_arch build attribute.
+const CPU_ARCH_V7: u64 = 10;
+const CPU_ARCH_V6_M: u64 = 11;
+const CPU_ARCH_V6S_M: u64 = 12;
+const CPU_ARCH_V7E_M: u64 = 13;
+const CPU_ARCH_V8_M_BASE: u64 = 16;
+
+impl Default for TargetConfiguration {
+    /// Defaults to a Cortex-M0+ as that was the only supported core before the target was
+    /// read from the ELF file.
+    fn default() -> Self {
+        TargetConfiguration {
+            core: CoreFamily::ArmM(ArmMCore::ArmM0Plus),
+        }
+    }
+}
+
+impl TargetConfiguration {
+    pub fn new(core: CoreFamily) -> Self {
+        TargetConfiguration { core }
+    }
+
+    pub fn core(&self) -> CoreFamily {
+        self.core
+    }
+
+    /// Name of the register holding the return address of the current function.
+    pub fn return_address_register(&self) -> &'static str {
+        match self.core {
+            CoreFamily::ArmM(_) => "LR",
+            CoreFamily::RiscV(_) => "ra",
+        }
+    }
+
+    /// Name of the register holding argument `n` (starting at 0) of a function call.
+    pub fn argument_register(&self, n: usize) -> String {
+        match self.core {
+            CoreFamily::ArmM(_) => format!("R{}", n),
+            CoreFamily::RiscV(_) => format!("a{}", n),
+        }
+    }
+
+    /// Name of the stack pointer register.
+    pub fn stack_pointer_register(&self) -> &'static str {
+        match self.core {
+            CoreFamily::ArmM(_) => "SP",
+            CoreFamily::RiscV(_) => "sp",
+        }
+    }
+
+    /// Check if `register` is a single precision floating point register.
+    pub fn is_float_register(&self, register: &str) -> bool {
+        match self.core {
+            CoreFamily::ArmM(_) => match register.strip_prefix('S') {
+                Some(number) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+                None => false,
+            },
+            CoreFamily::RiscV(_) => false,
+        }
+    }
+
+    /// Number of cycles from an exception being taken until the first instruction of the
+    /// handler is executed, including stacking of the exception frame.
+    pub fn exception_entry_cycles(&self) -> Result<usize, ProjectError> {
+        match self.core {
+            CoreFamily::ArmM(ArmMCore::ArmM0) => Ok(16),
+            CoreFamily::ArmM(ArmMCore::ArmM0Plus) => Ok(15),
+            CoreFamily::ArmM(ArmMCore::ArmM3 | ArmMCore::ArmM4) => Ok(12),
+            CoreFamily::ArmM(ArmMCore::ArmM23) => Ok(15),
+            CoreFamily::RiscV(_) => Err(ProjectError::UnsupportedArchitecture(
+                "exceptions are only modeled for Arm M-profile cores".to_owned(),
+            )),
+        }
+    }
+
+    /// Number of cycles from a exception return until the first instruction of the interrupted
+    /// code is executed, including unstacking of the exception frame.
+    ///
+    /// The ARMv6-M and ARMv8-M baseline cores do not document the return latency separately
+    /// so it is assumed to be the same as the entry latency.
+    pub fn exception_return_cycles(&self) -> Result<usize, ProjectError> {
+        match self.core {
+            CoreFamily::ArmM(ArmMCore::ArmM3 | ArmMCore::ArmM4) => Ok(10),
+            _ => self.exception_entry_cycles(),
+        }
+    }
+
+    /// Determine the core from the content of the `.ARM.attributes` section.
+    ///
+    /// Returns `None` if the section can not be parsed or the architecture is unknown.
+    pub fn from_arm_attributes(data: &[u8], endianness: Endianness) -> Option<Self> {
+        let attributes = parse_arm_attributes(data, endianness)?;
+        let arch = attributes.cpu_arch?;
+        let m_profile = attributes.cpu_arch_profile == Some(b'M' as u64);
+        debug!(
+            "Found cpu arch {} profile {:?} in build attributes",
+            arch, attributes.cpu_arch_profile
+        );
+
+        let core = match arch {
+            CPU_ARCH_V6_M | CPU_ARCH_V6S_M => ArmMCore::ArmM0Plus,
+            CPU_ARCH_V7 if m_profile => ArmMCore::ArmM3,
+            CPU_ARCH_V7E_M => ArmMCore::ArmM4,
+            CPU_ARCH_V8_M_BASE => ArmMCore::ArmM23,
+            _ => return None,
+        };
+        Some(TargetConfiguration::new(CoreFamily::ArmM(core)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct ArmAttributes {
+    cpu_arch: Option<u64>,
+    cpu_arch_profile: Option<u64>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.offset)?;
+        self.offset += 1;
+        Some(value)
+    }
+
+    fn u32(&mut self, endianness: &Endianness) -> Option<u32> {
+        let bytes: [u8; 4] = self
+            .data
+            .get(self.offset..self.offset + 4)?
+            .try_into()
+            .ok()?;
+        self.offset += 4;
+        Some(match endianness {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn uleb128(&mut self) -> Option<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    fn ntbs(&mut self) -> Option<&'a [u8]> {
+        let rest = self.data.get(self.offset..)?;
+        let len = rest.iter().position(|b| *b == 0)?;
+        self.offset += len + 1;
+        Some(&rest[..len])
+    }
+}
+
+/// Parses the file scope attributes of the "aeabi" vendor section.
+fn parse_arm_attributes(data: &[u8], endianness: Endianness) -> Option<ArmAttributes> {
+    let mut reader = Reader { data, offset: 0 };
+    if reader.u8()? != b'A' {
+        return None;
+    }
+
+    let mut attributes = ArmAttributes::default();
+    while reader.offset < data.len() {
+        let section_start = reader.offset;
+        let section_end = section_start + reader.u32(&endianness)? as usize;
+        let vendor = reader.ntbs()?;
+        if vendor != b"aeabi" {
+            reader.offset = section_end;
+            continue;
+        }
+
+        while reader.offset < section_end {
+            let subsection_start = reader.offset;
+            let tag = reader.uleb128()?;
+            let subsection_end = subsection_start + reader.u32(&endianness)? as usize;
+            // Only file scope attributes are of interest
+            if tag != 1 {
+                reader.offset = subsection_end;
+                continue;
+            }
+
+            let mut sub_reader = Reader {
+                data: data.get(..subsection_end)?,
+                offset: reader.offset,
+            };
+            while sub_reader.offset < subsection_end {
+                let tag = sub_reader.uleb128()?;
+                match tag {
+                    4 | 5 | 67 => {
+                        sub_reader.ntbs()?;
+                    }
+                    6 => attributes.cpu_arch = Some(sub_reader.uleb128()?),
+                    7 => attributes.cpu_arch_profile = Some(sub_reader.uleb128()?),
+                    32 => {
+                        sub_reader.uleb128()?;
+                        sub_reader.ntbs()?;
+                    }
+                    // Unknown tags above 32 follow the rule that odd tags are strings.
+                    t if t > 32 && t % 2 == 1 => {
+                        sub_reader.ntbs()?;
+                    }
+                    _ => {
+                        sub_reader.uleb128()?;
+                    }
+                }
+            }
+            reader.offset = subsection_end;
+        }
+        reader.offset = section_end;
+    }
+
+    Some(attributes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build a attribute section with the given file scope attributes.
+    fn attribute_section(attributes: &[u8]) -> Vec<u8> {
+        let mut data = vec![b'A'];
+        let mut section = b"aeabi\0".to_vec();
+        let mut file = vec![1];
+        file.extend_from_slice(&((attributes.len() + 5) as u32).to_le_bytes());
+        file.extend_from_slice(attributes);
+        section.extend_from_slice(&file);
+        data.extend_from_slice(&((section.len() + 4) as u32).to_le_bytes());
+        data.extend_from_slice(&section);
+        data
+    }
+
+    #[test]
+    fn test_cortex_m4_attributes() {
+        // Attributes emitted by rustc for thumbv7em-none-eabi.
+        let data = attribute_section(&[
+            5, b'c', b'o', b'r', b't', b'e', b'x', b'-', b'm', b'4', 0, 6, 13, 7, b'M', 8, 0, 9, 2,
+            34, 1,
+        ]);
+
+        assert_eq!(
+            TargetConfiguration::from_arm_attributes(&data, Endianness::Little),
+            Some(TargetConfiguration::new(CoreFamily::ArmM(ArmMCore::ArmM4)))
+        );
+    }
+
+    #[test]
+    fn test_cortex_m23_attributes() {
+        // Attributes emitted by rustc for thumbv8m.base-none-eabi.
+        let data = attribute_section(&[6, 16, 7, b'M', 8, 0, 9, 3, 34, 1]);
+
+        assert_eq!(
+            TargetConfiguration::from_arm_attributes(&data, Endianness::Little),
+            Some(TargetConfiguration::new(CoreFamily::ArmM(ArmMCore::ArmM23)))
+        );
+    }
+}
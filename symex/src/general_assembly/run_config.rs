@@ -3,15 +3,24 @@
 //! setups in the symbolic execution. All hooks can be used to exchange generic functionality
 //! with a provided function to carry out case specific functionality.
 //!
+//! A hook is either a function or a type implementing one of the hook traits in
+//! [`project`](super::project). Hook types can carry their own data, each path has its own copy
+//! of the hooks that is cloned when the path forks.
+//!
 //! Writing a hook function can meaningfully alter how the symbolic execution is carried out.
 //! Therefore it is advised that one familiarizes oneself with the inner workings of Symex
 //! executor before writing a hook function.
 
 use regex::Regex;
 
+use super::instruction::SpecialInstruction;
+use super::memory_map::MemoryMap;
+use super::observer::Observer;
 use super::project::{
-    MemoryHookAddress, MemoryReadHook, MemoryWriteHook, PCHook, RegisterReadHook, RegisterWriteHook,
+    MemoryHookAddress, MemoryRead, MemoryWrite, PCHook, RegisterRead, RegisterWrite,
 };
+use super::svd::PeripheralRegister;
+use super::Config;
 
 /// Configures a symbolic execution run.
 pub struct RunConfig {
@@ -24,23 +33,110 @@ pub struct RunConfig {
 
     /// A register read hook will run a function instead of reading from a specified register.
     /// There can only be one hook on a single register.
-    pub register_read_hooks: Vec<(String, RegisterReadHook)>,
+    pub register_read_hooks: Vec<(String, Box<dyn RegisterRead>)>,
 
     /// A register write hook will run a function instead of writing to a specified register.
     /// There can only be one hook on a single register.
-    pub register_write_hooks: Vec<(String, RegisterWriteHook)>,
+    pub register_write_hooks: Vec<(String, Box<dyn RegisterWrite>)>,
 
     /// A memory write hook will run a function instead of writing to a single address or range of addresses.
     /// There can only be one hook on a single address but may be multiple on a range but only one hook will be run.
     /// The hook that will run on multiple possible matches is the hook for the matching single address if it exist
     /// otherwise the first matching range will be executed.
     /// As it is not guaranteed that the order is preserved it is recommended to ensure that there are no overlapping ranges.
-    pub memory_write_hooks: Vec<(MemoryHookAddress, MemoryWriteHook)>,
+    pub memory_write_hooks: Vec<(MemoryHookAddress, Box<dyn MemoryWrite>)>,
 
     /// A memory read hook will run a function instead of read to a single address or range of addresses.
     /// There can only be one hook on a single address but may be multiple on a range but only one hook will be run.
     /// The hook that will run on multiple possible matches is the hook for the matching single address if it exist
     /// otherwise the first matching range will be executed.
     /// As it is not guaranteed that the order is preserved it is recommended to ensure that there are no overlapping ranges.
-    pub memory_read_hooks: Vec<(MemoryHookAddress, MemoryReadHook)>,
+    pub memory_read_hooks: Vec<(MemoryHookAddress, Box<dyn MemoryRead>)>,
+
+    /// Start the analyzed function as the handler of the exception with this exception number.
+    /// The exception frame is pushed to the stack and LR is set to a EXC_RETURN value, so the
+    /// path ends when the handler performs an exception return. The exception entry and return
+    /// latencies are included in the cycle count.
+    pub exception_handler: Option<u32>,
+
+    /// Interrupts that may preempt the analyzed function.
+    /// At every instruction boundary where a interrupt is allowed by its priority and the
+    /// PRIMASK and BASEPRI registers a new path is created where the interrupt handler runs
+    /// before the execution is resumed. Each interrupt is taken at most once per path.
+    pub interrupts: Vec<Interrupt>,
+
+    /// Behaviour of instructions that can not be modeled in a general way, such as sleep,
+    /// breakpoint and undefined instructions.
+    /// Instructions not listed here use their default behaviour: WFI and WFE are sleep points,
+    /// UDF ends the path with a failure and SEV, YIELD and BKPT do nothing.
+    pub special_instructions: Vec<(SpecialInstruction, InstructionBehaviour)>,
+
+    /// Core clock frequency in Hz, used to convert the cycle count to time for the semihosting
+    /// SYS_CLOCK call. If not set SYS_CLOCK reports that the clock is unavailable.
+    pub clock_frequency: Option<u64>,
+
+    /// Limits on the exploration of each path. A path that exceeds the call depth, executes the
+    /// same instruction too many times or accesses memory at a address with too many possible
+    /// values ends with [`PathResult::LimitReached`](super::executor::PathResult::LimitReached).
+    pub config: Config,
+
+    /// How the RAM resident statics in `.data` and `.bss` are initialised at entry.
+    pub statics: Statics,
+
+    /// Memory map of the target. Accesses outside the mapped regions or not permitted by them
+    /// end the path with a bus fault and the wait states of the regions are added to the cycles
+    /// of loads and stores. A empty memory map permits all accesses without wait states.
+    pub memory_map: MemoryMap,
+
+    /// Memory mapped peripheral registers, accessed by the hooks added by
+    /// [`Device::add_hooks`](super::svd::Device::add_hooks).
+    pub peripherals: Vec<PeripheralRegister>,
+
+    /// Observers called on the events of each path, every path gets its own copy of them.
+    /// The observers of a finished path are returned in its path result.
+    pub observers: Vec<Box<dyn Observer>>,
+}
+
+/// Initial contents of the RAM resident statics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Statics {
+    /// `.data` holds the initial values from the ELF file and `.bss` is zeroed, as after the
+    /// startup code has run.
+    #[default]
+    Initialised,
+
+    /// All statics are unconstrained, so every possible value of them is explored.
+    Symbolic,
+}
+
+/// What to do when a [`SpecialInstruction`] is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionBehaviour {
+    /// Do nothing and continue with the next instruction.
+    Nop,
+
+    /// End the path successfully.
+    EndSuccess,
+
+    /// End the path with a failure naming the instruction and its address.
+    EndFaliure,
+
+    /// Sleep until a interrupt fires.
+    /// A path is created for each configured interrupt that can be taken after the instruction
+    /// and the sleeping path ends successfully. Time spent sleeping is not counted.
+    Sleep,
+}
+
+/// A interrupt that can preempt the execution.
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    /// Name of the handler function in the ELF symbol table.
+    pub handler: String,
+
+    /// Exception number of the interrupt, for external interrupts this is the IRQ number + 16.
+    pub exception_number: u32,
+
+    /// Priority of the interrupt, a lower value is a higher priority.
+    /// Active exceptions without a configured priority are assumed to have priority 0.
+    pub priority: u8,
 }
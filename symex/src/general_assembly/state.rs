@@ -5,18 +5,25 @@ use std::collections::HashMap;
 use tracing::{debug, trace};
 
 use crate::{
-    elf_util::{ExpressionType, Variable},
+    elf_util::{ExpressionType, LineTrace, Variable},
     general_assembly::{
-        project::{PCHook, ProjectError},
-        GAError, Result,
+        project::{
+            target_config::{ArmMCore, CoreFamily},
+            HookResult, Hooks, InterruptHandler, PCHook, ProjectError, SourceLocation,
+        },
+        GAError, Limit, Result, Statics,
     },
     memory::ArrayMemory,
-    smt::{DContext, DExpr, DSolver},
+    smt::{DContext, DExpr, DSolver, SolverError},
 };
 
 use super::{
     instruction::{Condition, Instruction},
+    memory_map::Access,
+    observer::Observer,
+    profile::{Cost, Profile, UNKNOWN_FUNCTION},
     project::Project,
+    timing::{FunctionSpans, Timeline, TimingEventKind},
 };
 
 pub enum HookOrInstruction {
@@ -31,6 +38,38 @@ pub struct ContinueInsideInstruction {
     pub local: HashMap<String, DExpr>,
 }
 
+/// A interrupt that preempted the execution of a path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preemption {
+    /// Name of the interrupt handler.
+    pub handler: String,
+
+    /// Exception number of the interrupt.
+    pub exception_number: u32,
+
+    /// Address of the instruction that was preempted.
+    pub pc: u64,
+
+    /// Cycle count when the interrupt was taken.
+    pub cycle: usize,
+}
+
+/// A write to a peripheral register.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeripheralWrite {
+    /// Name of the written register.
+    pub register: String,
+
+    /// Address that was written.
+    pub address: u64,
+
+    /// Value that was written.
+    pub value: Variable,
+
+    /// Cycle count when the write was made.
+    pub cycle: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct GAState {
     pub project: &'static Project,
@@ -40,9 +79,22 @@ pub struct GAState {
     pub memory: ArrayMemory,
     pub count_cycles: bool,
     pub cycle_count: usize,
-    pub cycle_laps: Vec<(usize, String)>,
+    pub timeline: Timeline,
+    pub hooks: Hooks,
+    pub observers: Vec<Box<dyn Observer>>,
+    pub preemptions: Vec<Preemption>,
+    pub peripheral_writes: Vec<PeripheralWrite>,
+    pub call_stack: Vec<u64>,
+    pub profile: Profile,
+    pub function_spans: FunctionSpans,
+    pub output: String,
+    pub exit_status: Option<u64>,
     pub last_instruction: Option<Instruction>,
     pub last_pc: u64,
+    /// The call stack when the last instruction started, a call or return made by the
+    /// instruction is only charged to the callers it was executed in.
+    pub last_call_stack: Vec<u64>,
+    pub last_user_pc: Option<u64>,
     pub registers: HashMap<String, DExpr>,
     pub continue_in_instruction: Option<ContinueInsideInstruction>,
     pub current_instruction: Option<Instruction>,
@@ -50,8 +102,28 @@ pub struct GAState {
     flags: HashMap<String, DExpr>,
     instruction_counter: usize,
     has_jumped: bool,
+    conditional_execution: Vec<Condition>,
+    in_conditional_block: bool,
+    active_exceptions: Vec<u32>,
+    banked_stack_pointer: DExpr,
+    visits: HashMap<u64, usize>,
 }
 
+/// Bit in the stacked xPSR indicating that the stack was realigned on exception entry.
+const XPSR_STACK_ALIGN: u64 = 1 << 9;
+
+/// The Thumb bit of the xPSR, always set on Arm M-profile cores.
+const XPSR_THUMB: u64 = 1 << 24;
+
+/// Position of the condition flags in the APSR.
+const XPSR_FLAGS: [(&str, u32); 5] = [("N", 31), ("Z", 30), ("C", 29), ("V", 28), ("Q", 27)];
+
+/// Bit in CONTROL that is set when the process stack pointer is used.
+const CONTROL_SPSEL: u64 = 0b10;
+
+/// Bit in CONTROL that is set when thread mode is unprivileged.
+const CONTROL_NPRIV: u64 = 0b1;
+
 impl GAState {
     /// Create a new state.
     pub fn new(
@@ -76,23 +148,40 @@ impl GAState {
         }?;
         debug!("Found stack start at addr: {:#X}.", sp_reg);
 
-        let memory = ArrayMemory::new(ctx, ptr_size, project.get_endianness());
+        let mut memory = ArrayMemory::new(ctx, ptr_size, project.get_endianness());
+        if project.get_statics() == Statics::Initialised {
+            for (start_address, data) in project.get_ram_segments() {
+                for (offset, byte) in data.iter().enumerate() {
+                    let address = ctx.from_u64(start_address + offset as u64, ptr_size);
+                    memory.write(&address, ctx.from_u64(*byte as u64, 8))?;
+                }
+            }
+        }
         let mut registers = HashMap::new();
         let pc_expr = ctx.from_u64(pc_reg, ptr_size);
         registers.insert("PC".to_owned(), pc_expr);
 
+        // The registers used to pass arguments are left unconstrained and are created
+        // when they are first read.
+        let target = project.get_target();
         let sp_expr = ctx.from_u64(sp_reg, ptr_size);
-        registers.insert("SP".to_owned(), sp_expr);
+        registers.insert(target.stack_pointer_register().to_owned(), sp_expr);
 
         // set the link register to max value to detect when returning from a function
         let end_pc_expr = ctx.from_u64(end_address, ptr_size);
-        registers.insert("LR".to_owned(), end_pc_expr);
+        registers.insert(target.return_address_register().to_owned(), end_pc_expr);
+
+        // The floating point unit starts with round to nearest and all flags cleared.
+        if target.core() == CoreFamily::ArmM(ArmMCore::ArmM4) {
+            registers.insert("FPSCR".to_owned(), ctx.zero(32));
+        }
 
         let mut flags = HashMap::new();
         flags.insert("N".to_owned(), ctx.unconstrained(1, "flags.N"));
         flags.insert("Z".to_owned(), ctx.unconstrained(1, "flags.Z"));
         flags.insert("C".to_owned(), ctx.unconstrained(1, "flags.C"));
         flags.insert("V".to_owned(), ctx.unconstrained(1, "flags.V"));
+        flags.insert("Q".to_owned(), ctx.from_bool(false));
 
         Ok(GAState {
             project,
@@ -101,7 +190,16 @@ impl GAState {
             marked_symbolic: Vec::new(),
             memory,
             cycle_count: 0,
-            cycle_laps: vec![],
+            timeline: Timeline::default(),
+            hooks: project.get_hooks().clone(),
+            observers: project.get_observers().to_vec(),
+            preemptions: vec![],
+            peripheral_writes: vec![],
+            call_stack: vec![],
+            profile: Profile::default(),
+            function_spans: FunctionSpans::default(),
+            output: String::new(),
+            exit_status: None,
             registers,
             pc_register: pc_reg,
             flags,
@@ -109,12 +207,106 @@ impl GAState {
             has_jumped: false,
             last_instruction: None,
             last_pc: pc_reg,
+            last_call_stack: vec![],
+            last_user_pc: None,
             count_cycles: true,
             continue_in_instruction: None,
             current_instruction: None,
+            conditional_execution: vec![],
+            in_conditional_block: false,
+            active_exceptions: vec![],
+            banked_stack_pointer: ctx.unconstrained(ptr_size, "PSP"),
+            visits: HashMap::new(),
         })
     }
 
+    /// Count a execution of the instruction at `pc` and return how many times it has been
+    /// executed on this path.
+    pub fn visit(&mut self, pc: u64) -> usize {
+        let visits = self.visits.entry(pc).or_insert(0);
+        *visits += 1;
+        *visits
+    }
+
+    /// Update the call stack after executing a instruction whose next instruction is at
+    /// `return_address`.
+    ///
+    /// A branch that leaves the return address in the link register is a call and a branch to
+    /// a return address on the call stack is a return from that call and all calls it made.
+    pub fn update_call_stack(&mut self, return_address: u64) {
+        // a hook returning to the link register leaves the Thumb bit in the PC
+        let return_address = return_address & !1;
+        let pc = self.pc_register & !1;
+        if pc == return_address {
+            return;
+        }
+
+        self.return_to_pc();
+
+        let link_register = self.project.get_target().return_address_register();
+        let link = self
+            .registers
+            .get(link_register)
+            .and_then(|link| link.get_constant());
+        if link.map(|link| link & !1) == Some(return_address) {
+            self.call_stack.push(return_address);
+        }
+    }
+
+    /// Pop the calls returned from if the PC is a return address on the call stack, a return
+    /// from the call and all calls it made.
+    pub fn return_to_pc(&mut self) {
+        let pc = self.pc_register & !1;
+        if let Some(depth) = self.call_stack.iter().rposition(|a| *a == pc) {
+            self.call_stack.truncate(depth);
+        }
+    }
+
+    /// Get the source location of the call to the hooked function at the PC. Used to find where
+    /// a panic was called from when a PC hook ends the path in the panic function.
+    pub fn hook_call_site(&self) -> Option<&'static SourceLocation> {
+        let pc = self.pc_register & !1;
+        if !self.hooks.pc.contains_key(&pc) {
+            return None;
+        }
+        let link_register = self.project.get_target().return_address_register();
+        let return_address = self.registers.get(link_register)?.get_constant()? & !1;
+        // the call is the instruction before the return address
+        self.project
+            .get_source_location(return_address.checked_sub(1)?)
+    }
+
+    /// Get the backtrace of the path from the innermost function to the outermost, the call
+    /// sites are found from the return addresses on the call stack.
+    pub fn backtrace(&self) -> Vec<LineTrace> {
+        let pc = self.pc_register & !1;
+        // a hooked function is never executed so the path is at its first instruction
+        let current = if self.hooks.pc.contains_key(&pc) {
+            pc
+        } else {
+            self.last_pc
+        };
+        let call_sites = self
+            .call_stack
+            .iter()
+            .rev()
+            .filter_map(|return_address| return_address.checked_sub(1));
+
+        std::iter::once(current)
+            .chain(call_sites)
+            .map(|address| LineTrace {
+                function_name: match self.project.get_function_name(address) {
+                    Some(name) => name.to_owned(),
+                    None => format!("{:#x}", address),
+                },
+                line: self
+                    .project
+                    .get_source_location(address)
+                    .map(ToString::to_string),
+            })
+            .collect()
+    }
+
     pub fn reset_has_jumped(&mut self) {
         self.has_jumped = false;
     }
@@ -128,6 +320,27 @@ impl GAState {
         self.has_jumped
     }
 
+    /// Set the conditions for the following instructions, one condition per instruction.
+    pub fn set_conditional_execution(&mut self, conditions: Vec<Condition>) {
+        self.conditional_execution = conditions;
+    }
+
+    /// Get the condition for the next instruction if it is in a conditional block.
+    pub fn next_conditional_execution(&mut self) -> Option<Condition> {
+        if self.conditional_execution.is_empty() {
+            self.in_conditional_block = false;
+            None
+        } else {
+            self.in_conditional_block = true;
+            Some(self.conditional_execution.remove(0))
+        }
+    }
+
+    /// Indicates if the current instruction is part of a conditional block.
+    pub fn in_conditional_block(&self) -> bool {
+        self.in_conditional_block
+    }
+
     /// Increments the instruction counter by one.
     pub fn increment_instruction_count(&mut self) {
         self.instruction_counter += 1;
@@ -158,6 +371,116 @@ impl GAState {
             self.last_instruction
         );
         self.cycle_count += cycles;
+        if self.last_instruction.is_some() {
+            self.profile_instruction(cycles);
+        }
+    }
+
+    /// Record that the instruction at `pc` starts at the current cycle count in the calls made by
+    /// the path.
+    pub fn record_function_span(&mut self, pc: u64) {
+        let project = self.project;
+        let function_name = |address| {
+            project
+                .get_function_name(address)
+                .unwrap_or(UNKNOWN_FUNCTION)
+        };
+        let mut functions: Vec<&str> = self
+            .call_stack
+            .iter()
+            .filter_map(|return_address| return_address.checked_sub(1))
+            .map(function_name)
+            .collect();
+        functions.push(function_name(pc));
+        self.function_spans.update(&functions, self.cycle_count);
+    }
+
+    /// Add the cycles of the last instruction to the function containing it and the functions
+    /// on the call stack.
+    fn profile_instruction(&mut self, cycles: usize) {
+        let project = self.project;
+        let function_name = |address| {
+            project
+                .get_function_name(address)
+                .unwrap_or(UNKNOWN_FUNCTION)
+        };
+        let callers = self
+            .last_call_stack
+            .iter()
+            .rev()
+            .filter_map(|return_address| return_address.checked_sub(1))
+            .map(function_name);
+        self.profile.add(
+            function_name(self.last_pc),
+            callers,
+            Cost {
+                cycles,
+                instructions: 1,
+            },
+        );
+    }
+
+    /// Get the cycle count at the end of the instruction being executed. Between instructions,
+    /// such as in a PC hook, this is the current cycle count.
+    pub fn cycle_count_after_instruction(&self) -> usize {
+        let cycles = match &self.current_instruction {
+            Some(i) if self.count_cycles => match i.max_cycle {
+                super::instruction::CycleCount::Value(v) => v,
+                super::instruction::CycleCount::Function(f) => f(self),
+            },
+            _ => 0,
+        };
+        self.cycle_count + cycles
+    }
+
+    /// Begin the span `name`. The span includes the instruction being executed.
+    pub fn span_begin(&mut self, name: impl Into<String>) {
+        self.timeline
+            .push(self.cycle_count, TimingEventKind::SpanBegin(name.into()));
+    }
+
+    /// End the innermost open span `name`. The span includes the instruction being executed.
+    pub fn span_end(&mut self, name: impl Into<String>) {
+        self.timeline.push(
+            self.cycle_count_after_instruction(),
+            TimingEventKind::SpanEnd(name.into()),
+        );
+    }
+
+    /// Add a instant marker `name` at the start of the instruction being executed.
+    pub fn mark(&mut self, name: impl Into<String>) {
+        self.timeline
+            .push(self.cycle_count, TimingEventKind::Instant(name.into()));
+    }
+
+    /// Add metadata describing the path.
+    pub fn add_metadata(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.timeline.push(
+            self.cycle_count,
+            TimingEventKind::Metadata(key.into(), value.into()),
+        );
+    }
+
+    /// Get the number of wait states of the last load or store, or the largest number of wait
+    /// states of any memory region if the address is not known.
+    pub fn last_access_wait_states(&self, access: Access) -> usize {
+        self.project
+            .get_memory_map()
+            .wait_states(self.last_access_address(), access)
+    }
+
+    /// Check if the last load or store was done through the single cycle I/O port.
+    pub fn last_access_is_io_port(&self) -> bool {
+        self.project
+            .get_memory_map()
+            .is_io_port(self.last_access_address())
+    }
+
+    /// Get the address of the last load or store if it is known.
+    fn last_access_address(&self) -> Option<u64> {
+        self.registers
+            .get("LastAddr")
+            .and_then(|address| address.get_constant())
     }
 
     /// Update the last instruction that was executed.
@@ -165,6 +488,242 @@ impl GAState {
         self.last_instruction = Some(instruction);
     }
 
+    /// Start execution of the entry function as if it was the handler for `exception_number`.
+    ///
+    /// The current return address is used as the address the exception returns to, so the
+    /// path ends when the handler performs an exception return.
+    pub fn start_as_exception_handler(&mut self, exception_number: u32) -> Result<()> {
+        let handler = self.pc_register;
+        let return_address_register = self.project.get_target().return_address_register();
+        let return_address = self.get_register(return_address_register.to_owned())?;
+        self.set_register("PC".to_owned(), return_address)?;
+        self.enter_exception(exception_number, handler)
+    }
+
+    /// Take the exception `exception_number` and continue execution at `handler`.
+    ///
+    /// Pushes the basic exception frame (R0-R3, R12, LR, return address and xPSR) to the stack,
+    /// sets LR to the matching EXC_RETURN value and adds the exception entry latency to the
+    /// cycle count.
+    pub fn enter_exception(&mut self, exception_number: u32, handler: u64) -> Result<()> {
+        let target = self.project.get_target();
+        let entry_cycles = target.exception_entry_cycles()?;
+        let word_size = self.project.get_word_size();
+        let sp_name = target.stack_pointer_register().to_owned();
+        debug!(
+            "Entering exception {} with handler at {:#X}",
+            exception_number, handler
+        );
+
+        // The frame is always 8 byte aligned, a symbolic stack pointer is assumed to be aligned.
+        let mut sp = self.get_register(sp_name.clone())?;
+        let mut xpsr_extra = XPSR_THUMB;
+        if let Some(sp_value) = sp.get_constant() {
+            if sp_value & 0b100 != 0 {
+                sp = sp.sub(&self.ctx.from_u64(4, word_size));
+                xpsr_extra |= XPSR_STACK_ALIGN;
+            }
+        }
+
+        let interrupted_ipsr = self.active_exceptions.last().copied().unwrap_or(0) as u64;
+        let xpsr = self
+            .flags_to_xpsr()
+            .or(&self.ctx.from_u64(xpsr_extra | interrupted_ipsr, word_size));
+        let return_address = self.ctx.from_u64(self.pc_register, word_size);
+        let lr = self.get_register("LR".to_owned())?;
+        let mut frame = vec![];
+        for register in ["R0", "R1", "R2", "R3", "R12"] {
+            frame.push(self.get_register(register.to_owned())?);
+        }
+        frame.push(lr);
+        frame.push(return_address);
+        frame.push(xpsr);
+
+        let frame_size = self.ctx.from_u64(4 * frame.len() as u64, word_size);
+        let sp = sp.sub(&frame_size);
+        for (i, value) in frame.into_iter().enumerate() {
+            let address = sp.add(&self.ctx.from_u64(4 * i as u64, word_size));
+            self.write_word_to_memory(&address, value)?;
+        }
+        self.set_register(sp_name, sp)?;
+
+        // Return to thread mode if no other exception is active, otherwise return to handler mode.
+        let exc_return = match (self.in_handler_mode(), self.process_stack_active()) {
+            (true, _) => 0xFFFF_FFF1,
+            (false, true) => 0xFFFF_FFFD,
+            (false, false) => 0xFFFF_FFF9,
+        };
+        self.set_register("LR".to_owned(), self.ctx.from_u64(exc_return, word_size))?;
+
+        // Handlers always use the main stack.
+        self.select_stack_pointer(false)?;
+        self.active_exceptions.push(exception_number);
+        self.registers.insert(
+            "IPSR".to_owned(),
+            self.ctx.from_u64(exception_number as u64, word_size),
+        );
+        self.set_register("PC".to_owned(), self.ctx.from_u64(handler, word_size))?;
+
+        if self.count_cycles {
+            self.cycle_count += entry_cycles;
+        }
+        Ok(())
+    }
+
+    /// Indicates if the PC holds a EXC_RETURN value while an exception is active, meaning the
+    /// last instruction was a exception return.
+    pub fn is_exception_return(&self) -> bool {
+        !self.active_exceptions.is_empty() && self.pc_register & 0xF000_0000 == 0xF000_0000
+    }
+
+    /// Return from the currently active exception.
+    ///
+    /// Pops the exception frame from the stack, restores the registers and flags and adds the
+    /// exception return latency to the cycle count.
+    pub fn exception_return(&mut self) -> Result<()> {
+        let target = self.project.get_target();
+        let return_cycles = target.exception_return_cycles()?;
+        let word_size = self.project.get_word_size();
+        let sp_name = target.stack_pointer_register().to_owned();
+        let exception_number = self.active_exceptions.pop();
+        debug!("Returning from exception {:?}", exception_number);
+
+        // Bit 2 of EXC_RETURN selects the stack the frame is popped from.
+        self.select_stack_pointer(self.pc_register & 0b100 != 0)?;
+        let sp = self.get_register(sp_name.clone())?;
+        let mut frame = vec![];
+        for i in 0..8 {
+            let address = sp.add(&self.ctx.from_u64(4 * i, word_size));
+            frame.push(self.read_word_from_memory(&address)?);
+        }
+        let xpsr = frame.pop().unwrap();
+        let return_address = frame.pop().unwrap();
+        for (register, value) in ["R0", "R1", "R2", "R3", "R12", "LR"].iter().zip(frame) {
+            self.set_register(register.to_string(), value)?;
+        }
+
+        let mut sp = sp.add(&self.ctx.from_u64(32, word_size));
+        let realigned = xpsr
+            .and(&self.ctx.from_u64(XPSR_STACK_ALIGN, word_size))
+            .get_constant()
+            .unwrap_or(0);
+        if realigned != 0 {
+            sp = sp.add(&self.ctx.from_u64(4, word_size));
+        }
+        self.set_register(sp_name, sp)?;
+
+        for (flag, bit) in XPSR_FLAGS {
+            self.set_flag(flag.to_owned(), xpsr.slice(bit, bit));
+        }
+        let ipsr = self.active_exceptions.last().copied().unwrap_or(0) as u64;
+        self.registers
+            .insert("IPSR".to_owned(), self.ctx.from_u64(ipsr, word_size));
+        self.set_register("PC".to_owned(), return_address)?;
+
+        if self.count_cycles {
+            self.cycle_count += return_cycles;
+        }
+        Ok(())
+    }
+
+    /// Get the priority of the currently executing code, lower values are more urgent.
+    /// Thread mode has a lower priority than all configurable priorities.
+    fn execution_priority(&self) -> i32 {
+        self.active_exceptions
+            .iter()
+            .map(|exception_number| {
+                match self
+                    .project
+                    .get_interrupts()
+                    .iter()
+                    .find(|i| i.exception_number == *exception_number)
+                {
+                    Some(interrupt) => interrupt.priority as i32,
+                    // Reset, NMI and HardFault have fixed negative priorities.
+                    None => match exception_number {
+                        1 => -3,
+                        2 => -2,
+                        3 => -1,
+                        _ => 0,
+                    },
+                }
+            })
+            .min()
+            .unwrap_or(256)
+    }
+
+    /// Get the condition for `interrupt` to preempt the execution at the current instruction
+    /// boundary.
+    ///
+    /// Returns `None` if the interrupt can not be taken here, either because it has already been
+    /// taken in this path, its priority is not high enough or the current address is not a
+    /// instruction boundary that can be interrupted.
+    pub fn preemption_condition(&mut self, interrupt: &InterruptHandler) -> Option<DExpr> {
+        let already_taken = self
+            .preemptions
+            .iter()
+            .any(|p| p.exception_number == interrupt.exception_number);
+        // The IT state is not part of the stacked xPSR so do not interrupt a IT block.
+        if already_taken
+            || !self.conditional_execution.is_empty()
+            || self.hooks.pc.contains_key(&(self.pc_register & !1))
+            || interrupt.priority as i32 >= self.execution_priority()
+        {
+            return None;
+        }
+
+        // Registers that have not been written has their reset value of zero.
+        let word_size = self.project.get_word_size();
+        let zero = self.ctx.zero(word_size);
+        let primask = self.registers.get("PRIMASK").unwrap_or(&zero);
+        let faultmask = self.registers.get("FAULTMASK").unwrap_or(&zero);
+        let basepri = self.registers.get("BASEPRI").unwrap_or(&zero);
+
+        let primask_clear = primask
+            .or(faultmask)
+            .and(&self.ctx.from_u64(1, word_size))
+            ._eq(&zero);
+        let basepri = basepri.and(&self.ctx.from_u64(0xff, word_size));
+        let priority = self.ctx.from_u64(interrupt.priority as u64, word_size);
+        let unmasked = basepri._eq(&zero).or(&priority.ult(&basepri));
+        let condition = primask_clear.and(&unmasked).simplify();
+
+        match condition.get_constant_bool() {
+            Some(false) => None,
+            _ => Some(condition),
+        }
+    }
+
+    /// Preempt the execution at the current instruction boundary with `interrupt`.
+    pub fn preempt(&mut self, interrupt: &InterruptHandler) -> Result<()> {
+        // Count the last instruction before the exception entry.
+        self.increment_cycle_count();
+        self.last_instruction = None;
+
+        debug!(
+            "Interrupt {} preempts execution at {:#X}",
+            interrupt.name, self.pc_register
+        );
+        self.preemptions.push(Preemption {
+            handler: interrupt.name.to_owned(),
+            exception_number: interrupt.exception_number,
+            pc: self.pc_register,
+            cycle: self.cycle_count,
+        });
+        self.enter_exception(interrupt.exception_number, interrupt.address)
+    }
+
+    /// Pack the condition flags into their position in the xPSR.
+    fn flags_to_xpsr(&mut self) -> DExpr {
+        let word_size = self.project.get_word_size();
+        let mut xpsr = self.ctx.zero(word_size);
+        for (flag, bit) in XPSR_FLAGS {
+            let value = self.get_flag(flag.to_owned()).unwrap().zero_ext(word_size);
+            xpsr = xpsr.or(&value.sll(&self.ctx.from_u64(bit as u64, word_size)));
+        }
+        xpsr
+    }
+
     /// Create a state used for testing.
     pub fn create_test_state(
         project: &'static Project,
@@ -185,13 +744,17 @@ impl GAState {
         registers.insert("PC".to_owned(), pc_expr);
 
         let sp_expr = ctx.from_u64(sp_reg, ptr_size);
-        registers.insert("SP".to_owned(), sp_expr);
+        registers.insert(
+            project.get_target().stack_pointer_register().to_owned(),
+            sp_expr,
+        );
 
         let mut flags = HashMap::new();
         flags.insert("N".to_owned(), ctx.unconstrained(1, "flags.N"));
         flags.insert("Z".to_owned(), ctx.unconstrained(1, "flags.Z"));
         flags.insert("C".to_owned(), ctx.unconstrained(1, "flags.C"));
         flags.insert("V".to_owned(), ctx.unconstrained(1, "flags.V"));
+        flags.insert("Q".to_owned(), ctx.from_bool(false));
 
         GAState {
             project,
@@ -200,7 +763,16 @@ impl GAState {
             marked_symbolic: Vec::new(),
             memory,
             cycle_count: 0,
-            cycle_laps: vec![],
+            timeline: Timeline::default(),
+            hooks: project.get_hooks().clone(),
+            observers: project.get_observers().to_vec(),
+            preemptions: vec![],
+            peripheral_writes: vec![],
+            call_stack: vec![],
+            profile: Profile::default(),
+            function_spans: FunctionSpans::default(),
+            output: String::new(),
+            exit_status: None,
             registers,
             pc_register: pc_reg,
             flags,
@@ -208,9 +780,16 @@ impl GAState {
             has_jumped: false,
             last_instruction: None,
             last_pc: pc_reg,
+            last_call_stack: vec![],
+            last_user_pc: None,
             count_cycles: true,
             continue_in_instruction: None,
             current_instruction: None,
+            conditional_execution: vec![],
+            in_conditional_block: false,
+            active_exceptions: vec![],
+            banked_stack_pointer: ctx.unconstrained(ptr_size, "PSP"),
+            visits: HashMap::new(),
         }
     }
 
@@ -220,32 +799,38 @@ impl GAState {
         if register == "PC" {
             let value = match expr.get_constant() {
                 Some(v) => v,
-                None => {
-                    trace!("not a concrete pc try to generate possible values");
-                    let values: Vec<u64> = match self.constraints.get_values(&expr, 500).unwrap() {
-                        crate::smt::Solutions::Exactly(v) => v
-                            .iter()
-                            .map(|n| match n.get_constant() {
-                                Some(v) => v,
-                                None => todo!("e"),
-                            })
-                            .collect(),
-                        crate::smt::Solutions::AtLeast(_v) => todo!(),
-                    };
-                    trace!("{} possible PC values", values.len());
-                    for v in values {
-                        trace!("Possible PC: {:#X}", v);
+                // instructions branching to a symbolic target are forked per target by the
+                // executor, a hook writing a symbolic PC must have a single possible target
+                None => match self.constraints.get_values(&expr, 1)? {
+                    crate::smt::Solutions::Exactly(v) if !v.is_empty() => {
+                        self.constraints.assert(&expr._eq(&v[0]));
+                        v[0].get_constant().unwrap()
                     }
-                    todo!("handel symbolic branch")
-                }
+                    crate::smt::Solutions::Exactly(_) => return Err(SolverError::Unsat.into()),
+                    crate::smt::Solutions::AtLeast(_) => {
+                        return Err(Limit::FnPtrResolutions(1).into())
+                    }
+                },
             };
             self.pc_register = value;
         }
 
-        match self.project.get_register_write_hook(&register) {
-            Some(hook) => hook(self, expr),
+        match self.hooks.register_write.remove(&register) {
+            Some(mut hook) => {
+                let result = hook.write(self, expr);
+                self.hooks.register_write.insert(register, hook);
+                result
+            }
+            None if is_special_register(&register) => {
+                self.set_special_register(&register, expr)?;
+                // the observers see the value after masking and ignored writes
+                let value = self.get_special_register(&register)?;
+                self.notify(|observer, state| observer.register_write(state, &register, &value));
+                Ok(())
+            }
             None => {
-                self.registers.insert(register, expr);
+                self.registers.insert(register.to_owned(), expr.clone());
+                self.notify(|observer, state| observer.register_write(state, &register, &expr));
                 Ok(())
             }
         }
@@ -254,9 +839,14 @@ impl GAState {
     /// Get the value stored at a register.
     pub fn get_register(&mut self, register: String) -> Result<DExpr> {
         // check register hooks
-        match self.project.get_register_read_hook(&register) {
-            // run hook if found
-            Some(hook) => Ok(hook(self)?),
+        match self.hooks.register_read.remove(&register) {
+            // run hook if found, it is taken out of the hooks while it runs
+            Some(mut hook) => {
+                let result = hook.read(self);
+                self.hooks.register_read.insert(register, hook);
+                result
+            }
+            None if is_special_register(&register) => self.get_special_register(&register),
             // if no hook found read like normal
             None => match self.registers.get(&register) {
                 Some(v) => Ok(v.to_owned()),
@@ -268,7 +858,7 @@ impl GAState {
                     self.marked_symbolic.push(Variable {
                         name: Some(register.to_owned()),
                         value: value.clone(),
-                        ty: ExpressionType::Integer(self.project.get_word_size() as usize),
+                        ty: self.register_type(&register),
                     });
                     self.registers.insert(register.to_owned(), value.to_owned());
                     Ok(value)
@@ -277,6 +867,141 @@ impl GAState {
         }
     }
 
+    /// Write a Arm M-profile special register.
+    ///
+    /// Only the APSR can be written from unprivileged thread mode and the read only parts of the
+    /// program status registers are ignored, as with the MSR instruction.
+    fn set_special_register(&mut self, register: &str, expr: DExpr) -> Result<()> {
+        let word_size = self.project.get_word_size();
+        let privileged = self.in_handler_mode() || self.control() & CONTROL_NPRIV == 0;
+        match register {
+            "APSR" | "IAPSR" | "EAPSR" | "XPSR" => {
+                for (flag, bit) in XPSR_FLAGS {
+                    self.set_flag(flag.to_owned(), expr.slice(bit, bit));
+                }
+            }
+            "IPSR" | "EPSR" | "IEPSR" => (),
+            _ if !privileged => trace!("Ignoring unprivileged write to {}", register),
+            "MSP" | "PSP" => {
+                if (register == "PSP") == self.process_stack_active() {
+                    let sp_name = self.project.get_target().stack_pointer_register();
+                    self.registers.insert(sp_name.to_owned(), expr);
+                } else {
+                    self.banked_stack_pointer = expr;
+                }
+            }
+            "PRIMASK" | "FAULTMASK" => {
+                let value = expr.and(&self.ctx.from_u64(1, word_size));
+                self.registers.insert(register.to_owned(), value);
+            }
+            "BASEPRI" => {
+                let value = expr.and(&self.ctx.from_u64(0xff, word_size));
+                self.registers.insert(register.to_owned(), value);
+            }
+            "BASEPRI_MAX" => {
+                // Only raises the priority, a write of zero is ignored.
+                let zero = self.ctx.zero(word_size);
+                let value = expr.and(&self.ctx.from_u64(0xff, word_size));
+                let basepri = self.get_special_register("BASEPRI")?;
+                let raise = value
+                    ._ne(&zero)
+                    .and(&basepri._eq(&zero).or(&value.ult(&basepri)));
+                let value = raise.ite(&value, &basepri);
+                self.registers.insert("BASEPRI".to_owned(), value);
+            }
+            "CONTROL" => {
+                let mask = match self.project.get_target().core() {
+                    CoreFamily::ArmM(ArmMCore::ArmM4) => 0b111,
+                    _ => 0b11,
+                };
+                let mut value = expr.and(&self.ctx.from_u64(mask, word_size));
+                // The stack pointer can only be selected in thread mode.
+                let process_stack = if self.in_handler_mode() {
+                    value = value.and(&self.ctx.from_u64(!CONTROL_SPSEL, word_size));
+                    false
+                } else {
+                    match value.get_constant() {
+                        Some(control) => control & CONTROL_SPSEL != 0,
+                        None => self.process_stack_active(),
+                    }
+                };
+                self.select_stack_pointer(process_stack)?;
+                self.registers.insert(register.to_owned(), value);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Read a Arm M-profile special register.
+    fn get_special_register(&mut self, register: &str) -> Result<DExpr> {
+        let word_size = self.project.get_word_size();
+        let zero = self.ctx.zero(word_size);
+        let ipsr = self.registers.get("IPSR").unwrap_or(&zero).clone();
+        Ok(match register {
+            "APSR" | "EAPSR" => self.flags_to_xpsr(),
+            "IAPSR" | "XPSR" => self.flags_to_xpsr().or(&ipsr),
+            "IPSR" | "IEPSR" => ipsr,
+            // The execution state bits always read as zero.
+            "EPSR" => zero,
+            "MSP" | "PSP" => {
+                if (register == "PSP") == self.process_stack_active() {
+                    let sp_name = self.project.get_target().stack_pointer_register();
+                    self.get_register(sp_name.to_owned())?
+                } else {
+                    self.banked_stack_pointer.clone()
+                }
+            }
+            "BASEPRI_MAX" => self.registers.get("BASEPRI").unwrap_or(&zero).clone(),
+            _ => self.registers.get(register).unwrap_or(&zero).clone(),
+        })
+    }
+
+    /// Indicates if a exception is being handled.
+    pub fn in_handler_mode(&self) -> bool {
+        !self.active_exceptions.is_empty()
+    }
+
+    /// Get the concrete value of the CONTROL register, a symbolic value is treated as zero.
+    fn control(&self) -> u64 {
+        match self.registers.get("CONTROL") {
+            Some(control) => control.get_constant().unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Indicates if the process stack pointer is the active stack pointer.
+    fn process_stack_active(&self) -> bool {
+        self.control() & CONTROL_SPSEL != 0
+    }
+
+    /// Make the main or process stack pointer the active stack pointer.
+    fn select_stack_pointer(&mut self, process_stack: bool) -> Result<()> {
+        if self.process_stack_active() == process_stack {
+            return Ok(());
+        }
+
+        let sp_name = self.project.get_target().stack_pointer_register();
+        let active = self.get_register(sp_name.to_owned())?;
+        let banked = std::mem::replace(&mut self.banked_stack_pointer, active);
+        self.registers.insert(sp_name.to_owned(), banked);
+
+        let word_size = self.project.get_word_size();
+        let control = self.control() ^ CONTROL_SPSEL;
+        self.registers
+            .insert("CONTROL".to_owned(), self.ctx.from_u64(control, word_size));
+        Ok(())
+    }
+
+    /// Get the type used when displaying the value of a register.
+    pub fn register_type(&self, register: &str) -> ExpressionType {
+        if self.project.get_target().is_float_register(register) {
+            ExpressionType::Float(32)
+        } else {
+            ExpressionType::Integer(self.project.get_word_size() as usize)
+        }
+    }
+
     /// Set the value of a flag.
     pub fn set_flag(&mut self, flag: String, expr: DExpr) {
         trace!("flag {} set to {:?}", flag, expr);
@@ -338,14 +1063,64 @@ impl GAState {
         })
     }
 
+    /// Call `event` for each observer of the path.
+    pub fn notify(&mut self, mut event: impl FnMut(&mut dyn Observer, &GAState)) {
+        if self.observers.is_empty() {
+            return;
+        }
+        // the observers are taken out of the state while they observe it
+        let mut observers = std::mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            event(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    /// Run the intrinsic PC hook at the address in the PC register.
+    pub fn run_intrinsic_hook(&mut self) -> Result<HookResult> {
+        let pc = self.pc_register & !1;
+        if let Some(PCHook::Intrinsic(mut hook)) = self.hooks.pc.remove(&pc) {
+            let result = hook.run(self);
+            self.hooks.pc.insert(pc, PCHook::Intrinsic(hook));
+            return result;
+        }
+        Ok(HookResult::Continue)
+    }
+
+    /// Run the memory read hook on `address` if there is one.
+    pub fn run_memory_read_hook(&mut self, address: u64) -> Option<Result<DExpr>> {
+        let (slot, mut hook) = self.hooks.memory_read.take(address)?;
+        let result = hook.read(self, address);
+        self.hooks.memory_read.restore(slot, hook);
+        Some(result)
+    }
+
+    /// Run the memory write hook on `address` if there is one.
+    pub fn run_memory_write_hook(
+        &mut self,
+        address: u64,
+        value: DExpr,
+        bits: u32,
+    ) -> Option<Result<()>> {
+        let (slot, mut hook) = self.hooks.memory_write.take(address)?;
+        let result = hook.write(self, address, value, bits);
+        self.hooks.memory_write.restore(slot, hook);
+        Some(result)
+    }
+
     /// Get the next instruction based on the address in the PC register.
     pub fn get_next_instruction(&self) -> Result<HookOrInstruction> {
         let pc = self.pc_register & !(0b1); // Not applicable for all architectures TODO: Fix this.;
-        match self.project.get_pc_hook(pc) {
-            Some(hook) => Ok(HookOrInstruction::PcHook(hook)),
-            None => Ok(HookOrInstruction::Instruction(
-                self.project.get_instruction(pc)?,
-            )),
+        match self.hooks.pc.get(&pc) {
+            Some(hook) => Ok(HookOrInstruction::PcHook(hook.clone())),
+            None => {
+                self.project
+                    .get_memory_map()
+                    .check_access(pc, Access::Execute)?;
+                Ok(HookOrInstruction::Instruction(
+                    self.project.get_instruction(pc)?,
+                ))
+            }
         }
     }
 
@@ -357,6 +1132,25 @@ impl GAState {
         Ok(self.memory.write(address, value)?)
     }
 
+    /// Read `len` bytes at `address` as a string. Bytes that are not constant are read as `?`
+    /// and invalid UTF-8 is replaced.
+    pub fn read_string(&self, address: u64, len: u64) -> Result<String> {
+        let mut bytes = vec![];
+        for address in address..address + len {
+            let byte = if self.project.address_in_range(address) {
+                self.project.get_byte(address)?
+            } else {
+                let address = self.ctx.from_u64(address, self.project.get_ptr_size());
+                match self.memory.read(&address, 8)?.get_constant() {
+                    Some(byte) => byte as u8,
+                    None => b'?',
+                }
+            };
+            bytes.push(byte);
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
     /// Read a word form memory. Will respect the endianness of the project.
     pub fn read_word_from_memory(&self, address: &DExpr) -> Result<DExpr> {
         match address.get_constant() {
@@ -404,3 +1198,24 @@ impl GAState {
         }
     }
 }
+
+/// Check if `register` is one of the Arm M-profile special registers accessed with MRS and MSR.
+fn is_special_register(register: &str) -> bool {
+    matches!(
+        register,
+        "APSR"
+            | "IAPSR"
+            | "EAPSR"
+            | "XPSR"
+            | "IPSR"
+            | "EPSR"
+            | "IEPSR"
+            | "MSP"
+            | "PSP"
+            | "PRIMASK"
+            | "BASEPRI"
+            | "BASEPRI_MAX"
+            | "FAULTMASK"
+            | "CONTROL"
+    )
+}
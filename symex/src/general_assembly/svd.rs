@@ -0,0 +1,532 @@
+//! Import of memory mapped peripherals from CMSIS-SVD files.
+//!
+//! Every register described in the SVD file gets a memory read and write hook. A read from a
+//! readable register returns a fresh symbolic value named after the register, such as
+//! `TIMER.TIMERAWL`, where the bits not covered by any field keep their reset value. A read from a
+//! write-only register returns the reset value. Writes to writable registers are recorded in the
+//! path and writes to read-only registers are ignored.
+
+use std::{
+    collections::HashMap,
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use tracing::{debug, trace};
+
+use self::xml::Element;
+use super::{
+    project::MemoryHookAddress,
+    state::{GAState, PeripheralWrite},
+    Result, RunConfig,
+};
+use crate::{
+    elf_util::{ExpressionType, Variable},
+    smt::DExpr,
+};
+
+mod xml;
+
+/// Error when reading a SVD file.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SvdError {
+    #[error("Unable to read SVD file: {0}")]
+    Io(String),
+
+    #[error("Invalid XML: {0}")]
+    InvalidXml(String),
+
+    #[error("Missing <{0}> in <{1}>")]
+    MissingElement(String, String),
+
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+
+    #[error("Invalid access: {0}")]
+    InvalidAccess(String),
+
+    #[error("Peripheral {0} derived from unknown peripheral {1}")]
+    UnknownPeripheral(String, String),
+}
+
+/// Access permitted to a peripheral register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+    WriteOnce,
+    ReadWriteOnce,
+}
+
+impl RegisterAccess {
+    fn parse(access: &str) -> std::result::Result<Self, SvdError> {
+        match access {
+            "read-only" => Ok(RegisterAccess::ReadOnly),
+            "write-only" => Ok(RegisterAccess::WriteOnly),
+            "read-write" => Ok(RegisterAccess::ReadWrite),
+            "writeOnce" => Ok(RegisterAccess::WriteOnce),
+            "read-writeOnce" => Ok(RegisterAccess::ReadWriteOnce),
+            _ => Err(SvdError::InvalidAccess(access.to_owned())),
+        }
+    }
+
+    /// Check if the register can be read.
+    pub fn is_readable(&self) -> bool {
+        !matches!(self, RegisterAccess::WriteOnly | RegisterAccess::WriteOnce)
+    }
+
+    /// Check if the register can be written.
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, RegisterAccess::ReadOnly)
+    }
+}
+
+/// A memory mapped peripheral register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeripheralRegister {
+    /// Name of the register prefixed by the name of its peripheral, `PERIPHERAL.REGISTER`.
+    pub name: String,
+
+    /// Address of the register.
+    pub address: u64,
+
+    /// Size of the register in bits.
+    pub size: u32,
+
+    /// Access permitted to the register.
+    pub access: RegisterAccess,
+
+    /// Value of the register after reset.
+    pub reset_value: u64,
+
+    /// Bits of the register that have a defined reset value.
+    pub reset_mask: u64,
+
+    /// Bits of the register covered by fields, zero if the register has no fields.
+    pub field_mask: u64,
+}
+
+impl PeripheralRegister {
+    /// Check if `address` is one of the bytes of the register.
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.address && address - self.address < self.size as u64 / 8
+    }
+
+    /// Mask of all bits in the register.
+    fn size_mask(&self) -> u64 {
+        match self.size {
+            64.. => u64::MAX,
+            size => (1 << size) - 1,
+        }
+    }
+
+    /// Bits that are read as a fresh symbolic value, the fields of the register and all bits
+    /// without a defined reset value.
+    pub fn symbolic_mask(&self) -> u64 {
+        let fields = match self.field_mask {
+            0 => self.size_mask(),
+            mask => mask,
+        };
+        (fields | !self.reset_mask) & self.size_mask()
+    }
+}
+
+/// A device described by a SVD file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// Name of the device.
+    pub name: String,
+
+    /// All registers of all peripherals of the device.
+    pub registers: Vec<PeripheralRegister>,
+}
+
+impl Device {
+    /// Read a SVD file.
+    pub fn from_path(path: &str) -> std::result::Result<Self, SvdError> {
+        let document = fs::read_to_string(path).map_err(|e| SvdError::Io(e.to_string()))?;
+        Self::parse(&document)
+    }
+
+    /// Parse the contents of a SVD file.
+    pub fn parse(document: &str) -> std::result::Result<Self, SvdError> {
+        let device = xml::parse(document)?;
+        let name = required_text(&device, "name")?.to_owned();
+        let defaults = RegisterProperties::default().inherit(&device)?;
+
+        let peripherals: Vec<&Element> = match device.child("peripherals") {
+            Some(peripherals) => peripherals.children("peripheral").collect(),
+            None => vec![],
+        };
+        let by_name: HashMap<&str, &Element> = peripherals
+            .iter()
+            .filter_map(|peripheral| Some((peripheral.child_text("name")?, *peripheral)))
+            .collect();
+
+        let mut registers = vec![];
+        for peripheral in peripherals.iter() {
+            let peripheral_name = required_text(peripheral, "name")?;
+            let base_address = parse_number(required_text(peripheral, "baseAddress")?)?;
+
+            // a derived peripheral is a copy of another one at a different address
+            let base = match peripheral.attribute("derivedFrom") {
+                Some(base_name) => match by_name.get(base_name) {
+                    Some(base) => Some(*base),
+                    None => {
+                        return Err(SvdError::UnknownPeripheral(
+                            peripheral_name.to_owned(),
+                            base_name.to_owned(),
+                        ))
+                    }
+                },
+                None => None,
+            };
+            let mut properties = defaults.clone();
+            if let Some(base) = base {
+                properties = properties.inherit(base)?;
+            }
+            properties = properties.inherit(peripheral)?;
+
+            let register_list = peripheral
+                .child("registers")
+                .or_else(|| base.and_then(|base| base.child("registers")));
+            if let Some(register_list) = register_list {
+                add_registers(
+                    register_list,
+                    peripheral_name,
+                    base_address,
+                    &properties,
+                    &mut registers,
+                )?;
+            }
+        }
+        debug!("Read {} registers from SVD of {}", registers.len(), name);
+
+        Ok(Device { name, registers })
+    }
+
+    /// Add hooks for all registers of the device.
+    pub fn add_hooks(&self, cfg: &mut RunConfig) {
+        for register in self.registers.iter() {
+            for address in register.address..register.address + register.size as u64 / 8 {
+                cfg.memory_read_hooks
+                    .push((MemoryHookAddress::Single(address), Box::new(read_register)));
+                cfg.memory_write_hooks
+                    .push((MemoryHookAddress::Single(address), Box::new(write_register)));
+            }
+            cfg.peripherals.push(register.clone());
+        }
+    }
+}
+
+/// Counter used to give every symbolic register value a unique name in the solver.
+static READ_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Read hook for peripheral registers.
+fn read_register(state: &mut GAState, address: u64) -> Result<DExpr> {
+    let register = state
+        .project
+        .get_peripheral_register(address)
+        .expect("peripheral hook on a address without a register");
+    trace!("Reading peripheral register {}", register.name);
+
+    let symbolic_mask = register.symbolic_mask();
+    let value = if register.access.is_readable() && symbolic_mask != 0 {
+        let id = READ_COUNT.fetch_add(1, Ordering::Relaxed);
+        let symbol = state
+            .ctx
+            .unconstrained(register.size, &format!("{}#{}", register.name, id));
+        state.marked_symbolic.push(Variable {
+            name: Some(register.name.to_owned()),
+            value: symbol.clone(),
+            ty: ExpressionType::Integer(register.size as usize),
+        });
+        let mask = state.ctx.from_u64(symbolic_mask, register.size);
+        let fixed = state
+            .ctx
+            .from_u64(register.reset_value & !symbolic_mask, register.size);
+        symbol.and(&mask).or(&fixed)
+    } else {
+        state.ctx.from_u64(register.reset_value, register.size)
+    };
+
+    Ok(register_bytes(state, value, address - register.address))
+}
+
+/// Write hook for peripheral registers.
+fn write_register(state: &mut GAState, address: u64, value: DExpr, bits: u32) -> Result<()> {
+    let register = state
+        .project
+        .get_peripheral_register(address)
+        .expect("peripheral hook on a address without a register");
+    if !register.access.is_writable() {
+        trace!("Ignoring write to read-only register {}", register.name);
+        return Ok(());
+    }
+
+    trace!("Writing peripheral register {}", register.name);
+    state.peripheral_writes.push(PeripheralWrite {
+        register: register.name.to_owned(),
+        address,
+        value: Variable {
+            name: Some(register.name.to_owned()),
+            value: value.resize_unsigned(bits),
+            ty: ExpressionType::Integer(bits as usize),
+        },
+        cycle: state.cycle_count,
+    });
+    Ok(())
+}
+
+/// Get the part of a register value starting at byte `offset`, sized to the word size.
+fn register_bytes(state: &GAState, value: DExpr, offset: u64) -> DExpr {
+    let low = offset as u32 * 8;
+    let value = match low {
+        0 => value,
+        _ => value.slice(low, value.len() - 1),
+    };
+    value.resize_unsigned(state.project.get_word_size())
+}
+
+/// Properties of registers that are inherited from the device, peripheral and cluster.
+#[derive(Debug, Clone, Default)]
+struct RegisterProperties {
+    size: Option<u32>,
+    access: Option<RegisterAccess>,
+    reset_value: Option<u64>,
+    reset_mask: Option<u64>,
+}
+
+impl RegisterProperties {
+    /// Get the properties with the ones set in `element` replaced.
+    fn inherit(&self, element: &Element) -> std::result::Result<Self, SvdError> {
+        let mut properties = self.clone();
+        if let Some(size) = element.child_text("size") {
+            properties.size = Some(parse_number(size)? as u32);
+        }
+        if let Some(access) = element.child_text("access") {
+            properties.access = Some(RegisterAccess::parse(access)?);
+        }
+        if let Some(reset_value) = element.child_text("resetValue") {
+            properties.reset_value = Some(parse_number(reset_value)?);
+        }
+        if let Some(reset_mask) = element.child_text("resetMask") {
+            properties.reset_mask = Some(parse_number(reset_mask)?);
+        }
+        Ok(properties)
+    }
+}
+
+/// Add the registers and clusters in `parent` to `registers`.
+fn add_registers(
+    parent: &Element,
+    prefix: &str,
+    base_address: u64,
+    properties: &RegisterProperties,
+    registers: &mut Vec<PeripheralRegister>,
+) -> std::result::Result<(), SvdError> {
+    for element in parent.children.iter() {
+        if element.name != "register" && element.name != "cluster" {
+            continue;
+        }
+
+        let properties = properties.inherit(element)?;
+        let offset = parse_number(required_text(element, "addressOffset")?)?;
+        for (name, address) in dimensions(element)? {
+            let name = format!("{prefix}.{name}");
+            let address = base_address + offset + address;
+
+            if element.name == "cluster" {
+                add_registers(element, &name, address, &properties, registers)?;
+                continue;
+            }
+
+            let size = properties.size.unwrap_or(32);
+            let mut field_mask = 0;
+            if let Some(fields) = element.child("fields") {
+                for field in fields.children("field") {
+                    let (lsb, width) = field_bits(field)?;
+                    field_mask |= (((1u128 << width) - 1) as u64) << lsb;
+                }
+            }
+
+            registers.push(PeripheralRegister {
+                name,
+                address,
+                size,
+                access: properties.access.unwrap_or(RegisterAccess::ReadWrite),
+                reset_value: properties.reset_value.unwrap_or(0),
+                reset_mask: properties.reset_mask.unwrap_or(u64::MAX),
+                field_mask,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Get the name and address offset of each instance of a register or cluster, a element without
+/// `dim` has a single instance.
+fn dimensions(element: &Element) -> std::result::Result<Vec<(String, u64)>, SvdError> {
+    let name = required_text(element, "name")?;
+    let dim = match element.child_text("dim") {
+        Some(dim) => parse_number(dim)?,
+        None => return Ok(vec![(name.to_owned(), 0)]),
+    };
+    let increment = parse_number(required_text(element, "dimIncrement")?)?;
+
+    let indices: Vec<String> = match element.child_text("dimIndex") {
+        Some(index) if index.contains('-') => {
+            let (first, last) = index.split_once('-').unwrap();
+            let first = parse_number(first)?;
+            let last = parse_number(last)?;
+            (first..=last).map(|i| i.to_string()).collect()
+        }
+        Some(index) => index.split(',').map(|i| i.trim().to_owned()).collect(),
+        None => (0..dim).map(|i| i.to_string()).collect(),
+    };
+
+    Ok(indices
+        .iter()
+        .enumerate()
+        .map(|(i, index)| (name.replace("%s", index), i as u64 * increment))
+        .collect())
+}
+
+/// Get the least significant bit and the width of a field.
+fn field_bits(field: &Element) -> std::result::Result<(u32, u32), SvdError> {
+    if let Some(offset) = field.child_text("bitOffset") {
+        let width = match field.child_text("bitWidth") {
+            Some(width) => parse_number(width)?,
+            None => 1,
+        };
+        return Ok((parse_number(offset)? as u32, width as u32));
+    }
+    if let (Some(lsb), Some(msb)) = (field.child_text("lsb"), field.child_text("msb")) {
+        let lsb = parse_number(lsb)? as u32;
+        return Ok((lsb, parse_number(msb)? as u32 - lsb + 1));
+    }
+    let range = required_text(field, "bitRange")?;
+    let invalid = || SvdError::InvalidNumber(range.to_owned());
+    let (msb, lsb) = range
+        .strip_prefix('[')
+        .and_then(|range| range.strip_suffix(']'))
+        .and_then(|range| range.split_once(':'))
+        .ok_or_else(invalid)?;
+    let lsb = parse_number(lsb)? as u32;
+    Ok((lsb, parse_number(msb)? as u32 - lsb + 1))
+}
+
+/// Get the text of a child element that must exist.
+fn required_text<'a>(element: &'a Element, name: &str) -> std::result::Result<&'a str, SvdError> {
+    element
+        .child_text(name)
+        .ok_or_else(|| SvdError::MissingElement(name.to_owned(), element.name.to_owned()))
+}
+
+/// Parse a SVD number, either hexadecimal with `0x`, binary with `#` or decimal.
+fn parse_number(text: &str) -> std::result::Result<u64, SvdError> {
+    let text = text.trim();
+    let result = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix('#') {
+        // don't care bits are read as zero
+        u64::from_str_radix(&binary.replace('x', "0"), 2)
+    } else {
+        text.parse()
+    };
+    result.map_err(|_| SvdError::InvalidNumber(text.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- a small test device -->
+<device schemaVersion="1.1">
+  <name>TEST</name>
+  <size>32</size>
+  <access>read-write</access>
+  <resetValue>0x00000000</resetValue>
+  <resetMask>0xFFFFFFFF</resetMask>
+  <peripherals>
+    <peripheral>
+      <name>TIMER</name>
+      <baseAddress>0x40054000</baseAddress>
+      <registers>
+        <register>
+          <name>TIMEHW</name>
+          <addressOffset>0x0</addressOffset>
+          <access>write-only</access>
+        </register>
+        <register>
+          <name>TIMERAWL</name>
+          <addressOffset>0x28</addressOffset>
+          <access>read-only</access>
+        </register>
+        <register>
+          <name>ALARM%s</name>
+          <addressOffset>0x10</addressOffset>
+          <dim>2</dim>
+          <dimIncrement>4</dimIncrement>
+        </register>
+        <register>
+          <name>DBGPAUSE</name>
+          <addressOffset>0x2c</addressOffset>
+          <resetValue>0x00000007</resetValue>
+          <fields>
+            <field><name>DBG0</name><bitOffset>1</bitOffset><bitWidth>1</bitWidth></field>
+            <field><name>DBG1</name><bitRange>[2:2]</bitRange></field>
+          </fields>
+        </register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="TIMER">
+      <name>TIMER1</name>
+      <baseAddress>0x40058000</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>
+"#;
+
+    #[test]
+    fn test_parse_svd() {
+        let device = Device::parse(SVD).unwrap();
+        assert_eq!(device.name, "TEST");
+        assert_eq!(device.registers.len(), 10);
+
+        let register = |name: &str| {
+            device
+                .registers
+                .iter()
+                .find(|register| register.name == name)
+                .unwrap()
+        };
+
+        let timerawl = register("TIMER.TIMERAWL");
+        assert_eq!(timerawl.address, 0x4005_4028);
+        assert_eq!(timerawl.size, 32);
+        assert_eq!(timerawl.access, RegisterAccess::ReadOnly);
+        assert_eq!(timerawl.symbolic_mask(), 0xffff_ffff);
+
+        assert_eq!(register("TIMER.TIMEHW").access, RegisterAccess::WriteOnly);
+        assert_eq!(register("TIMER.ALARM1").address, 0x4005_4014);
+        assert_eq!(register("TIMER1.ALARM1").address, 0x4005_8014);
+
+        let dbgpause = register("TIMER.DBGPAUSE");
+        assert_eq!(dbgpause.reset_value, 0x7);
+        assert_eq!(dbgpause.field_mask, 0b110);
+        assert_eq!(dbgpause.symbolic_mask(), 0b110);
+        assert!(dbgpause.contains(0x4005_402f));
+        assert!(!dbgpause.contains(0x4005_4030));
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("0x1F"), Ok(0x1f));
+        assert_eq!(parse_number("#101"), Ok(0b101));
+        assert_eq!(parse_number("12"), Ok(12));
+        assert!(parse_number("twelve").is_err());
+    }
+}
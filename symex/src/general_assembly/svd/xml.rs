@@ -0,0 +1,169 @@
+//! A minimal XML reader, enough to read CMSIS-SVD files.
+//!
+//! Only elements, attributes and text are kept. Processing instructions, comments and doctype
+//! declarations are skipped.
+
+use super::SvdError;
+
+/// A XML element.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    /// Get the first child element named `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Get all child elements named `name`.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Get the trimmed text of the first child element named `name`.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+
+    /// Get the value of the attribute `name`.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a XML document and return its root element.
+pub fn parse(document: &str) -> Result<Element, SvdError> {
+    let mut reader = Reader {
+        input: document,
+        position: 0,
+    };
+    // the root element is the child of a virtual document element
+    let mut stack = vec![Element::default()];
+
+    while reader.position < document.len() {
+        let rest = reader.rest();
+        if rest.starts_with("<?") {
+            reader.skip_past("?>")?;
+        } else if rest.starts_with("<!--") {
+            reader.skip_past("-->")?;
+        } else if rest.starts_with("<![CDATA[") {
+            reader.position += "<![CDATA[".len();
+            let text = reader.take_until("]]>")?;
+            stack.last_mut().unwrap().text.push_str(text);
+            reader.position += text.len() + "]]>".len();
+        } else if rest.starts_with("<!") {
+            reader.skip_past(">")?;
+        } else if rest.starts_with("</") {
+            reader.position += 2;
+            let tag = reader.take_until(">")?;
+            reader.position += tag.len() + 1;
+            let name = tag.trim();
+            let element = stack.pop().unwrap();
+            if element.name != name || stack.is_empty() {
+                return Err(SvdError::InvalidXml(format!(
+                    "unexpected closing tag </{name}>"
+                )));
+            }
+            stack.last_mut().unwrap().children.push(element);
+        } else if rest.starts_with('<') {
+            reader.position += 1;
+            let tag = reader.take_until(">")?;
+            reader.position += tag.len() + 1;
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag)?;
+            if self_closing {
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        } else {
+            let text = reader.take_until("<").unwrap_or(rest);
+            reader.position += text.len();
+            stack.last_mut().unwrap().text.push_str(&unescape(text));
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(SvdError::InvalidXml(format!(
+            "unclosed element <{}>",
+            stack.last().unwrap().name
+        )));
+    }
+    match stack.pop().unwrap().children.pop() {
+        Some(root) => Ok(root),
+        None => Err(SvdError::InvalidXml("no root element".to_owned())),
+    }
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    /// Get the text up to `pattern` without consuming it.
+    fn take_until(&self, pattern: &str) -> Result<&'a str, SvdError> {
+        let rest = self.rest();
+        match rest.find(pattern) {
+            Some(end) => Ok(&rest[..end]),
+            None => Err(SvdError::InvalidXml(format!("expected {pattern}"))),
+        }
+    }
+
+    fn skip_past(&mut self, pattern: &str) -> Result<(), SvdError> {
+        let skipped = self.take_until(pattern)?;
+        self.position += skipped.len() + pattern.len();
+        Ok(())
+    }
+}
+
+/// Parse the name and attributes of a start tag.
+fn parse_tag(tag: &str) -> Result<Element, SvdError> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = Element {
+        name: tag[..name_end].to_owned(),
+        ..Default::default()
+    };
+
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let invalid = || SvdError::InvalidXml(format!("invalid attributes in <{tag}>"));
+        let equals = rest.find('=').ok_or_else(invalid)?;
+        let name = rest[..equals].trim().to_owned();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value.chars().next().ok_or_else(invalid)?;
+        if quote != '"' && quote != '\'' {
+            return Err(invalid());
+        }
+        let end = value[1..].find(quote).ok_or_else(invalid)? + 1;
+        element.attributes.push((name, unescape(&value[1..end])));
+        rest = value[end + 1..].trim_start();
+    }
+
+    Ok(element)
+}
+
+/// Replace the predefined entities.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,254 @@
+//! Timing events of a path.
+//!
+//! Hooks record when spans, such as critical sections, begin and end, instant markers and
+//! metadata with the methods on [`GAState`](super::state::GAState). The events are timestamped
+//! with the cycle count of the path and the spans are reconstructed into a tree per path.
+//!
+//! The calls made by the path are also recorded as a tree of spans, see [`FunctionSpans`].
+
+use std::fmt;
+
+/// What happened at a timing event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimingEventKind {
+    /// A span named `0` began.
+    SpanBegin(String),
+
+    /// The innermost open span named `0` ended.
+    SpanEnd(String),
+
+    /// A instant marker.
+    Instant(String),
+
+    /// A key value pair describing the path.
+    Metadata(String, String),
+}
+
+/// A timing event and the cycle it happened at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimingEvent {
+    pub cycle: usize,
+    pub kind: TimingEventKind,
+}
+
+/// A span of cycles and the spans nested in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<Span>,
+}
+
+impl Span {
+    /// Number of cycles in the span.
+    pub fn cycles(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{}: {}..{} ({} cycles)",
+            "",
+            self.name,
+            self.start,
+            self.end,
+            self.cycles(),
+            indent = depth * 2
+        )?;
+        for child in self.children.iter() {
+            child.fmt_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, 0)
+    }
+}
+
+/// Close the innermost open span at `end` and add it to its parent or to `spans`.
+fn close_span(open: &mut Vec<Span>, spans: &mut Vec<Span>, end: usize) {
+    let mut span = open.pop().unwrap();
+    span.end = end;
+    match open.last_mut() {
+        Some(parent) => parent.children.push(span),
+        None => spans.push(span),
+    }
+}
+
+/// The calls made by a path as a tree of spans named after the called functions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionSpans {
+    /// The functions on the call stack, the outermost is first.
+    open: Vec<Span>,
+    spans: Vec<Span>,
+}
+
+impl FunctionSpans {
+    /// Update the spans for a instruction starting at `cycle` in the innermost of `functions`,
+    /// the functions on the call stack from the outermost to the innermost.
+    pub fn update(&mut self, functions: &[&str], cycle: usize) {
+        let common = self
+            .open
+            .iter()
+            .zip(functions)
+            .take_while(|(span, function)| span.name == **function)
+            .count();
+        while self.open.len() > common {
+            close_span(&mut self.open, &mut self.spans, cycle);
+        }
+        for function in &functions[common..] {
+            self.open.push(Span {
+                name: (*function).to_owned(),
+                start: cycle,
+                end: cycle,
+                children: vec![],
+            });
+        }
+    }
+
+    /// Get the tree of spans, the functions still on the call stack end at `end`.
+    pub fn spans(&self, end: usize) -> Vec<Span> {
+        let mut functions = self.clone();
+        while !functions.open.is_empty() {
+            close_span(&mut functions.open, &mut functions.spans, end);
+        }
+        functions.spans
+    }
+}
+
+/// The timing events of a path in the order they happened.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    events: Vec<TimingEvent>,
+}
+
+impl Timeline {
+    pub fn push(&mut self, cycle: usize, kind: TimingEventKind) {
+        self.events.push(TimingEvent { cycle, kind });
+    }
+
+    pub fn events(&self) -> &[TimingEvent] {
+        &self.events
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Get the instant markers and the cycles they happened at.
+    pub fn instants(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.events.iter().filter_map(|event| match &event.kind {
+            TimingEventKind::Instant(name) => Some((event.cycle, name.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Get the metadata key value pairs.
+    pub fn metadata(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.events.iter().filter_map(|event| match &event.kind {
+            TimingEventKind::Metadata(key, value) => Some((key.as_str(), value.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Reconstruct the tree of spans.
+    ///
+    /// A span end closes the innermost open span with the same name and all spans opened inside
+    /// it. Ends without a open span are ignored and spans that are still open are ended at `end`,
+    /// the last cycle of the path.
+    pub fn spans(&self, end: usize) -> Vec<Span> {
+        // the open spans, the outermost is first
+        let mut open: Vec<Span> = vec![];
+        let mut spans = vec![];
+
+        for event in self.events.iter() {
+            match &event.kind {
+                TimingEventKind::SpanBegin(name) => open.push(Span {
+                    name: name.to_owned(),
+                    start: event.cycle,
+                    end: event.cycle,
+                    children: vec![],
+                }),
+                TimingEventKind::SpanEnd(name) => {
+                    if let Some(depth) = open.iter().rposition(|span| &span.name == name) {
+                        while open.len() > depth {
+                            close_span(&mut open, &mut spans, event.cycle);
+                        }
+                    }
+                }
+                TimingEventKind::Instant(_) | TimingEventKind::Metadata(_, _) => (),
+            }
+        }
+        while !open.is_empty() {
+            close_span(&mut open, &mut spans, end);
+        }
+
+        spans
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_function_spans() {
+        let mut functions = FunctionSpans::default();
+        functions.update(&["main"], 0);
+        functions.update(&["main", "f"], 2);
+        functions.update(&["main", "f", "g"], 3);
+        functions.update(&["main"], 5);
+        functions.update(&["main", "f"], 6);
+
+        let spans = functions.spans(8);
+        assert_eq!(spans.len(), 1);
+        assert_eq!((spans[0].start, spans[0].end), (0, 8));
+        let calls: Vec<_> = spans[0]
+            .children
+            .iter()
+            .map(|span| (span.start, span.end, span.children.len()))
+            .collect();
+        assert_eq!(calls, vec![(2, 5, 1), (6, 8, 0)]);
+    }
+
+    #[test]
+    fn test_span_tree() {
+        let mut timeline = Timeline::default();
+        timeline.push(
+            0,
+            TimingEventKind::Metadata("task".to_owned(), "io".to_owned()),
+        );
+        timeline.push(2, TimingEventKind::SpanBegin("a".to_owned()));
+        timeline.push(4, TimingEventKind::SpanBegin("b".to_owned()));
+        timeline.push(5, TimingEventKind::Instant("mark".to_owned()));
+        timeline.push(6, TimingEventKind::SpanEnd("b".to_owned()));
+        timeline.push(7, TimingEventKind::SpanBegin("c".to_owned()));
+        // ending the outer span also ends the inner span
+        timeline.push(9, TimingEventKind::SpanEnd("a".to_owned()));
+        timeline.push(10, TimingEventKind::SpanEnd("a".to_owned()));
+        timeline.push(11, TimingEventKind::SpanBegin("d".to_owned()));
+
+        let spans = timeline.spans(20);
+        assert_eq!(spans.len(), 2);
+        let a = &spans[0];
+        assert_eq!((a.start, a.end), (2, 9));
+        let children: Vec<_> = a
+            .children
+            .iter()
+            .map(|span| (span.name.as_str(), span.start, span.end))
+            .collect();
+        assert_eq!(children, vec![("b", 4, 6), ("c", 7, 9)]);
+        assert_eq!((spans[1].start, spans[1].end), (11, 20));
+
+        assert_eq!(timeline.instants().collect::<Vec<_>>(), vec![(5, "mark")]);
+        assert_eq!(
+            timeline.metadata().collect::<Vec<_>>(),
+            vec![("task", "io")]
+        );
+    }
+}
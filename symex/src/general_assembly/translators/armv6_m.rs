@@ -10,7 +10,8 @@ use tracing::trace;
 use crate::{
     elf_util::{ExpressionType, Variable},
     general_assembly::{
-        instruction::{Condition, CycleCount, Operand},
+        instruction::{Condition, CycleCount, Operand, SpecialInstruction},
+        memory_map::Access,
         project::{MemoryHookAddress, MemoryReadHook, PCHook, RegisterReadHook, RegisterWriteHook},
         state::GAState,
         translator::Translatable,
@@ -23,17 +24,20 @@ type GAOperation = crate::general_assembly::instruction::Operation;
 type ArmCodition = armv6_m_instruction_parser::conditions::Condition;
 
 fn cycle_count_m0plus_core(operation: &Operation) -> CycleCount {
-    // SIO based on the rp2040 make this configurable later
-    let address_max_cycle_function: fn(state: &GAState) -> usize = |state| {
-        let address = match state.registers.get("LastAddr").unwrap().get_constant() {
-            Some(v) => v,
-            None => return 2,
-        };
-
-        if address <= 0xdfffffff && address >= 0xd0000000 {
+    // Accesses through the single cycle I/O port take one cycle, other accesses take two
+    // cycles plus the wait states of the memory region.
+    let load_cycles: fn(state: &GAState) -> usize = |state| {
+        if state.last_access_is_io_port() {
+            1
+        } else {
+            2 + state.last_access_wait_states(Access::Read)
+        }
+    };
+    let store_cycles: fn(state: &GAState) -> usize = |state| {
+        if state.last_access_is_io_port() {
             1
         } else {
-            2
+            2 + state.last_access_wait_states(Access::Write)
         }
     };
     match operation {
@@ -82,23 +86,15 @@ fn cycle_count_m0plus_core(operation: &Operation) -> CycleCount {
         }
 
         // \/\/\/\/ Can be one depending on core implementation and address \/\/\/\/
-        Operation::LDRImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::LDRLiteral { t: _, imm: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::LDRReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::LDRBImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::LDRBReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::LDRHImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::LDRHReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::LDRSBReg { m: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::LDRSH { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
+        Operation::LDRImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRLiteral { t: _, imm: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRBImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRBReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRHImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRHReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRSBReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRSH { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
         // /\/\/\/\ Can be one depending on core implementation and address /\/\/\/\
         Operation::LSLImm { imm: _, m: _, d: _ } => CycleCount::Value(1),
         Operation::LSLReg { m: _, dn: _ } => CycleCount::Value(1),
@@ -140,18 +136,12 @@ fn cycle_count_m0plus_core(operation: &Operation) -> CycleCount {
         Operation::STM { n: _, reg_list } => CycleCount::Value(1 + reg_list.len()),
 
         // \/\/\/\/ Can be one depending on core implementation and address \/\/\/\/
-        Operation::STRImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::STRReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::STRBImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::STRBReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
-        Operation::STRHImm { imm: _, n: _, t: _ } => {
-            CycleCount::Function(address_max_cycle_function)
-        }
-        Operation::STRHReg { m: _, n: _, t: _ } => CycleCount::Function(address_max_cycle_function),
+        Operation::STRImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRBImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRBReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRHImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRHReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
         // /\/\/\/\ Can be one depending on core implementation and address /\/\/\/\
         Operation::SUBImm { imm: _, n: _, d: _ } => CycleCount::Value(1),
         Operation::SUBReg { m: _, n: _, d: _ } => CycleCount::Value(1),
@@ -160,24 +150,23 @@ fn cycle_count_m0plus_core(operation: &Operation) -> CycleCount {
         Operation::SXTB { m: _, d: _ } => CycleCount::Value(1),
         Operation::SXTH { m: _, d: _ } => CycleCount::Value(1),
         Operation::TSTReg { m: _, n: _ } => CycleCount::Value(1),
-        Operation::UDFT1 { imm: _ } => {
-            // generates a undefined exeption just panic for now
-            unimplemented!()
-        }
-        Operation::UDFT2 { imm: _ } => {
-            // generates a undefined exeption just panic for now
-            unimplemented!()
-        }
+        // The exception generated by a undefined instruction is not modeled.
+        Operation::UDFT1 { imm: _ } => CycleCount::Value(1),
+        Operation::UDFT2 { imm: _ } => CycleCount::Value(1),
         Operation::UXTB { m: _, d: _ } => CycleCount::Value(1),
         Operation::UXTH { m: _, d: _ } => CycleCount::Value(1),
-        Operation::WFE => todo!(),
-        Operation::WFI => todo!(),
-        Operation::YIELD => todo!(),
+        Operation::WFE => CycleCount::Value(2),
+        Operation::WFI => CycleCount::Value(2),
+        Operation::YIELD => CycleCount::Value(1),
     }
 }
 
 #[allow(dead_code)]
 fn cycle_count_m0_core(operation: &Operation) -> CycleCount {
+    let load_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Read);
+    let store_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Write);
     match operation {
         Operation::ADCReg { m: _, n: _, d: _ } => CycleCount::Value(1),
         Operation::ADDImm { imm: _, n: _, d: _ } => CycleCount::Value(1),
@@ -222,15 +211,15 @@ fn cycle_count_m0_core(operation: &Operation) -> CycleCount {
             let max_cycle = 1 + reg_list.len();
             CycleCount::Value(max_cycle)
         }
-        Operation::LDRImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRLiteral { t: _, imm: _ } => CycleCount::Value(2),
-        Operation::LDRReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRBImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRBReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRHImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRHReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRSBReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::LDRSH { m: _, n: _, t: _ } => CycleCount::Value(2),
+        Operation::LDRImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRLiteral { t: _, imm: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRBImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRBReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRHImm { imm: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRHReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRSBReg { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
+        Operation::LDRSH { m: _, n: _, t: _ } => CycleCount::Function(load_cycles),
         Operation::LSLImm { imm: _, m: _, d: _ } => CycleCount::Value(1),
         Operation::LSLReg { m: _, dn: _ } => CycleCount::Value(1),
         Operation::LSRImm { imm: _, m: _, d: _ } => CycleCount::Value(1),
@@ -269,12 +258,12 @@ fn cycle_count_m0_core(operation: &Operation) -> CycleCount {
         Operation::SBCReg { m: _, dn: _ } => CycleCount::Value(1),
         Operation::SEV => CycleCount::Value(1),
         Operation::STM { n: _, reg_list } => CycleCount::Value(1 + reg_list.len()),
-        Operation::STRImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::STRReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::STRBImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::STRBReg { m: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::STRHImm { imm: _, n: _, t: _ } => CycleCount::Value(2),
-        Operation::STRHReg { m: _, n: _, t: _ } => CycleCount::Value(2),
+        Operation::STRImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRBImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRBReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRHImm { imm: _, n: _, t: _ } => CycleCount::Function(store_cycles),
+        Operation::STRHReg { m: _, n: _, t: _ } => CycleCount::Function(store_cycles),
         Operation::SUBImm { imm: _, n: _, d: _ } => CycleCount::Value(1),
         Operation::SUBReg { m: _, n: _, d: _ } => CycleCount::Value(1),
         Operation::SUBImmSP { imm: _ } => CycleCount::Value(1),
@@ -282,19 +271,14 @@ fn cycle_count_m0_core(operation: &Operation) -> CycleCount {
         Operation::SXTB { m: _, d: _ } => CycleCount::Value(1),
         Operation::SXTH { m: _, d: _ } => CycleCount::Value(1),
         Operation::TSTReg { m: _, n: _ } => CycleCount::Value(1),
-        Operation::UDFT1 { imm: _ } => {
-            // generates a undefined exeption just panic for now
-            unimplemented!()
-        }
-        Operation::UDFT2 { imm: _ } => {
-            // generates a undefined exeption just panic for now
-            unimplemented!()
-        }
+        // The exception generated by a undefined instruction is not modeled.
+        Operation::UDFT1 { imm: _ } => CycleCount::Value(1),
+        Operation::UDFT2 { imm: _ } => CycleCount::Value(1),
         Operation::UXTB { m: _, d: _ } => CycleCount::Value(1),
         Operation::UXTH { m: _, d: _ } => CycleCount::Value(1),
-        Operation::WFE => todo!(),
-        Operation::WFI => todo!(),
-        Operation::YIELD => todo!(),
+        Operation::WFE => CycleCount::Value(2),
+        Operation::WFI => CycleCount::Value(2),
+        Operation::YIELD => CycleCount::Value(1),
     }
 }
 
@@ -513,7 +497,8 @@ impl Translatable for Instruction {
                     },
                 ]
             }
-            Operation::BKPT { imm: _ } => vec![],
+            Operation::BKPT { imm: 0xab } => vec![GAOperation::Semihosting],
+            Operation::BKPT { imm: _ } => vec![GAOperation::Special(SpecialInstruction::Bkpt)],
             Operation::BL { imm } => vec![
                 GAOperation::Move {
                     destination: Operand::Local("PC".to_owned()),
@@ -626,10 +611,12 @@ impl Translatable for Instruction {
                     },
                 ]
             }
-            Operation::CPS { im: _ } => {
-                // change processor state do nothig for now but should probably be modeled
+            Operation::CPS { im } => {
                 // in armv6-m it is only used to enable disable interupts
-                vec![]
+                vec![GAOperation::Move {
+                    destination: Operand::Register("PRIMASK".to_owned()),
+                    source: Operand::Immidiate(DataWord::Word32(*im as u32)),
+                }]
             }
             Operation::CPY => {
                 // this is not a real instruction is equvelatn to mov
@@ -1281,11 +1268,11 @@ impl Translatable for Instruction {
                     GAOperation::Sror {
                         destination: dn.clone(),
                         operand: dn.clone(),
-                        shift: shift,
+                        shift: shift.clone(),
                     },
                     GAOperation::SetNFlag(dn.clone()),
                     GAOperation::SetZFlag(dn.clone()),
-                    GAOperation::SetCFlagRor(dn),
+                    GAOperation::SetCFlagRor { result: dn, shift },
                 ]
             }
             Operation::RSBImm { n, d } => {
@@ -1356,10 +1343,7 @@ impl Translatable for Instruction {
                     },
                 ]
             }
-            Operation::SEV => {
-                // sends a hint event to all cores, multicore is not modeled so do nothing for now
-                vec![]
-            }
+            Operation::SEV => vec![GAOperation::Special(SpecialInstruction::Sev)],
             Operation::STM { n, reg_list } => {
                 let n = arm_register_to_ga_operand(n);
                 let addr = Operand::Local("addr".to_owned());
@@ -1637,13 +1621,8 @@ impl Translatable for Instruction {
                     GAOperation::SetZFlag(result),
                 ]
             }
-            Operation::UDFT1 { imm: _ } => {
-                // generates a undefined exeption just panic for now
-                unimplemented!()
-            }
-            Operation::UDFT2 { imm: _ } => {
-                // generates a undefined exeption just panic for now
-                unimplemented!()
+            Operation::UDFT1 { imm: _ } | Operation::UDFT2 { imm: _ } => {
+                vec![GAOperation::Special(SpecialInstruction::Udf)]
             }
             Operation::UXTB { m, d } => vec![GAOperation::ZeroExtend {
                 destination: arm_register_to_ga_operand(d),
@@ -1655,9 +1634,9 @@ impl Translatable for Instruction {
                 operand: arm_register_to_ga_operand(m),
                 bits: 16,
             }],
-            Operation::WFE => todo!(),
-            Operation::WFI => todo!(),
-            Operation::YIELD => todo!(),
+            Operation::WFE => vec![GAOperation::Special(SpecialInstruction::Wfe)],
+            Operation::WFI => vec![GAOperation::Special(SpecialInstruction::Wfi)],
+            Operation::YIELD => vec![GAOperation::Special(SpecialInstruction::Yield)],
         };
 
         let instruction_width = match self.width {
@@ -1697,9 +1676,39 @@ impl Translatable for Instruction {
             Ok(())
         };
 
+        // Matches every instantiation except `f32` and `f64`, those are hooked below.
         cfg.pc_hooks.push((
-            Regex::new(r"^symbolic_size<.+>$").unwrap(),
-            PCHook::Intrinsic(symbolic_sized),
+            Regex::new(r"^symbolic_size<([^f].*|f|f[^36].*|f3([^2].*)?|f6([^4].*)?|f(32|64).+)>$")
+                .unwrap(),
+            PCHook::intrinsic(symbolic_sized),
+        ));
+
+        // Floating point values are displayed as floats.
+        let symbolic_sized_float = |state: &mut GAState| {
+            let value_ptr = state.get_register("R0".to_owned())?;
+            let size = state.get_register("R1".to_owned())?.get_constant().unwrap() * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Float(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let lr = state.get_register("LR".to_owned())?;
+            state.set_register("PC".to_owned(), lr)?;
+            Ok(())
+        };
+
+        cfg.pc_hooks.push((
+            Regex::new(r"^symbolic_size<f(32|64)>$").unwrap(),
+            PCHook::intrinsic(symbolic_sized_float),
         ));
 
         let read_pc: RegisterReadHook = |state| {
@@ -1710,16 +1719,20 @@ impl Translatable for Instruction {
 
         let write_pc: RegisterWriteHook = |state, value| state.set_register("PC".to_owned(), value);
 
-        cfg.register_read_hooks.push(("PC+".to_owned(), read_pc));
-        cfg.register_write_hooks.push(("PC+".to_owned(), write_pc));
+        cfg.register_read_hooks
+            .push(("PC+".to_owned(), Box::new(read_pc)));
+        cfg.register_write_hooks
+            .push(("PC+".to_owned(), Box::new(write_pc)));
 
         // reset allways done
         let read_reset_done: MemoryReadHook = |state, _addr| {
             let value = state.ctx.from_u64(0xffff_ffff, 32);
             Ok(value)
         };
-        cfg.memory_read_hooks
-            .push((MemoryHookAddress::Single(0x4000c008), read_reset_done));
+        cfg.memory_read_hooks.push((
+            MemoryHookAddress::Single(0x4000c008),
+            Box::new(read_reset_done),
+        ));
     }
 }
 
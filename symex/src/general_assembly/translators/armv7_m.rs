@@ -0,0 +1,2004 @@
+//! Translator for the armv7-m instruction set
+
+pub mod decoder;
+
+use regex::Regex;
+use tracing::trace;
+
+use crate::{
+    elf_util::{ExpressionType, Variable},
+    general_assembly::{
+        instruction::{Comparison, Condition, CycleCount, Operand, SpecialInstruction},
+        memory_map::Access,
+        project::{target_config::ArmMCore, PCHook, RegisterReadHook, RegisterWriteHook},
+        state::GAState,
+        translator::Translatable,
+        DataWord, RunConfig,
+    },
+};
+
+use self::decoder::{
+    Address, DataProcessingOp, FloatArithOp, FloatConversion, FloatRegister, ImmShift, Instruction,
+    InstructionWidth, Offset, Operation, Register, SaturatingOp, SetFlags, Shift, ShifterOperand,
+    SpecialRegister,
+};
+
+type GAInstruction = crate::general_assembly::instruction::Instruction;
+type GAOperation = crate::general_assembly::instruction::Operation;
+type ArmCondition = decoder::Condition;
+
+/// A decoded instruction together with its cycle count on the core it executes on.
+#[derive(Debug, Clone)]
+pub struct Armv7MInstruction {
+    instruction: Instruction,
+    max_cycle: CycleCount,
+}
+
+/// The decoded operation is not implemented by the core.
+#[derive(Clone, Debug, thiserror::Error)]
+#[error("{operation:?} is not implemented by the {core:?} core")]
+pub struct UnsupportedOperation {
+    pub operation: Operation,
+    pub core: ArmMCore,
+}
+
+impl Armv7MInstruction {
+    /// Pairs `instruction` with the core it executes on, fails if the core does not implement
+    /// the operation.
+    pub fn new(instruction: Instruction, core: ArmMCore) -> Result<Self, UnsupportedOperation> {
+        let max_cycle = match core {
+            ArmMCore::ArmM4 => cycle_count_m4_core(&instruction.operation),
+            ArmMCore::ArmM23 => cycle_count_m23_core(&instruction.operation),
+            _ => cycle_count_m3_core(&instruction.operation),
+        };
+        match max_cycle {
+            Some(max_cycle) => Ok(Self {
+                instruction,
+                max_cycle,
+            }),
+            None => Err(UnsupportedOperation {
+                operation: instruction.operation,
+                core,
+            }),
+        }
+    }
+}
+
+/// Number of cycles needed to refill the pipeline after a branch.
+/// Ranges from 1 to 3 depending on the alignment and width of the target instruction,
+/// the worst case is used.
+const PIPELINE_REFILL: usize = 3;
+
+/// Returns `None` if the core does not implement the operation.
+fn cycle_count_m3_core(operation: &Operation) -> Option<CycleCount> {
+    let branch_max_cycle: fn(state: &GAState) -> usize = |state| {
+        if state.get_has_jumped() {
+            1 + PIPELINE_REFILL
+        } else {
+            1
+        }
+    };
+    let load_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Read);
+    let load_pc_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + PIPELINE_REFILL + state.last_access_wait_states(Access::Read);
+    let store_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Write);
+    Some(match operation {
+        Operation::DataProcessing { d, .. } => {
+            let max_cycle = if *d == Some(Register::PC) {
+                1 + PIPELINE_REFILL
+            } else {
+                1
+            };
+            CycleCount::Value(max_cycle)
+        }
+        Operation::ShiftRegister { .. } => CycleCount::Value(1),
+        Operation::Adr { .. } => CycleCount::Value(1),
+        Operation::MovTop { .. } => CycleCount::Value(1),
+        Operation::Mul { .. } => CycleCount::Value(1),
+        Operation::Mla { .. } => CycleCount::Value(2),
+        Operation::Mls { .. } => CycleCount::Value(2),
+        Operation::MulLong { accumulate, .. } => {
+            // Terminates early depending on the size of the operands.
+            let max_cycle = if *accumulate { 7 } else { 5 };
+            CycleCount::Value(max_cycle)
+        }
+        Operation::Div { .. } => {
+            // Terminates early depending on the size of the operands.
+            CycleCount::Value(12)
+        }
+        Operation::Bfi { .. } => CycleCount::Value(1),
+        Operation::Bfc { .. } => CycleCount::Value(1),
+        Operation::Bfx { .. } => CycleCount::Value(1),
+        Operation::Saturate { .. } => CycleCount::Value(1),
+        Operation::SaturatingArith { .. } => CycleCount::Value(1),
+        Operation::Extend { .. } => CycleCount::Value(1),
+        Operation::Clz { .. } => CycleCount::Value(1),
+        Operation::Rbit { .. } => CycleCount::Value(1),
+        Operation::Rev { .. } => CycleCount::Value(1),
+        Operation::Rev16 { .. } => CycleCount::Value(1),
+        Operation::Revsh { .. } => CycleCount::Value(1),
+        Operation::B { .. } => CycleCount::Function(branch_max_cycle),
+        Operation::Bl { .. } => CycleCount::Value(1 + PIPELINE_REFILL),
+        Operation::Blx { .. } => CycleCount::Value(1 + PIPELINE_REFILL),
+        Operation::Bx { .. } => CycleCount::Value(1 + PIPELINE_REFILL),
+        Operation::Cbz { .. } => CycleCount::Function(branch_max_cycle),
+        Operation::TableBranch { .. } => CycleCount::Value(2 + PIPELINE_REFILL),
+        Operation::It { .. } => CycleCount::Value(1),
+        Operation::Load { t, .. } => {
+            if *t == Register::PC {
+                CycleCount::Function(load_pc_cycles)
+            } else {
+                CycleCount::Function(load_cycles)
+            }
+        }
+        Operation::Store { .. } => CycleCount::Function(store_cycles),
+        Operation::LoadDual { .. } => CycleCount::Value(3),
+        Operation::StoreDual { .. } => CycleCount::Value(3),
+        Operation::LoadMultiple { registers, .. } => {
+            let max_cycle = if registers.contains(&Register::PC) {
+                1 + PIPELINE_REFILL
+            } else {
+                1
+            } + registers.len();
+            CycleCount::Value(max_cycle)
+        }
+        Operation::StoreMultiple { registers, .. } => CycleCount::Value(1 + registers.len()),
+        Operation::LoadExclusive { .. } => CycleCount::Value(2),
+        Operation::StoreExclusive { .. } => CycleCount::Value(2),
+        Operation::ClearExclusive => CycleCount::Value(1),
+        Operation::Mrs { .. } => CycleCount::Value(2),
+        Operation::Msr { .. } => CycleCount::Value(2),
+        Operation::Cps { .. } => CycleCount::Value(2),
+        Operation::Nop => CycleCount::Value(1),
+        Operation::Yield => CycleCount::Value(1),
+        Operation::Wfe => CycleCount::Value(1),
+        Operation::Wfi => CycleCount::Value(1),
+        Operation::Sev => CycleCount::Value(1),
+        // The time to complete the barrier depends on the memory system,
+        // use the same as the armv6-m cores for now.
+        Operation::Dmb => CycleCount::Value(4),
+        Operation::Dsb => CycleCount::Value(4),
+        Operation::Isb => CycleCount::Value(4),
+        // The exception generated by these instructions is not modeled, only the cycle to issue
+        // them is counted.
+        Operation::Bkpt { .. } => CycleCount::Value(1),
+        Operation::Svc { .. } => CycleCount::Value(1),
+        Operation::Udf { .. } => CycleCount::Value(1),
+        Operation::Preload => CycleCount::Value(1),
+        Operation::FloatArith { .. }
+        | Operation::FloatCompare { .. }
+        | Operation::FloatConvert { .. }
+        | Operation::FloatMove { .. }
+        | Operation::FloatMoveImmediate { .. }
+        | Operation::FloatAbs { .. }
+        | Operation::FloatNeg { .. }
+        | Operation::FloatToCore { .. }
+        | Operation::CoreToFloat { .. }
+        | Operation::FloatLoad { .. }
+        | Operation::FloatStore { .. }
+        | Operation::FloatLoadMultiple { .. }
+        | Operation::FloatStoreMultiple { .. }
+        | Operation::Vmrs { .. }
+        | Operation::Vmsr { .. } => {
+            // the core has no floating point unit
+            return None;
+        }
+    })
+}
+
+/// Returns `None` if the core does not implement the operation.
+fn cycle_count_m4_core(operation: &Operation) -> Option<CycleCount> {
+    Some(match operation {
+        // The Cortex-M4 has a single cycle 32x32 multiplier with a 64 bit result.
+        Operation::MulLong { .. } => CycleCount::Value(1),
+        // Timings of the FPv4-SP floating point unit.
+        Operation::FloatArith {
+            op: FloatArithOp::Div,
+            ..
+        } => CycleCount::Value(14),
+        Operation::FloatArith { .. } => CycleCount::Value(1),
+        Operation::FloatCompare { .. } => CycleCount::Value(1),
+        Operation::FloatConvert { .. } => CycleCount::Value(1),
+        Operation::FloatMove { .. } => CycleCount::Value(1),
+        Operation::FloatMoveImmediate { .. } => CycleCount::Value(1),
+        Operation::FloatAbs { .. } => CycleCount::Value(1),
+        Operation::FloatNeg { .. } => CycleCount::Value(1),
+        Operation::FloatToCore { .. } => CycleCount::Value(1),
+        Operation::CoreToFloat { .. } => CycleCount::Value(2),
+        Operation::FloatLoad { count, .. } => CycleCount::Value(1 + *count as usize),
+        Operation::FloatStore { count, .. } => CycleCount::Value(1 + *count as usize),
+        Operation::FloatLoadMultiple { count, .. } => CycleCount::Value(1 + *count as usize),
+        Operation::FloatStoreMultiple { count, .. } => CycleCount::Value(1 + *count as usize),
+        Operation::Vmrs { .. } => CycleCount::Value(1),
+        Operation::Vmsr { .. } => CycleCount::Value(2),
+        _ => return cycle_count_m3_core(operation),
+    })
+}
+
+/// Returns `None` if the core does not implement the operation.
+fn cycle_count_m23_core(operation: &Operation) -> Option<CycleCount> {
+    let branch_max_cycle: fn(state: &GAState) -> usize = |state| {
+        if state.get_has_jumped() {
+            2
+        } else {
+            1
+        }
+    };
+    let load_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Read);
+    let store_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Write);
+    Some(match operation {
+        Operation::DataProcessing { d, .. } => {
+            let max_cycle = if *d == Some(Register::PC) { 2 } else { 1 };
+            CycleCount::Value(max_cycle)
+        }
+        Operation::ShiftRegister { .. } => CycleCount::Value(1),
+        Operation::Adr { .. } => CycleCount::Value(1),
+        Operation::MovTop { .. } => CycleCount::Value(1),
+        Operation::Mul { .. } => {
+            CycleCount::Value(32) // Can be one depending on core implementation.
+        }
+        Operation::Div { .. } => {
+            CycleCount::Value(34) // Can be 17 depending on core implementation.
+        }
+        Operation::Extend { .. } => CycleCount::Value(1),
+        Operation::Rev { .. } => CycleCount::Value(1),
+        Operation::Rev16 { .. } => CycleCount::Value(1),
+        Operation::Revsh { .. } => CycleCount::Value(1),
+        Operation::B { .. } => CycleCount::Function(branch_max_cycle),
+        Operation::Bl { .. } => CycleCount::Value(3),
+        Operation::Blx { .. } => CycleCount::Value(2),
+        Operation::Bx { .. } => CycleCount::Value(2),
+        Operation::Cbz { .. } => CycleCount::Function(branch_max_cycle),
+        Operation::Load { .. } => CycleCount::Function(load_cycles),
+        Operation::Store { .. } => CycleCount::Function(store_cycles),
+        Operation::LoadMultiple { registers, .. } => {
+            let max_cycle = if registers.contains(&Register::PC) {
+                3
+            } else {
+                1
+            } + registers.len();
+            CycleCount::Value(max_cycle)
+        }
+        Operation::StoreMultiple { registers, .. } => CycleCount::Value(1 + registers.len()),
+        Operation::LoadExclusive { .. } => CycleCount::Value(2),
+        Operation::StoreExclusive { .. } => CycleCount::Value(2),
+        Operation::ClearExclusive => CycleCount::Value(1),
+        Operation::Mrs { .. } => CycleCount::Value(3),
+        Operation::Msr { .. } => CycleCount::Value(3),
+        Operation::Cps { .. } => CycleCount::Value(1),
+        Operation::Nop => CycleCount::Value(1),
+        Operation::Yield => CycleCount::Value(1),
+        Operation::Wfe => CycleCount::Value(1),
+        Operation::Wfi => CycleCount::Value(1),
+        Operation::Sev => CycleCount::Value(1),
+        Operation::Dmb => CycleCount::Value(3),
+        Operation::Dsb => CycleCount::Value(3),
+        Operation::Isb => CycleCount::Value(3),
+        // The exception generated by these instructions is not modeled, only the cycle to issue
+        // them is counted.
+        Operation::Bkpt { .. } => CycleCount::Value(1),
+        Operation::Svc { .. } => CycleCount::Value(1),
+        Operation::Udf { .. } => CycleCount::Value(1),
+        Operation::Mla { .. }
+        | Operation::Mls { .. }
+        | Operation::MulLong { .. }
+        | Operation::Bfi { .. }
+        | Operation::Bfc { .. }
+        | Operation::Bfx { .. }
+        | Operation::Saturate { .. }
+        | Operation::SaturatingArith { .. }
+        | Operation::Clz { .. }
+        | Operation::Rbit { .. }
+        | Operation::TableBranch { .. }
+        | Operation::It { .. }
+        | Operation::LoadDual { .. }
+        | Operation::StoreDual { .. }
+        | Operation::Preload
+        | Operation::FloatArith { .. }
+        | Operation::FloatCompare { .. }
+        | Operation::FloatConvert { .. }
+        | Operation::FloatMove { .. }
+        | Operation::FloatMoveImmediate { .. }
+        | Operation::FloatAbs { .. }
+        | Operation::FloatNeg { .. }
+        | Operation::FloatToCore { .. }
+        | Operation::CoreToFloat { .. }
+        | Operation::FloatLoad { .. }
+        | Operation::FloatStore { .. }
+        | Operation::FloatLoadMultiple { .. }
+        | Operation::FloatStoreMultiple { .. }
+        | Operation::Vmrs { .. }
+        | Operation::Vmsr { .. } => {
+            // not part of the armv8-m baseline profile
+            return None;
+        }
+    })
+}
+
+impl Translatable for Armv7MInstruction {
+    fn translate(&self) -> GAInstruction {
+        let operations = match &self.instruction.operation {
+            Operation::DataProcessing {
+                op,
+                d,
+                n,
+                operand,
+                set_flags,
+            } => data_processing(op, d, n, operand, set_flags),
+            Operation::ShiftRegister {
+                shift,
+                d,
+                n,
+                m,
+                set_flags,
+            } => {
+                let n = arm_register_to_ga_operand(n);
+                let result = local("result");
+                let amount = local("shift");
+                let mut operations = vec![GAOperation::And {
+                    destination: amount.clone(),
+                    operand1: arm_register_to_ga_operand(m),
+                    operand2: imm(0xff),
+                }];
+                let (operation, carry) = match shift {
+                    Shift::Lsl => (
+                        GAOperation::Sl {
+                            destination: result.clone(),
+                            operand: n.clone(),
+                            shift: amount.clone(),
+                        },
+                        GAOperation::SetCFlagShiftLeft {
+                            operand: n,
+                            shift: amount,
+                        },
+                    ),
+                    Shift::Lsr => (
+                        GAOperation::Srl {
+                            destination: result.clone(),
+                            operand: n.clone(),
+                            shift: amount.clone(),
+                        },
+                        GAOperation::SetCFlagSrl {
+                            operand: n,
+                            shift: amount,
+                        },
+                    ),
+                    Shift::Asr => (
+                        GAOperation::Sra {
+                            destination: result.clone(),
+                            operand: n.clone(),
+                            shift: amount.clone(),
+                        },
+                        GAOperation::SetCFlagSra {
+                            operand: n,
+                            shift: amount,
+                        },
+                    ),
+                    _ => (
+                        GAOperation::Sror {
+                            destination: result.clone(),
+                            operand: n,
+                            shift: amount.clone(),
+                        },
+                        GAOperation::SetCFlagRor {
+                            result: result.clone(),
+                            shift: amount,
+                        },
+                    ),
+                };
+                operations.push(operation);
+                operations.extend(flag_operations(
+                    set_flags,
+                    vec![
+                        GAOperation::SetNFlag(result.clone()),
+                        GAOperation::SetZFlag(result.clone()),
+                        carry,
+                    ],
+                ));
+                operations.push(GAOperation::Move {
+                    destination: arm_register_to_ga_operand(d),
+                    source: result,
+                });
+                operations
+            }
+            Operation::Adr {
+                d,
+                imm: offset,
+                add,
+            } => {
+                let addr = local("addr");
+                let mut operations = aligned_pc(&addr);
+                operations.push(if *add {
+                    GAOperation::Add {
+                        destination: arm_register_to_ga_operand(d),
+                        operand1: addr,
+                        operand2: imm(*offset),
+                    }
+                } else {
+                    GAOperation::Sub {
+                        destination: arm_register_to_ga_operand(d),
+                        operand1: addr,
+                        operand2: imm(*offset),
+                    }
+                });
+                operations
+            }
+            Operation::MovTop { d, imm: value } => {
+                let d = arm_register_to_ga_operand(d);
+                vec![
+                    GAOperation::And {
+                        destination: d.clone(),
+                        operand1: d.clone(),
+                        operand2: imm(0xffff),
+                    },
+                    GAOperation::Or {
+                        destination: d.clone(),
+                        operand1: d,
+                        operand2: imm(value << 16),
+                    },
+                ]
+            }
+            Operation::Mul { d, n, m, set_flags } => {
+                let result = local("result");
+                let mut operations = vec![GAOperation::Mul {
+                    destination: result.clone(),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: arm_register_to_ga_operand(m),
+                }];
+                operations.extend(flag_operations(
+                    set_flags,
+                    vec![
+                        GAOperation::SetNFlag(result.clone()),
+                        GAOperation::SetZFlag(result.clone()),
+                    ],
+                ));
+                operations.push(GAOperation::Move {
+                    destination: arm_register_to_ga_operand(d),
+                    source: result,
+                });
+                operations
+            }
+            Operation::Mla { d, n, m, a } => vec![
+                GAOperation::Mul {
+                    destination: local("product"),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: arm_register_to_ga_operand(m),
+                },
+                GAOperation::Add {
+                    destination: arm_register_to_ga_operand(d),
+                    operand1: arm_register_to_ga_operand(a),
+                    operand2: local("product"),
+                },
+            ],
+            Operation::Mls { d, n, m, a } => vec![
+                GAOperation::Mul {
+                    destination: local("product"),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: arm_register_to_ga_operand(m),
+                },
+                GAOperation::Sub {
+                    destination: arm_register_to_ga_operand(d),
+                    operand1: arm_register_to_ga_operand(a),
+                    operand2: local("product"),
+                },
+            ],
+            Operation::MulLong {
+                d_lo,
+                d_hi,
+                n,
+                m,
+                signed,
+                accumulate,
+            } => vec![GAOperation::MulLong {
+                destination_high: arm_register_to_ga_operand(d_hi),
+                destination_low: arm_register_to_ga_operand(d_lo),
+                operand1: arm_register_to_ga_operand(n),
+                operand2: arm_register_to_ga_operand(m),
+                signed: *signed,
+                accumulate: *accumulate,
+            }],
+            Operation::Div { d, n, m, signed } => {
+                let destination = arm_register_to_ga_operand(d);
+                let operand1 = arm_register_to_ga_operand(n);
+                let operand2 = arm_register_to_ga_operand(m);
+                if *signed {
+                    vec![GAOperation::SDiv {
+                        destination,
+                        operand1,
+                        operand2,
+                    }]
+                } else {
+                    vec![GAOperation::UDiv {
+                        destination,
+                        operand1,
+                        operand2,
+                    }]
+                }
+            }
+            Operation::Bfi { d, n, lsb, width } => {
+                let d = arm_register_to_ga_operand(d);
+                let field = local("field");
+                let mask = bit_mask(*lsb, *width);
+                vec![
+                    GAOperation::Sl {
+                        destination: field.clone(),
+                        operand: arm_register_to_ga_operand(n),
+                        shift: imm(*lsb),
+                    },
+                    GAOperation::And {
+                        destination: field.clone(),
+                        operand1: field.clone(),
+                        operand2: imm(mask),
+                    },
+                    GAOperation::And {
+                        destination: d.clone(),
+                        operand1: d.clone(),
+                        operand2: imm(!mask),
+                    },
+                    GAOperation::Or {
+                        destination: d.clone(),
+                        operand1: d,
+                        operand2: field,
+                    },
+                ]
+            }
+            Operation::Bfc { d, lsb, width } => {
+                let d = arm_register_to_ga_operand(d);
+                vec![GAOperation::And {
+                    destination: d.clone(),
+                    operand1: d,
+                    operand2: imm(!bit_mask(*lsb, *width)),
+                }]
+            }
+            Operation::Bfx {
+                d,
+                n,
+                lsb,
+                width,
+                signed,
+            } => {
+                let d = arm_register_to_ga_operand(d);
+                let n = arm_register_to_ga_operand(n);
+                let field = local("field");
+                if *signed {
+                    // move the field to the top and shift it back down to sign extend it
+                    vec![
+                        GAOperation::Sl {
+                            destination: field.clone(),
+                            operand: n,
+                            shift: imm(32 - lsb - width),
+                        },
+                        GAOperation::Sra {
+                            destination: d,
+                            operand: field,
+                            shift: imm(32 - width),
+                        },
+                    ]
+                } else {
+                    vec![
+                        GAOperation::Srl {
+                            destination: field.clone(),
+                            operand: n,
+                            shift: imm(*lsb),
+                        },
+                        GAOperation::And {
+                            destination: d,
+                            operand1: field,
+                            operand2: imm(bit_mask(0, *width)),
+                        },
+                    ]
+                }
+            }
+            Operation::Saturate {
+                d,
+                n,
+                shift,
+                saturate_to,
+                signed,
+            } => {
+                let operand = local("operand");
+                let shift_operation = if shift.shift == Shift::Asr {
+                    GAOperation::Sra {
+                        destination: operand.clone(),
+                        operand: arm_register_to_ga_operand(n),
+                        shift: imm(shift.amount),
+                    }
+                } else {
+                    GAOperation::Sl {
+                        destination: operand.clone(),
+                        operand: arm_register_to_ga_operand(n),
+                        shift: imm(shift.amount),
+                    }
+                };
+                vec![
+                    shift_operation,
+                    GAOperation::Saturate {
+                        destination: arm_register_to_ga_operand(d),
+                        operand,
+                        bits: *saturate_to,
+                        signed: *signed,
+                    },
+                ]
+            }
+            Operation::SaturatingArith { op, d, n, m } => {
+                let d = arm_register_to_ga_operand(d);
+                let n = arm_register_to_ga_operand(n);
+                let m = arm_register_to_ga_operand(m);
+                let doubled = local("doubled");
+                let double_n = GAOperation::SaturatingAdd {
+                    destination: doubled.clone(),
+                    operand1: n.clone(),
+                    operand2: n.clone(),
+                    signed: true,
+                };
+                match op {
+                    SaturatingOp::Qadd => vec![GAOperation::SaturatingAdd {
+                        destination: d,
+                        operand1: m,
+                        operand2: n,
+                        signed: true,
+                    }],
+                    SaturatingOp::Qsub => vec![GAOperation::SaturatingSub {
+                        destination: d,
+                        operand1: m,
+                        operand2: n,
+                        signed: true,
+                    }],
+                    SaturatingOp::Qdadd => vec![
+                        double_n,
+                        GAOperation::SaturatingAdd {
+                            destination: d,
+                            operand1: m,
+                            operand2: doubled,
+                            signed: true,
+                        },
+                    ],
+                    SaturatingOp::Qdsub => vec![
+                        double_n,
+                        GAOperation::SaturatingSub {
+                            destination: d,
+                            operand1: m,
+                            operand2: doubled,
+                            signed: true,
+                        },
+                    ],
+                }
+            }
+            Operation::Extend {
+                d,
+                m,
+                add,
+                rotation,
+                bits,
+                signed,
+            } => {
+                let rotated = local("rotated");
+                let extended = local("extended");
+                let mut operations = vec![GAOperation::Sror {
+                    destination: rotated.clone(),
+                    operand: arm_register_to_ga_operand(m),
+                    shift: imm(*rotation),
+                }];
+                operations.push(if *signed {
+                    GAOperation::SignExtend {
+                        destination: extended.clone(),
+                        operand: rotated,
+                        bits: *bits,
+                    }
+                } else {
+                    GAOperation::ZeroExtend {
+                        destination: extended.clone(),
+                        operand: rotated,
+                        bits: *bits,
+                    }
+                });
+                operations.push(match add {
+                    Some(n) => GAOperation::Add {
+                        destination: arm_register_to_ga_operand(d),
+                        operand1: arm_register_to_ga_operand(n),
+                        operand2: extended,
+                    },
+                    None => GAOperation::Move {
+                        destination: arm_register_to_ga_operand(d),
+                        source: extended,
+                    },
+                });
+                operations
+            }
+            Operation::Clz { d, m } => vec![GAOperation::CountLeadingZeroes {
+                destination: arm_register_to_ga_operand(d),
+                operand: arm_register_to_ga_operand(m),
+            }],
+            Operation::Rbit { d, m } => vec![GAOperation::ReverseBits {
+                destination: arm_register_to_ga_operand(d),
+                operand: arm_register_to_ga_operand(m),
+            }],
+            Operation::Rev { d, m } => shuffle_bytes(
+                d,
+                m,
+                &[
+                    (0x000000ff, 24),
+                    (0x0000ff00, 8),
+                    (0x00ff0000, -8),
+                    (0xff000000, -24),
+                ],
+                false,
+            ),
+            Operation::Rev16 { d, m } => {
+                shuffle_bytes(d, m, &[(0x00ff00ff, 8), (0xff00ff00, -8)], false)
+            }
+            Operation::Revsh { d, m } => {
+                shuffle_bytes(d, m, &[(0x000000ff, 8), (0x0000ff00, -8)], true)
+            }
+            Operation::B { cond, imm: offset } => vec![
+                GAOperation::Add {
+                    destination: local("new_pc"),
+                    operand1: arm_register_to_ga_operand(&Register::PC),
+                    operand2: imm(*offset as u32),
+                },
+                GAOperation::ConditionalJump {
+                    destination: local("new_pc"),
+                    condition: arm_cond_to_ga_cond(cond),
+                },
+            ],
+            Operation::Bl { imm: offset } => vec![
+                GAOperation::Add {
+                    destination: local("new_pc"),
+                    operand1: arm_register_to_ga_operand(&Register::PC),
+                    operand2: imm(*offset as u32),
+                },
+                // PC already points to the next instruction, mark the return address as thumb
+                GAOperation::Or {
+                    destination: Operand::Register("LR".to_owned()),
+                    operand1: Operand::Register("PC".to_owned()),
+                    operand2: imm(1),
+                },
+                GAOperation::Move {
+                    destination: Operand::Register("PC".to_owned()),
+                    source: local("new_pc"),
+                },
+            ],
+            Operation::Blx { m } => vec![
+                GAOperation::Move {
+                    destination: local("target"),
+                    source: arm_register_to_ga_operand(m),
+                },
+                GAOperation::Or {
+                    destination: Operand::Register("LR".to_owned()),
+                    operand1: Operand::Register("PC".to_owned()),
+                    operand2: imm(1),
+                },
+                GAOperation::Move {
+                    destination: arm_register_to_ga_operand(&Register::PC),
+                    source: local("target"),
+                },
+            ],
+            Operation::Bx { m } => vec![GAOperation::Move {
+                destination: arm_register_to_ga_operand(&Register::PC),
+                source: arm_register_to_ga_operand(m),
+            }],
+            Operation::Cbz {
+                n,
+                imm: offset,
+                nonzero,
+            } => vec![
+                GAOperation::Add {
+                    destination: local("new_pc"),
+                    operand1: arm_register_to_ga_operand(&Register::PC),
+                    operand2: imm(*offset),
+                },
+                GAOperation::CompareAndJump {
+                    destination: local("new_pc"),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: imm(0),
+                    comparison: if *nonzero {
+                        Comparison::NE
+                    } else {
+                        Comparison::EQ
+                    },
+                },
+            ],
+            Operation::TableBranch { n, m, half } => {
+                let addr = local("addr");
+                let offset = local("offset");
+                let (index, width) = if *half {
+                    (local("index"), 16)
+                } else {
+                    (arm_register_to_ga_operand(m), 8)
+                };
+                let mut operations = vec![];
+                if *half {
+                    operations.push(GAOperation::Sl {
+                        destination: index.clone(),
+                        operand: arm_register_to_ga_operand(m),
+                        shift: imm(1),
+                    });
+                }
+                operations.extend([
+                    GAOperation::Add {
+                        destination: addr.clone(),
+                        operand1: arm_register_to_ga_operand(n),
+                        operand2: index,
+                    },
+                    GAOperation::Move {
+                        destination: Operand::Register("LastAddr".to_owned()),
+                        source: addr,
+                    },
+                    GAOperation::Move {
+                        destination: offset.clone(),
+                        source: Operand::AddressInLocal("addr".to_owned(), width),
+                    },
+                    GAOperation::ZeroExtend {
+                        destination: offset.clone(),
+                        operand: offset.clone(),
+                        bits: width,
+                    },
+                    GAOperation::Sl {
+                        destination: offset.clone(),
+                        operand: offset.clone(),
+                        shift: imm(1),
+                    },
+                    GAOperation::Add {
+                        destination: local("new_pc"),
+                        operand1: arm_register_to_ga_operand(&Register::PC),
+                        operand2: offset,
+                    },
+                    GAOperation::Move {
+                        destination: Operand::Register("PC".to_owned()),
+                        source: local("new_pc"),
+                    },
+                ]);
+                operations
+            }
+            Operation::It { conditions } => vec![GAOperation::ConditionalExecution {
+                conditions: conditions.iter().map(arm_cond_to_ga_cond).collect(),
+            }],
+            Operation::Load {
+                t,
+                address,
+                bits,
+                signed,
+            } => {
+                let data = local("data");
+                let (mut operations, writeback) = address_calculation(address);
+                operations.push(GAOperation::Move {
+                    destination: data.clone(),
+                    source: Operand::AddressInLocal("address".to_owned(), *bits),
+                });
+                if *bits < 32 {
+                    operations.push(if *signed {
+                        GAOperation::SignExtend {
+                            destination: data.clone(),
+                            operand: data.clone(),
+                            bits: *bits,
+                        }
+                    } else {
+                        GAOperation::ZeroExtend {
+                            destination: data.clone(),
+                            operand: data.clone(),
+                            bits: *bits,
+                        }
+                    });
+                }
+                operations.extend(writeback);
+                operations.push(GAOperation::Move {
+                    destination: arm_register_to_ga_operand(t),
+                    source: data,
+                });
+                operations
+            }
+            Operation::Store { t, address, bits } => {
+                let (mut operations, writeback) = address_calculation(address);
+                operations.push(GAOperation::Move {
+                    destination: Operand::AddressInLocal("address".to_owned(), *bits),
+                    source: arm_register_to_ga_operand(t),
+                });
+                operations.extend(writeback);
+                operations
+            }
+            Operation::LoadDual { t, t2, address } => {
+                let (mut operations, writeback) = address_calculation(address);
+                operations.extend([
+                    GAOperation::Add {
+                        destination: local("address2"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    },
+                    GAOperation::Move {
+                        destination: local("data"),
+                        source: Operand::AddressInLocal("address".to_owned(), 32),
+                    },
+                    GAOperation::Move {
+                        destination: local("data2"),
+                        source: Operand::AddressInLocal("address2".to_owned(), 32),
+                    },
+                ]);
+                operations.extend(writeback);
+                operations.extend([
+                    GAOperation::Move {
+                        destination: arm_register_to_ga_operand(t),
+                        source: local("data"),
+                    },
+                    GAOperation::Move {
+                        destination: arm_register_to_ga_operand(t2),
+                        source: local("data2"),
+                    },
+                ]);
+                operations
+            }
+            Operation::StoreDual { t, t2, address } => {
+                let (mut operations, writeback) = address_calculation(address);
+                operations.extend([
+                    GAOperation::Add {
+                        destination: local("address2"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    },
+                    GAOperation::Move {
+                        destination: Operand::AddressInLocal("address".to_owned(), 32),
+                        source: arm_register_to_ga_operand(t),
+                    },
+                    GAOperation::Move {
+                        destination: Operand::AddressInLocal("address2".to_owned(), 32),
+                        source: arm_register_to_ga_operand(t2),
+                    },
+                ]);
+                operations.extend(writeback);
+                operations
+            }
+            Operation::LoadMultiple {
+                n,
+                registers,
+                wback,
+                decrement_before,
+            } => {
+                let (mut operations, writeback) =
+                    multiple_address(n, registers.len(), *wback, *decrement_before);
+                for reg in registers {
+                    operations.push(GAOperation::Move {
+                        destination: arm_register_to_ga_operand(reg),
+                        source: Operand::AddressInLocal("address".to_owned(), 32),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::StoreMultiple {
+                n,
+                registers,
+                wback,
+                decrement_before,
+            } => {
+                let (mut operations, writeback) =
+                    multiple_address(n, registers.len(), *wback, *decrement_before);
+                for reg in registers {
+                    operations.push(GAOperation::Move {
+                        destination: Operand::AddressInLocal("address".to_owned(), 32),
+                        source: arm_register_to_ga_operand(reg),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::LoadExclusive {
+                t,
+                n,
+                imm: offset,
+                bits,
+            } => vec![
+                GAOperation::Add {
+                    destination: local("address"),
+                    operand1: arm_register_to_ga_operand(n),
+                    operand2: imm(*offset),
+                },
+                GAOperation::Move {
+                    destination: Operand::Register("LastAddr".to_owned()),
+                    source: local("address"),
+                },
+                GAOperation::Move {
+                    destination: local("data"),
+                    source: Operand::AddressInLocal("address".to_owned(), *bits),
+                },
+                GAOperation::ZeroExtend {
+                    destination: arm_register_to_ga_operand(t),
+                    operand: local("data"),
+                    bits: *bits,
+                },
+            ],
+            Operation::StoreExclusive {
+                d,
+                t,
+                n,
+                imm: offset,
+                bits,
+            } => {
+                // There is only one core and no other bus masters are modeled
+                // so the exclusive access always succeeds.
+                vec![
+                    GAOperation::Add {
+                        destination: local("address"),
+                        operand1: arm_register_to_ga_operand(n),
+                        operand2: imm(*offset),
+                    },
+                    GAOperation::Move {
+                        destination: Operand::Register("LastAddr".to_owned()),
+                        source: local("address"),
+                    },
+                    GAOperation::Move {
+                        destination: Operand::AddressInLocal("address".to_owned(), *bits),
+                        source: arm_register_to_ga_operand(t),
+                    },
+                    GAOperation::Move {
+                        destination: arm_register_to_ga_operand(d),
+                        source: imm(0),
+                    },
+                ]
+            }
+            Operation::ClearExclusive => {
+                // exclusive monitors are not modeled so do nothing
+                vec![]
+            }
+            Operation::Mrs { d, sysm } => vec![GAOperation::Move {
+                destination: arm_register_to_ga_operand(d),
+                source: arm_special_register_to_operand(sysm),
+            }],
+            Operation::Msr { n, sysm } => vec![GAOperation::Move {
+                source: arm_register_to_ga_operand(n),
+                destination: arm_special_register_to_operand(sysm),
+            }],
+            Operation::Cps {
+                disable,
+                primask,
+                faultmask,
+            } => {
+                let value = Operand::Immidiate(DataWord::Word32(*disable as u32));
+                let mut operations = vec![];
+                if *primask {
+                    operations.push(GAOperation::Move {
+                        destination: Operand::Register("PRIMASK".to_owned()),
+                        source: value.clone(),
+                    });
+                }
+                if *faultmask {
+                    operations.push(GAOperation::Move {
+                        destination: Operand::Register("FAULTMASK".to_owned()),
+                        source: value,
+                    });
+                }
+                operations
+            }
+            Operation::Nop => vec![],
+            Operation::Yield => vec![GAOperation::Special(SpecialInstruction::Yield)],
+            Operation::Wfe => vec![GAOperation::Special(SpecialInstruction::Wfe)],
+            Operation::Wfi => vec![GAOperation::Special(SpecialInstruction::Wfi)],
+            Operation::Sev => vec![GAOperation::Special(SpecialInstruction::Sev)],
+            Operation::Dmb | Operation::Dsb => {
+                // data barier do nothig as data barier is not modeled yet
+                vec![]
+            }
+            Operation::Isb => {
+                // flushes pipeline do nothig as pipeline is not modeled
+                vec![]
+            }
+            Operation::Bkpt { imm: 0xab } => vec![GAOperation::Semihosting],
+            Operation::Bkpt { .. } => vec![GAOperation::Special(SpecialInstruction::Bkpt)],
+            Operation::Svc { .. } => {
+                // to be used to call a supervisor in a OS
+                // this functionality is not modeled so do nothing
+                vec![]
+            }
+            Operation::Udf { .. } => vec![GAOperation::Special(SpecialInstruction::Udf)],
+            Operation::Preload => {
+                // caches are not modeled so do nothing
+                vec![]
+            }
+            Operation::FloatArith { op, d, n, m } => {
+                let destination = float_register_to_ga_operand(d);
+                let operand1 = float_register_to_ga_operand(n);
+                let operand2 = float_register_to_ga_operand(m);
+                vec![match op {
+                    FloatArithOp::Add => GAOperation::FAdd {
+                        destination,
+                        operand1,
+                        operand2,
+                    },
+                    FloatArithOp::Sub => GAOperation::FSub {
+                        destination,
+                        operand1,
+                        operand2,
+                    },
+                    FloatArithOp::Mul => GAOperation::FMul {
+                        destination,
+                        operand1,
+                        operand2,
+                    },
+                    FloatArithOp::Div => GAOperation::FDiv {
+                        destination,
+                        operand1,
+                        operand2,
+                    },
+                }]
+            }
+            Operation::FloatCompare { d, m } => vec![
+                GAOperation::FCompare {
+                    destination: local("flags"),
+                    operand1: float_register_to_ga_operand(d),
+                    // +0.0 is all zeroes
+                    operand2: match m {
+                        Some(m) => float_register_to_ga_operand(m),
+                        None => imm(0),
+                    },
+                },
+                GAOperation::And {
+                    destination: local("fpscr"),
+                    operand1: fpscr(),
+                    operand2: imm(0x0fff_ffff),
+                },
+                GAOperation::Or {
+                    destination: fpscr(),
+                    operand1: local("fpscr"),
+                    operand2: local("flags"),
+                },
+            ],
+            Operation::FloatConvert {
+                d,
+                m,
+                conversion,
+                round_to_zero,
+            } => {
+                let destination = float_register_to_ga_operand(d);
+                let operand = float_register_to_ga_operand(m);
+                // The rounding mode in FPSCR is not modeled, VCVTR always rounds to nearest.
+                vec![match conversion {
+                    FloatConversion::ToSigned | FloatConversion::ToUnsigned => {
+                        GAOperation::FloatToInt {
+                            destination,
+                            operand,
+                            signed: *conversion == FloatConversion::ToSigned,
+                            round_to_zero: *round_to_zero,
+                        }
+                    }
+                    FloatConversion::FromSigned | FloatConversion::FromUnsigned => {
+                        GAOperation::IntToFloat {
+                            destination,
+                            operand,
+                            signed: *conversion == FloatConversion::FromSigned,
+                        }
+                    }
+                }]
+            }
+            Operation::FloatMove { d, m } => vec![GAOperation::Move {
+                destination: float_register_to_ga_operand(d),
+                source: float_register_to_ga_operand(m),
+            }],
+            Operation::FloatMoveImmediate { d, value } => vec![GAOperation::Move {
+                destination: float_register_to_ga_operand(d),
+                source: imm(*value),
+            }],
+            Operation::FloatAbs { d, m } => vec![GAOperation::And {
+                destination: float_register_to_ga_operand(d),
+                operand1: float_register_to_ga_operand(m),
+                operand2: imm(0x7fff_ffff),
+            }],
+            Operation::FloatNeg { d, m } => vec![GAOperation::Xor {
+                destination: float_register_to_ga_operand(d),
+                operand1: float_register_to_ga_operand(m),
+                operand2: imm(0x8000_0000),
+            }],
+            Operation::FloatToCore { t, n } => vec![GAOperation::Move {
+                destination: arm_register_to_ga_operand(t),
+                source: float_register_to_ga_operand(n),
+            }],
+            Operation::CoreToFloat { n, t } => vec![GAOperation::Move {
+                destination: float_register_to_ga_operand(n),
+                source: arm_register_to_ga_operand(t),
+            }],
+            Operation::FloatLoad { d, count, address } => {
+                let (mut operations, writeback) = address_calculation(address);
+                for i in 0..*count {
+                    operations.push(GAOperation::Move {
+                        destination: float_register_to_ga_operand(&FloatRegister(d.0 + i as u8)),
+                        source: Operand::AddressInLocal("address".to_owned(), 32),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::FloatStore { d, count, address } => {
+                let (mut operations, writeback) = address_calculation(address);
+                for i in 0..*count {
+                    operations.push(GAOperation::Move {
+                        destination: Operand::AddressInLocal("address".to_owned(), 32),
+                        source: float_register_to_ga_operand(&FloatRegister(d.0 + i as u8)),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::FloatLoadMultiple {
+                n,
+                d,
+                count,
+                wback,
+                decrement_before,
+            } => {
+                let (mut operations, writeback) =
+                    multiple_address(n, *count as usize, *wback, *decrement_before);
+                for i in 0..*count {
+                    operations.push(GAOperation::Move {
+                        destination: float_register_to_ga_operand(&FloatRegister(d.0 + i as u8)),
+                        source: Operand::AddressInLocal("address".to_owned(), 32),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::FloatStoreMultiple {
+                n,
+                d,
+                count,
+                wback,
+                decrement_before,
+            } => {
+                let (mut operations, writeback) =
+                    multiple_address(n, *count as usize, *wback, *decrement_before);
+                for i in 0..*count {
+                    operations.push(GAOperation::Move {
+                        destination: Operand::AddressInLocal("address".to_owned(), 32),
+                        source: float_register_to_ga_operand(&FloatRegister(d.0 + i as u8)),
+                    });
+                    operations.push(GAOperation::Add {
+                        destination: local("address"),
+                        operand1: local("address"),
+                        operand2: imm(4),
+                    });
+                }
+                operations.extend(writeback);
+                operations
+            }
+            Operation::Vmrs { t: Some(t) } => vec![GAOperation::Move {
+                destination: arm_register_to_ga_operand(t),
+                source: fpscr(),
+            }],
+            Operation::Vmrs { t: None } => {
+                // Copy the floating point flags to APSR.
+                let mut operations = vec![];
+                for (flag, bit) in [("N", 31), ("Z", 30), ("C", 29), ("V", 28)] {
+                    operations.push(GAOperation::Srl {
+                        destination: local("flag"),
+                        operand: fpscr(),
+                        shift: imm(bit),
+                    });
+                    operations.push(GAOperation::SetFlag {
+                        flag: flag.to_owned(),
+                        operand: local("flag"),
+                    });
+                }
+                operations
+            }
+            Operation::Vmsr { t } => vec![GAOperation::Move {
+                destination: fpscr(),
+                source: arm_register_to_ga_operand(t),
+            }],
+        };
+
+        let instruction_width = match self.instruction.width {
+            InstructionWidth::Bit32 => 32,
+            InstructionWidth::Bit16 => 16,
+        };
+
+        GAInstruction {
+            instruction_size: instruction_width,
+            operations,
+            max_cycle: self.max_cycle.clone(),
+        }
+    }
+
+    fn add_hooks(cfg: &mut RunConfig) {
+        let symbolic_sized = |state: &mut GAState| {
+            let value_ptr = state.get_register("R0".to_owned())?;
+            let size = state.get_register("R1".to_owned())?.get_constant().unwrap() * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Integer(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let lr = state.get_register("LR".to_owned())?;
+            state.set_register("PC".to_owned(), lr)?;
+            Ok(())
+        };
+
+        // Matches every instantiation except `f32` and `f64`, those are hooked below.
+        cfg.pc_hooks.push((
+            Regex::new(r"^symbolic_size<([^f].*|f|f[^36].*|f3([^2].*)?|f6([^4].*)?|f(32|64).+)>$")
+                .unwrap(),
+            PCHook::intrinsic(symbolic_sized),
+        ));
+
+        // Floating point values are displayed as floats.
+        let symbolic_sized_float = |state: &mut GAState| {
+            let value_ptr = state.get_register("R0".to_owned())?;
+            let size = state.get_register("R1".to_owned())?.get_constant().unwrap() * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Float(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let lr = state.get_register("LR".to_owned())?;
+            state.set_register("PC".to_owned(), lr)?;
+            Ok(())
+        };
+
+        cfg.pc_hooks.push((
+            Regex::new(r"^symbolic_size<f(32|64)>$").unwrap(),
+            PCHook::intrinsic(symbolic_sized_float),
+        ));
+
+        // PC reads as the address of the current instruction plus four,
+        // the PC register already points to the next instruction.
+        let read_pc: RegisterReadHook = |state| {
+            let size = match &state.current_instruction {
+                Some(instruction) => instruction.instruction_size / 8,
+                None => 4,
+            };
+            let offset = state.ctx.from_u64(4 - size as u64, 32);
+            let pc = state.get_register("PC".to_owned()).unwrap();
+            Ok(pc.add(&offset))
+        };
+
+        // Writes to PC clear the thumb bit.
+        let write_pc: RegisterWriteHook = |state, value| {
+            let mask = state.ctx.from_u64(!0b1, 32);
+            state.set_register("PC".to_owned(), value.and(&mask))
+        };
+
+        cfg.register_read_hooks
+            .push(("PC+".to_owned(), Box::new(read_pc)));
+        cfg.register_write_hooks
+            .push(("PC+".to_owned(), Box::new(write_pc)));
+    }
+}
+
+/// Creates the operations for the data processing instructions.
+fn data_processing(
+    op: &DataProcessingOp,
+    d: &Option<Register>,
+    n: &Option<Register>,
+    operand: &ShifterOperand,
+    set_flags: &SetFlags,
+) -> Vec<GAOperation> {
+    let (mut operations, operand2, carry) = shifter_operand(operand);
+    let n = match n {
+        Some(n) => arm_register_to_ga_operand(n),
+        None => imm(0),
+    };
+    let result = local("result");
+    let inverted = local("inverted");
+    let mut flags = vec![
+        GAOperation::SetNFlag(result.clone()),
+        GAOperation::SetZFlag(result.clone()),
+    ];
+
+    let logical = |operations: &mut Vec<GAOperation>, flags: &mut Vec<GAOperation>, operation| {
+        operations.push(operation);
+        flags.extend(carry.clone());
+    };
+    let arithmetic = |operations: &mut Vec<GAOperation>,
+                      flags: &mut Vec<GAOperation>,
+                      operation,
+                      operand1: &Operand,
+                      operand2: &Operand,
+                      sub: bool,
+                      with_carry: bool| {
+        operations.push(operation);
+        // the overflow flag depends on the old carry so it must be set first
+        flags.push(GAOperation::SetVFlag {
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+            sub,
+            carry: with_carry,
+        });
+        flags.push(GAOperation::SetCFlag {
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+            sub,
+            carry: with_carry,
+        });
+    };
+
+    match op {
+        DataProcessingOp::And | DataProcessingOp::Tst => logical(
+            &mut operations,
+            &mut flags,
+            GAOperation::And {
+                destination: result.clone(),
+                operand1: n,
+                operand2,
+            },
+        ),
+        DataProcessingOp::Bic => {
+            operations.push(GAOperation::Not {
+                destination: inverted.clone(),
+                operand: operand2,
+            });
+            logical(
+                &mut operations,
+                &mut flags,
+                GAOperation::And {
+                    destination: result.clone(),
+                    operand1: n,
+                    operand2: inverted,
+                },
+            )
+        }
+        DataProcessingOp::Orr => logical(
+            &mut operations,
+            &mut flags,
+            GAOperation::Or {
+                destination: result.clone(),
+                operand1: n,
+                operand2,
+            },
+        ),
+        DataProcessingOp::Orn => {
+            operations.push(GAOperation::Not {
+                destination: inverted.clone(),
+                operand: operand2,
+            });
+            logical(
+                &mut operations,
+                &mut flags,
+                GAOperation::Or {
+                    destination: result.clone(),
+                    operand1: n,
+                    operand2: inverted,
+                },
+            )
+        }
+        DataProcessingOp::Eor | DataProcessingOp::Teq => logical(
+            &mut operations,
+            &mut flags,
+            GAOperation::Xor {
+                destination: result.clone(),
+                operand1: n,
+                operand2,
+            },
+        ),
+        DataProcessingOp::Mov => logical(
+            &mut operations,
+            &mut flags,
+            GAOperation::Move {
+                destination: result.clone(),
+                source: operand2,
+            },
+        ),
+        DataProcessingOp::Mvn => logical(
+            &mut operations,
+            &mut flags,
+            GAOperation::Not {
+                destination: result.clone(),
+                operand: operand2,
+            },
+        ),
+        DataProcessingOp::Add | DataProcessingOp::Cmn => arithmetic(
+            &mut operations,
+            &mut flags,
+            GAOperation::Add {
+                destination: result.clone(),
+                operand1: n.clone(),
+                operand2: operand2.clone(),
+            },
+            &n,
+            &operand2,
+            false,
+            false,
+        ),
+        DataProcessingOp::Adc => arithmetic(
+            &mut operations,
+            &mut flags,
+            GAOperation::Adc {
+                destination: result.clone(),
+                operand1: n.clone(),
+                operand2: operand2.clone(),
+            },
+            &n,
+            &operand2,
+            false,
+            true,
+        ),
+        DataProcessingOp::Sub | DataProcessingOp::Cmp => arithmetic(
+            &mut operations,
+            &mut flags,
+            GAOperation::Sub {
+                destination: result.clone(),
+                operand1: n.clone(),
+                operand2: operand2.clone(),
+            },
+            &n,
+            &operand2,
+            true,
+            false,
+        ),
+        DataProcessingOp::Sbc => {
+            // n + !operand2 + carry
+            operations.push(GAOperation::Not {
+                destination: inverted.clone(),
+                operand: operand2,
+            });
+            arithmetic(
+                &mut operations,
+                &mut flags,
+                GAOperation::Adc {
+                    destination: result.clone(),
+                    operand1: n.clone(),
+                    operand2: inverted.clone(),
+                },
+                &n,
+                &inverted,
+                false,
+                true,
+            )
+        }
+        DataProcessingOp::Rsb => arithmetic(
+            &mut operations,
+            &mut flags,
+            GAOperation::Sub {
+                destination: result.clone(),
+                operand1: operand2.clone(),
+                operand2: n.clone(),
+            },
+            &operand2,
+            &n,
+            true,
+            false,
+        ),
+    }
+
+    operations.extend(flag_operations(set_flags, flags));
+
+    if let Some(d) = d {
+        operations.push(GAOperation::Move {
+            destination: arm_register_to_ga_operand(d),
+            source: result,
+        });
+    }
+    operations
+}
+
+/// Calculates the value of a shifter operand.
+/// Returns the operations needed to calculate it, the operand holding the value
+/// and the operations that set the carry flag to the shifter carry out.
+fn shifter_operand(operand: &ShifterOperand) -> (Vec<GAOperation>, Operand, Vec<GAOperation>) {
+    match operand {
+        ShifterOperand::Immediate { value, carry } => {
+            let carry = match carry {
+                Some(carry) => vec![GAOperation::SetFlag {
+                    flag: "C".to_owned(),
+                    operand: imm(*carry as u32),
+                }],
+                None => vec![],
+            };
+            (vec![], imm(*value), carry)
+        }
+        ShifterOperand::Register { m, shift } => {
+            let m = arm_register_to_ga_operand(m);
+            let shifted = local("shifted");
+            let ImmShift { shift, amount } = shift;
+            let amount = imm(*amount);
+            match shift {
+                Shift::Lsl if amount_is_zero(&amount) => (vec![], m, vec![]),
+                Shift::Lsl => (
+                    vec![GAOperation::Sl {
+                        destination: shifted.clone(),
+                        operand: m.clone(),
+                        shift: amount.clone(),
+                    }],
+                    shifted,
+                    vec![GAOperation::SetCFlagShiftLeft {
+                        operand: m,
+                        shift: amount,
+                    }],
+                ),
+                Shift::Lsr => (
+                    vec![GAOperation::Srl {
+                        destination: shifted.clone(),
+                        operand: m.clone(),
+                        shift: amount.clone(),
+                    }],
+                    shifted,
+                    vec![GAOperation::SetCFlagSrl {
+                        operand: m,
+                        shift: amount,
+                    }],
+                ),
+                Shift::Asr => (
+                    vec![GAOperation::Sra {
+                        destination: shifted.clone(),
+                        operand: m.clone(),
+                        shift: amount.clone(),
+                    }],
+                    shifted,
+                    vec![GAOperation::SetCFlagSra {
+                        operand: m,
+                        shift: amount,
+                    }],
+                ),
+                Shift::Ror if amount_is_zero(&amount) => (vec![], m, vec![]),
+                Shift::Ror => (
+                    vec![GAOperation::Sror {
+                        destination: shifted.clone(),
+                        operand: m,
+                        shift: amount.clone(),
+                    }],
+                    shifted.clone(),
+                    vec![GAOperation::SetCFlagRor {
+                        result: shifted,
+                        shift: amount,
+                    }],
+                ),
+                Shift::Rrx => {
+                    let carry_in = local("carry_in");
+                    (
+                        vec![
+                            // read the carry flag by adding it to zero
+                            GAOperation::Adc {
+                                destination: carry_in.clone(),
+                                operand1: imm(0),
+                                operand2: imm(0),
+                            },
+                            GAOperation::Sl {
+                                destination: carry_in.clone(),
+                                operand: carry_in.clone(),
+                                shift: imm(31),
+                            },
+                            GAOperation::Srl {
+                                destination: shifted.clone(),
+                                operand: m.clone(),
+                                shift: imm(1),
+                            },
+                            GAOperation::Or {
+                                destination: shifted.clone(),
+                                operand1: shifted.clone(),
+                                operand2: carry_in,
+                            },
+                        ],
+                        shifted,
+                        vec![GAOperation::SetCFlagSrl {
+                            operand: m,
+                            shift: imm(1),
+                        }],
+                    )
+                }
+            }
+        }
+    }
+}
+
+fn amount_is_zero(amount: &Operand) -> bool {
+    matches!(amount, Operand::Immidiate(DataWord::Word32(0)))
+}
+
+/// Wraps the flag operations depending on when the flags should be set.
+fn flag_operations(set_flags: &SetFlags, flags: Vec<GAOperation>) -> Vec<GAOperation> {
+    match set_flags {
+        SetFlags::Never => vec![],
+        SetFlags::Always => flags,
+        SetFlags::OutsideITBlock => {
+            vec![GAOperation::IfNotInConditionalBlock { operations: flags }]
+        }
+    }
+}
+
+/// Stores the word aligned value of PC in `destination`.
+fn aligned_pc(destination: &Operand) -> Vec<GAOperation> {
+    vec![
+        GAOperation::Move {
+            destination: destination.clone(),
+            source: arm_register_to_ga_operand(&Register::PC),
+        },
+        GAOperation::And {
+            destination: destination.clone(),
+            operand1: destination.clone(),
+            operand2: imm(!0b11),
+        },
+    ]
+}
+
+/// Calculates the address of a single load or store into the local `address`.
+/// Returns the operations for the calculation and the operations for the write back.
+fn address_calculation(address: &Address) -> (Vec<GAOperation>, Vec<GAOperation>) {
+    let base = local("base");
+    let offset_address = local("offset_address");
+    let mut operations = if address.n == Register::PC {
+        aligned_pc(&base)
+    } else {
+        vec![GAOperation::Move {
+            destination: base.clone(),
+            source: arm_register_to_ga_operand(&address.n),
+        }]
+    };
+
+    let offset = match address.offset {
+        Offset::Immediate(offset) => imm(offset),
+        Offset::Register { m, shift } => {
+            operations.push(GAOperation::Sl {
+                destination: local("offset"),
+                operand: arm_register_to_ga_operand(&m),
+                shift: imm(shift),
+            });
+            local("offset")
+        }
+    };
+
+    operations.push(if address.add {
+        GAOperation::Add {
+            destination: offset_address.clone(),
+            operand1: base.clone(),
+            operand2: offset,
+        }
+    } else {
+        GAOperation::Sub {
+            destination: offset_address.clone(),
+            operand1: base.clone(),
+            operand2: offset,
+        }
+    });
+    operations.push(GAOperation::Move {
+        destination: local("address"),
+        source: if address.index {
+            offset_address.clone()
+        } else {
+            base
+        },
+    });
+    operations.push(GAOperation::Move {
+        destination: Operand::Register("LastAddr".to_owned()),
+        source: local("address"),
+    });
+
+    let writeback = if address.wback {
+        vec![GAOperation::Move {
+            destination: arm_register_to_ga_operand(&address.n),
+            source: offset_address,
+        }]
+    } else {
+        vec![]
+    };
+    (operations, writeback)
+}
+
+/// Calculates the start address of a load or store multiple into the local `address`.
+/// Returns the operations for the calculation and the operations for the write back.
+fn multiple_address(
+    n: &Register,
+    count: usize,
+    wback: bool,
+    decrement_before: bool,
+) -> (Vec<GAOperation>, Vec<GAOperation>) {
+    let n = arm_register_to_ga_operand(n);
+    let size = imm(4 * count as u32);
+    let (operations, new_base) = if decrement_before {
+        (
+            vec![
+                GAOperation::Sub {
+                    destination: local("address"),
+                    operand1: n.clone(),
+                    operand2: size,
+                },
+                GAOperation::Move {
+                    destination: local("new_base"),
+                    source: local("address"),
+                },
+            ],
+            local("new_base"),
+        )
+    } else {
+        (
+            vec![
+                GAOperation::Move {
+                    destination: local("address"),
+                    source: n.clone(),
+                },
+                GAOperation::Add {
+                    destination: local("new_base"),
+                    operand1: n.clone(),
+                    operand2: size,
+                },
+            ],
+            local("new_base"),
+        )
+    };
+
+    let writeback = if wback {
+        vec![GAOperation::Move {
+            destination: n,
+            source: new_base,
+        }]
+    } else {
+        vec![]
+    };
+    (operations, writeback)
+}
+
+/// Moves the bytes selected by each mask the given number of bits, positive is left.
+fn shuffle_bytes(
+    d: &Register,
+    m: &Register,
+    moves: &[(u32, i32)],
+    sign_extend: bool,
+) -> Vec<GAOperation> {
+    let m = arm_register_to_ga_operand(m);
+    let d = arm_register_to_ga_operand(d);
+    let result = local("result");
+    let byte = local("byte");
+    let mut operations = vec![GAOperation::Move {
+        destination: result.clone(),
+        source: imm(0),
+    }];
+    for (mask, shift) in moves {
+        operations.push(GAOperation::And {
+            destination: byte.clone(),
+            operand1: m.clone(),
+            operand2: imm(*mask),
+        });
+        operations.push(if *shift > 0 {
+            GAOperation::Sl {
+                destination: byte.clone(),
+                operand: byte.clone(),
+                shift: imm(*shift as u32),
+            }
+        } else {
+            GAOperation::Srl {
+                destination: byte.clone(),
+                operand: byte.clone(),
+                shift: imm(shift.unsigned_abs()),
+            }
+        });
+        operations.push(GAOperation::Or {
+            destination: result.clone(),
+            operand1: result.clone(),
+            operand2: byte.clone(),
+        });
+    }
+    operations.push(if sign_extend {
+        GAOperation::SignExtend {
+            destination: d,
+            operand: result,
+            bits: 16,
+        }
+    } else {
+        GAOperation::Move {
+            destination: d,
+            source: result,
+        }
+    });
+    operations
+}
+
+/// A mask with `width` bits set starting at `lsb`.
+fn bit_mask(lsb: u32, width: u32) -> u32 {
+    (((1u64 << width) - 1) << lsb) as u32
+}
+
+fn local(name: &str) -> Operand {
+    Operand::Local(name.to_owned())
+}
+
+fn imm(value: u32) -> Operand {
+    Operand::Immidiate(DataWord::Word32(value))
+}
+
+fn arm_register_to_ga_operand(reg: &Register) -> Operand {
+    Operand::Register(match reg {
+        Register::R0 => "R0".to_owned(),
+        Register::R1 => "R1".to_owned(),
+        Register::R2 => "R2".to_owned(),
+        Register::R3 => "R3".to_owned(),
+        Register::R4 => "R4".to_owned(),
+        Register::R5 => "R5".to_owned(),
+        Register::R6 => "R6".to_owned(),
+        Register::R7 => "R7".to_owned(),
+        Register::R8 => "R8".to_owned(),
+        Register::R9 => "R9".to_owned(),
+        Register::R10 => "R10".to_owned(),
+        Register::R11 => "R11".to_owned(),
+        Register::R12 => "R12".to_owned(),
+        Register::SP => "SP".to_owned(),
+        Register::LR => "LR".to_owned(),
+        Register::PC => "PC+".to_owned(),
+    })
+}
+
+fn float_register_to_ga_operand(reg: &FloatRegister) -> Operand {
+    Operand::Register(format!("S{}", reg.0))
+}
+
+fn fpscr() -> Operand {
+    Operand::Register("FPSCR".to_owned())
+}
+
+fn arm_special_register_to_operand(reg: &SpecialRegister) -> Operand {
+    Operand::Register(match reg {
+        SpecialRegister::APSR => "APSR".to_owned(),
+        SpecialRegister::IAPSR => "IAPSR".to_owned(),
+        SpecialRegister::EAPSR => "EAPSR".to_owned(),
+        SpecialRegister::XPSR => "XPSR".to_owned(),
+        SpecialRegister::IPSR => "IPSR".to_owned(),
+        SpecialRegister::EPSR => "EPSR".to_owned(),
+        SpecialRegister::IEPSR => "IEPSR".to_owned(),
+        SpecialRegister::MSP => "MSP".to_owned(),
+        SpecialRegister::PSP => "PSP".to_owned(),
+        SpecialRegister::PRIMASK => "PRIMASK".to_owned(),
+        SpecialRegister::BASEPRI => "BASEPRI".to_owned(),
+        SpecialRegister::BASEPRIMAX => "BASEPRI_MAX".to_owned(),
+        SpecialRegister::FAULTMASK => "FAULTMASK".to_owned(),
+        SpecialRegister::CONTROL => "CONTROL".to_owned(),
+    })
+}
+
+fn arm_cond_to_ga_cond(conditon: &ArmCondition) -> Condition {
+    match conditon {
+        ArmCondition::EQ => Condition::EQ,
+        ArmCondition::NE => Condition::NE,
+        ArmCondition::CS => Condition::CS,
+        ArmCondition::CC => Condition::CC,
+        ArmCondition::MI => Condition::MI,
+        ArmCondition::PL => Condition::PL,
+        ArmCondition::VS => Condition::VS,
+        ArmCondition::VC => Condition::VC,
+        ArmCondition::HI => Condition::HI,
+        ArmCondition::LS => Condition::LS,
+        ArmCondition::GE => Condition::GE,
+        ArmCondition::LT => Condition::LT,
+        ArmCondition::GT => Condition::GT,
+        ArmCondition::LE => Condition::LE,
+        ArmCondition::None => Condition::None,
+    }
+}
@@ -0,0 +1,2227 @@
+//! Decoder for the Thumb-2 instruction set used by the armv7-m cores.
+//!
+//! The decoding follows the tables in chapter A5 of the ARMv7-M architecture reference manual.
+//! Encodings that only differ in how they are written in assembly (e.g. `MOV` with a shift
+//! and `LSL` immediate) are decoded to the same operation.
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    R0,
+    R1,
+    R2,
+    R3,
+    R4,
+    R5,
+    R6,
+    R7,
+    R8,
+    R9,
+    R10,
+    R11,
+    R12,
+    SP,
+    LR,
+    PC,
+}
+
+impl Register {
+    fn from_u32(value: u32) -> Register {
+        match value & 0b1111 {
+            0 => Register::R0,
+            1 => Register::R1,
+            2 => Register::R2,
+            3 => Register::R3,
+            4 => Register::R4,
+            5 => Register::R5,
+            6 => Register::R6,
+            7 => Register::R7,
+            8 => Register::R8,
+            9 => Register::R9,
+            10 => Register::R10,
+            11 => Register::R11,
+            12 => Register::R12,
+            13 => Register::SP,
+            14 => Register::LR,
+            _ => Register::PC,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialRegister {
+    APSR,
+    IAPSR,
+    EAPSR,
+    XPSR,
+    IPSR,
+    EPSR,
+    IEPSR,
+    MSP,
+    PSP,
+    PRIMASK,
+    BASEPRI,
+    BASEPRIMAX,
+    FAULTMASK,
+    CONTROL,
+}
+
+impl SpecialRegister {
+    fn from_sysm(sysm: u32) -> Result<SpecialRegister> {
+        Ok(match sysm {
+            0 => SpecialRegister::APSR,
+            1 => SpecialRegister::IAPSR,
+            2 => SpecialRegister::EAPSR,
+            3 => SpecialRegister::XPSR,
+            5 => SpecialRegister::IPSR,
+            6 => SpecialRegister::EPSR,
+            7 => SpecialRegister::IEPSR,
+            8 => SpecialRegister::MSP,
+            9 => SpecialRegister::PSP,
+            16 => SpecialRegister::PRIMASK,
+            17 => SpecialRegister::BASEPRI,
+            18 => SpecialRegister::BASEPRIMAX,
+            19 => SpecialRegister::FAULTMASK,
+            20 => SpecialRegister::CONTROL,
+            _ => return Err(DecodeError::Unpredictable),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    EQ,
+    NE,
+    CS,
+    CC,
+    MI,
+    PL,
+    VS,
+    VC,
+    HI,
+    LS,
+    GE,
+    LT,
+    GT,
+    LE,
+    None,
+}
+
+impl Condition {
+    fn from_u32(value: u32) -> Condition {
+        match value & 0b1111 {
+            0b0000 => Condition::EQ,
+            0b0001 => Condition::NE,
+            0b0010 => Condition::CS,
+            0b0011 => Condition::CC,
+            0b0100 => Condition::MI,
+            0b0101 => Condition::PL,
+            0b0110 => Condition::VS,
+            0b0111 => Condition::VC,
+            0b1000 => Condition::HI,
+            0b1001 => Condition::LS,
+            0b1010 => Condition::GE,
+            0b1011 => Condition::LT,
+            0b1100 => Condition::GT,
+            0b1101 => Condition::LE,
+            _ => Condition::None,
+        }
+    }
+}
+
+/// Shift types used by the barrel shifter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    Rrx,
+}
+
+/// A shift by a constant amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImmShift {
+    pub shift: Shift,
+    pub amount: u32,
+}
+
+impl ImmShift {
+    fn none() -> ImmShift {
+        ImmShift {
+            shift: Shift::Lsl,
+            amount: 0,
+        }
+    }
+
+    /// DecodeImmShift from the reference manual.
+    fn decode(shift_type: u32, imm5: u32) -> ImmShift {
+        match shift_type {
+            0b00 => ImmShift {
+                shift: Shift::Lsl,
+                amount: imm5,
+            },
+            0b01 => ImmShift {
+                shift: Shift::Lsr,
+                amount: if imm5 == 0 { 32 } else { imm5 },
+            },
+            0b10 => ImmShift {
+                shift: Shift::Asr,
+                amount: if imm5 == 0 { 32 } else { imm5 },
+            },
+            _ => {
+                if imm5 == 0 {
+                    ImmShift {
+                        shift: Shift::Rrx,
+                        amount: 1,
+                    }
+                } else {
+                    ImmShift {
+                        shift: Shift::Ror,
+                        amount: imm5,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// When the flags should be updated by a instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetFlags {
+    Never,
+    Always,
+    /// Most 16 bit encodings only update the flags when outside of a IT block.
+    OutsideITBlock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataProcessingOp {
+    And,
+    Bic,
+    Orr,
+    Orn,
+    Eor,
+    Add,
+    Adc,
+    Sbc,
+    Sub,
+    Rsb,
+    Mov,
+    Mvn,
+    Tst,
+    Teq,
+    Cmp,
+    Cmn,
+}
+
+/// The second operand of a data processing instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShifterOperand {
+    /// A constant, `carry` is the carry out of the immediate expansion if it affects the carry flag.
+    Immediate { value: u32, carry: Option<bool> },
+
+    /// A register shifted by a constant amount.
+    Register { m: Register, shift: ImmShift },
+}
+
+/// Offset added to or subtracted from the base register of a memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offset {
+    Immediate(u32),
+    /// Register `m` shifted left by `shift`.
+    Register {
+        m: Register,
+        shift: u32,
+    },
+}
+
+/// Address calculation for a load or store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address {
+    /// Base register, if PC the base is the word aligned PC value.
+    pub n: Register,
+    pub offset: Offset,
+    /// Use the offset address for the access, otherwise the base address is used (post-indexed).
+    pub index: bool,
+    /// Add the offset to the base, otherwise subtract.
+    pub add: bool,
+    /// Write the offset address back to the base register.
+    pub wback: bool,
+}
+
+impl Address {
+    fn offset(n: Register, offset: Offset) -> Address {
+        Address {
+            n,
+            offset,
+            index: true,
+            add: true,
+            wback: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaturatingOp {
+    Qadd,
+    Qsub,
+    Qdadd,
+    Qdsub,
+}
+
+/// A single precision floating point register, S0 to S31.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatRegister(pub u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Conversion between single precision and 32 bit integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatConversion {
+    ToSigned,
+    ToUnsigned,
+    FromSigned,
+    FromUnsigned,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// AND, BIC, ORR, ORN, EOR, ADD, ADC, SBC, SUB, RSB, MOV, MVN, TST, TEQ, CMP and CMN.
+    /// `d` is none for the compare and test instructions and `n` is none for MOV and MVN.
+    DataProcessing {
+        op: DataProcessingOp,
+        d: Option<Register>,
+        n: Option<Register>,
+        operand: ShifterOperand,
+        set_flags: SetFlags,
+    },
+    /// LSL, LSR, ASR and ROR with the shift amount in a register.
+    ShiftRegister {
+        shift: Shift,
+        d: Register,
+        n: Register,
+        m: Register,
+        set_flags: SetFlags,
+    },
+    /// Add or subtract a constant to the word aligned PC.
+    Adr {
+        d: Register,
+        imm: u32,
+        add: bool,
+    },
+    MovTop {
+        d: Register,
+        imm: u32,
+    },
+    Mul {
+        d: Register,
+        n: Register,
+        m: Register,
+        set_flags: SetFlags,
+    },
+    Mla {
+        d: Register,
+        n: Register,
+        m: Register,
+        a: Register,
+    },
+    Mls {
+        d: Register,
+        n: Register,
+        m: Register,
+        a: Register,
+    },
+    /// SMULL, UMULL, SMLAL and UMLAL.
+    MulLong {
+        d_lo: Register,
+        d_hi: Register,
+        n: Register,
+        m: Register,
+        signed: bool,
+        accumulate: bool,
+    },
+    Div {
+        d: Register,
+        n: Register,
+        m: Register,
+        signed: bool,
+    },
+    Bfi {
+        d: Register,
+        n: Register,
+        lsb: u32,
+        width: u32,
+    },
+    Bfc {
+        d: Register,
+        lsb: u32,
+        width: u32,
+    },
+    /// SBFX and UBFX.
+    Bfx {
+        d: Register,
+        n: Register,
+        lsb: u32,
+        width: u32,
+        signed: bool,
+    },
+    /// SSAT and USAT.
+    Saturate {
+        d: Register,
+        n: Register,
+        shift: ImmShift,
+        saturate_to: u32,
+        signed: bool,
+    },
+    SaturatingArith {
+        op: SaturatingOp,
+        d: Register,
+        n: Register,
+        m: Register,
+    },
+    /// SXTB, SXTH, UXTB, UXTH and the accumulating versions if `add` is some.
+    Extend {
+        d: Register,
+        m: Register,
+        add: Option<Register>,
+        rotation: u32,
+        bits: u32,
+        signed: bool,
+    },
+    Clz {
+        d: Register,
+        m: Register,
+    },
+    Rbit {
+        d: Register,
+        m: Register,
+    },
+    Rev {
+        d: Register,
+        m: Register,
+    },
+    Rev16 {
+        d: Register,
+        m: Register,
+    },
+    Revsh {
+        d: Register,
+        m: Register,
+    },
+    B {
+        cond: Condition,
+        imm: i32,
+    },
+    Bl {
+        imm: i32,
+    },
+    Blx {
+        m: Register,
+    },
+    Bx {
+        m: Register,
+    },
+    /// CBZ and CBNZ.
+    Cbz {
+        n: Register,
+        imm: u32,
+        nonzero: bool,
+    },
+    /// TBB and TBH.
+    TableBranch {
+        n: Register,
+        m: Register,
+        half: bool,
+    },
+    /// IT, the conditions for each instruction in the IT block in order.
+    It {
+        conditions: Vec<Condition>,
+    },
+    Load {
+        t: Register,
+        address: Address,
+        bits: u32,
+        signed: bool,
+    },
+    Store {
+        t: Register,
+        address: Address,
+        bits: u32,
+    },
+    LoadDual {
+        t: Register,
+        t2: Register,
+        address: Address,
+    },
+    StoreDual {
+        t: Register,
+        t2: Register,
+        address: Address,
+    },
+    /// LDM, LDMDB and POP.
+    LoadMultiple {
+        n: Register,
+        registers: Vec<Register>,
+        wback: bool,
+        decrement_before: bool,
+    },
+    /// STM, STMDB and PUSH.
+    StoreMultiple {
+        n: Register,
+        registers: Vec<Register>,
+        wback: bool,
+        decrement_before: bool,
+    },
+    LoadExclusive {
+        t: Register,
+        n: Register,
+        imm: u32,
+        bits: u32,
+    },
+    StoreExclusive {
+        d: Register,
+        t: Register,
+        n: Register,
+        imm: u32,
+        bits: u32,
+    },
+    ClearExclusive,
+    Mrs {
+        d: Register,
+        sysm: SpecialRegister,
+    },
+    Msr {
+        n: Register,
+        sysm: SpecialRegister,
+    },
+    Cps {
+        disable: bool,
+        primask: bool,
+        faultmask: bool,
+    },
+    Nop,
+    Yield,
+    Wfe,
+    Wfi,
+    Sev,
+    Dmb,
+    Dsb,
+    Isb,
+    Bkpt {
+        imm: u32,
+    },
+    Svc {
+        imm: u32,
+    },
+    Udf {
+        imm: u32,
+    },
+    /// PLD and PLI, memory hints that do not change the state.
+    Preload,
+    /// VADD, VSUB, VMUL and VDIV.
+    FloatArith {
+        op: FloatArithOp,
+        d: FloatRegister,
+        n: FloatRegister,
+        m: FloatRegister,
+    },
+    /// VCMP and VCMPE, compares with zero if `m` is `None`.
+    FloatCompare {
+        d: FloatRegister,
+        m: Option<FloatRegister>,
+    },
+    /// VCVT and VCVTR between single precision and 32 bit integers, the integer is held
+    /// in a floating point register.
+    FloatConvert {
+        d: FloatRegister,
+        m: FloatRegister,
+        conversion: FloatConversion,
+        round_to_zero: bool,
+    },
+    /// VMOV between floating point registers.
+    FloatMove {
+        d: FloatRegister,
+        m: FloatRegister,
+    },
+    /// VMOV immediate, `value` is the expanded single precision value.
+    FloatMoveImmediate {
+        d: FloatRegister,
+        value: u32,
+    },
+    FloatAbs {
+        d: FloatRegister,
+        m: FloatRegister,
+    },
+    FloatNeg {
+        d: FloatRegister,
+        m: FloatRegister,
+    },
+    /// VMOV from a floating point register to a core register.
+    FloatToCore {
+        t: Register,
+        n: FloatRegister,
+    },
+    /// VMOV from a core register to a floating point register.
+    CoreToFloat {
+        n: FloatRegister,
+        t: Register,
+    },
+    /// VLDR, a double precision load reads `count` consecutive single precision registers.
+    FloatLoad {
+        d: FloatRegister,
+        count: u32,
+        address: Address,
+    },
+    /// VSTR, a double precision store writes `count` consecutive single precision registers.
+    FloatStore {
+        d: FloatRegister,
+        count: u32,
+        address: Address,
+    },
+    /// VLDM and VPOP, `count` is the number of single precision registers.
+    FloatLoadMultiple {
+        n: Register,
+        d: FloatRegister,
+        count: u32,
+        wback: bool,
+        decrement_before: bool,
+    },
+    /// VSTM and VPUSH, `count` is the number of single precision registers.
+    FloatStoreMultiple {
+        n: Register,
+        d: FloatRegister,
+        count: u32,
+        wback: bool,
+        decrement_before: bool,
+    },
+    /// VMRS, moves FPSCR to `t` or the FPSCR flags to APSR if `t` is `None`.
+    Vmrs {
+        t: Option<Register>,
+    },
+    /// VMSR, moves `t` to FPSCR.
+    Vmsr {
+        t: Register,
+    },
+}
+
+impl Operation {
+    /// Checks if the operation is a floating point instruction, these need a floating point unit.
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Operation::FloatArith { .. }
+                | Operation::FloatCompare { .. }
+                | Operation::FloatConvert { .. }
+                | Operation::FloatMove { .. }
+                | Operation::FloatMoveImmediate { .. }
+                | Operation::FloatAbs { .. }
+                | Operation::FloatNeg { .. }
+                | Operation::FloatToCore { .. }
+                | Operation::CoreToFloat { .. }
+                | Operation::FloatLoad { .. }
+                | Operation::FloatStore { .. }
+                | Operation::FloatLoadMultiple { .. }
+                | Operation::FloatStoreMultiple { .. }
+                | Operation::Vmrs { .. }
+                | Operation::Vmsr { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionWidth {
+    Bit16,
+    Bit32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub width: InstructionWidth,
+    pub operation: Operation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Not enough bytes for the instruction.
+    Incomplete,
+    /// The encoding is undefined or not a armv7-m instruction.
+    Undefined(u32),
+    /// The encoding is unpredictable.
+    Unpredictable,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Incomplete => write!(f, "incomplete instruction"),
+            DecodeError::Undefined(encoding) => write!(f, "undefined encoding {:#X}", encoding),
+            DecodeError::Unpredictable => write!(f, "unpredictable encoding"),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// Extract bits `high` down to `low` from `value`.
+fn bits(value: u32, high: u32, low: u32) -> u32 {
+    (value >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+fn bit(value: u32, n: u32) -> bool {
+    (value >> n) & 1 == 1
+}
+
+fn reg(value: u32, low: u32) -> Register {
+    Register::from_u32(bits(value, low + 3, low))
+}
+
+fn low_reg(value: u32, low: u32) -> Register {
+    Register::from_u32(bits(value, low + 2, low))
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+fn register_list(list: u32) -> Vec<Register> {
+    (0..16)
+        .filter(|i| bit(list, *i))
+        .map(Register::from_u32)
+        .collect()
+}
+
+/// ThumbExpandImm_C from the reference manual.
+fn thumb_expand_imm(imm12: u32) -> ShifterOperand {
+    if bits(imm12, 11, 10) == 0 {
+        let imm8 = bits(imm12, 7, 0);
+        let value = match bits(imm12, 9, 8) {
+            0b00 => imm8,
+            0b01 => (imm8 << 16) | imm8,
+            0b10 => (imm8 << 24) | (imm8 << 8),
+            _ => (imm8 << 24) | (imm8 << 16) | (imm8 << 8) | imm8,
+        };
+        ShifterOperand::Immediate { value, carry: None }
+    } else {
+        let unrotated = (1 << 7) | bits(imm12, 6, 0);
+        let value = unrotated.rotate_right(bits(imm12, 11, 7));
+        ShifterOperand::Immediate {
+            value,
+            carry: Some(bit(value, 31)),
+        }
+    }
+}
+
+/// Parse a single thumb instruction from little endian bytes.
+pub fn parse(data: &[u8]) -> Result<Instruction> {
+    if data.len() < 2 {
+        return Err(DecodeError::Incomplete);
+    }
+    let hw1 = u16::from_le_bytes([data[0], data[1]]) as u32;
+
+    if matches!(bits(hw1, 15, 11), 0b11101..=0b11111) {
+        if data.len() < 4 {
+            return Err(DecodeError::Incomplete);
+        }
+        let hw2 = u16::from_le_bytes([data[2], data[3]]) as u32;
+        Ok(Instruction {
+            width: InstructionWidth::Bit32,
+            operation: decode_32(hw1, hw2)?,
+        })
+    } else {
+        Ok(Instruction {
+            width: InstructionWidth::Bit16,
+            operation: decode_16(hw1)?,
+        })
+    }
+}
+
+/// Decode a instruction for the armv8-m baseline profile used by the Cortex-M23.
+///
+/// The baseline profile is the armv6-m instruction set extended with CBZ/CBNZ and a few of the
+/// 32 bit armv7-m instructions, encodings outside of that subset are undefined.
+pub fn parse_armv8m_baseline(data: &[u8]) -> Result<Instruction> {
+    let instruction = parse(data)?;
+    let hw1 = u16::from_le_bytes([data[0], data[1]]) as u32;
+    match instruction.width {
+        InstructionWidth::Bit16 => {
+            if matches!(instruction.operation, Operation::It { .. }) {
+                return Err(DecodeError::Undefined(hw1));
+            }
+        }
+        InstructionWidth::Bit32 => {
+            let hw2 = u16::from_le_bytes([data[2], data[3]]) as u32;
+            if !armv8m_baseline_32(hw1, hw2) {
+                return Err(DecodeError::Undefined((hw1 << 16) | hw2));
+            }
+        }
+    }
+    Ok(instruction)
+}
+
+/// Decode a instruction for a armv7-m core without a floating point unit, such as the
+/// Cortex-M3. Floating point encodings are undefined on these cores.
+pub fn parse_armv7m_no_fpu(data: &[u8]) -> Result<Instruction> {
+    let instruction = parse(data)?;
+    if instruction.operation.is_float() {
+        // all floating point instructions are 32 bit
+        let hw1 = u16::from_le_bytes([data[0], data[1]]) as u32;
+        let hw2 = u16::from_le_bytes([data[2], data[3]]) as u32;
+        return Err(DecodeError::Undefined((hw1 << 16) | hw2));
+    }
+    Ok(instruction)
+}
+
+/// Checks if the 32 bit encoding is part of the armv8-m baseline profile.
+fn armv8m_baseline_32(hw1: u32, hw2: u32) -> bool {
+    let branch = hw1 & 0xf800 == 0xf000;
+    let branch_misc = hw2 & 0xd000 == 0x8000;
+    // BL and B.W
+    (branch && matches!(hw2 & 0xd000, 0xd000 | 0x9000))
+        // MSR
+        || (hw1 & 0xffe0 == 0xf380 && branch_misc)
+        // DSB, DMB, ISB and CLREX
+        || (hw1 == 0xf3bf && branch_misc)
+        // MRS
+        || (hw1 & 0xffe0 == 0xf3e0 && branch_misc)
+        // UDF.W
+        || (hw1 & 0xfff0 == 0xf7f0 && hw2 & 0xf000 == 0xa000)
+        // MOVW and MOVT
+        || (hw1 & 0xfb70 == 0xf240 && hw2 & 0x8000 == 0)
+        // SDIV and UDIV
+        || ((hw1 & 0xfff0 == 0xfb90 || hw1 & 0xfff0 == 0xfbb0) && hw2 & 0xf0f0 == 0xf0f0)
+        // LDREX and STREX
+        || hw1 & 0xffe0 == 0xe840
+        // LDREXB, LDREXH, STREXB and STREXH
+        || (hw1 & 0xffe0 == 0xe8c0 && matches!(bits(hw2, 7, 4), 0b0100 | 0b0101))
+}
+
+fn data_processing(
+    op: DataProcessingOp,
+    d: Option<Register>,
+    n: Option<Register>,
+    operand: ShifterOperand,
+    set_flags: SetFlags,
+) -> Operation {
+    Operation::DataProcessing {
+        op,
+        d,
+        n,
+        operand,
+        set_flags,
+    }
+}
+
+fn imm_operand(value: u32) -> ShifterOperand {
+    ShifterOperand::Immediate { value, carry: None }
+}
+
+fn reg_operand(m: Register) -> ShifterOperand {
+    ShifterOperand::Register {
+        m,
+        shift: ImmShift::none(),
+    }
+}
+
+fn decode_16(hw: u32) -> Result<Operation> {
+    Ok(match bits(hw, 15, 10) {
+        0b000000..=0b001111 => decode_16_shift_add_sub_mov_cmp(hw),
+        0b010000 => decode_16_data_processing(hw),
+        0b010001 => decode_16_special_data(hw)?,
+        0b010010 | 0b010011 => Operation::Load {
+            t: low_reg(hw, 8),
+            address: Address::offset(Register::PC, Offset::Immediate(bits(hw, 7, 0) << 2)),
+            bits: 32,
+            signed: false,
+        },
+        0b010100..=0b100111 => decode_16_load_store(hw),
+        0b101000 | 0b101001 => Operation::Adr {
+            d: low_reg(hw, 8),
+            imm: bits(hw, 7, 0) << 2,
+            add: true,
+        },
+        0b101010 | 0b101011 => data_processing(
+            DataProcessingOp::Add,
+            Some(low_reg(hw, 8)),
+            Some(Register::SP),
+            imm_operand(bits(hw, 7, 0) << 2),
+            SetFlags::Never,
+        ),
+        0b101100..=0b101111 => decode_16_misc(hw)?,
+        0b110000 | 0b110001 => Operation::StoreMultiple {
+            n: low_reg(hw, 8),
+            registers: register_list(bits(hw, 7, 0)),
+            wback: true,
+            decrement_before: false,
+        },
+        0b110010 | 0b110011 => {
+            let n = low_reg(hw, 8);
+            let registers = register_list(bits(hw, 7, 0));
+            Operation::LoadMultiple {
+                n,
+                wback: !registers.contains(&n),
+                registers,
+                decrement_before: false,
+            }
+        }
+        0b110100..=0b110111 => match bits(hw, 11, 8) {
+            0b1110 => Operation::Udf {
+                imm: bits(hw, 7, 0),
+            },
+            0b1111 => Operation::Svc {
+                imm: bits(hw, 7, 0),
+            },
+            cond => Operation::B {
+                cond: Condition::from_u32(cond),
+                imm: sign_extend(bits(hw, 7, 0) << 1, 9),
+            },
+        },
+        0b111000 | 0b111001 => Operation::B {
+            cond: Condition::None,
+            imm: sign_extend(bits(hw, 10, 0) << 1, 12),
+        },
+        _ => return Err(DecodeError::Undefined(hw)),
+    })
+}
+
+fn decode_16_shift_add_sub_mov_cmp(hw: u32) -> Operation {
+    let d = low_reg(hw, 0);
+    let m = low_reg(hw, 3);
+    let imm5 = bits(hw, 10, 6);
+    match bits(hw, 13, 9) {
+        0b00000..=0b01011 => data_processing(
+            DataProcessingOp::Mov,
+            Some(d),
+            None,
+            ShifterOperand::Register {
+                m,
+                shift: ImmShift::decode(bits(hw, 12, 11), imm5),
+            },
+            SetFlags::OutsideITBlock,
+        ),
+        0b01100..=0b01111 => {
+            let op = if bit(hw, 9) {
+                DataProcessingOp::Sub
+            } else {
+                DataProcessingOp::Add
+            };
+            let operand = if bit(hw, 10) {
+                imm_operand(bits(hw, 8, 6))
+            } else {
+                reg_operand(low_reg(hw, 6))
+            };
+            data_processing(op, Some(d), Some(m), operand, SetFlags::OutsideITBlock)
+        }
+        opcode => {
+            let dn = low_reg(hw, 8);
+            let imm = imm_operand(bits(hw, 7, 0));
+            match opcode >> 2 {
+                0b100 => data_processing(
+                    DataProcessingOp::Mov,
+                    Some(dn),
+                    None,
+                    imm,
+                    SetFlags::OutsideITBlock,
+                ),
+                0b101 => {
+                    data_processing(DataProcessingOp::Cmp, None, Some(dn), imm, SetFlags::Always)
+                }
+                0b110 => data_processing(
+                    DataProcessingOp::Add,
+                    Some(dn),
+                    Some(dn),
+                    imm,
+                    SetFlags::OutsideITBlock,
+                ),
+                _ => data_processing(
+                    DataProcessingOp::Sub,
+                    Some(dn),
+                    Some(dn),
+                    imm,
+                    SetFlags::OutsideITBlock,
+                ),
+            }
+        }
+    }
+}
+
+fn decode_16_data_processing(hw: u32) -> Operation {
+    let dn = low_reg(hw, 0);
+    let m = low_reg(hw, 3);
+    let flags = SetFlags::OutsideITBlock;
+    let dp = |op| data_processing(op, Some(dn), Some(dn), reg_operand(m), flags);
+    let shift = |shift| Operation::ShiftRegister {
+        shift,
+        d: dn,
+        n: dn,
+        m,
+        set_flags: flags,
+    };
+    match bits(hw, 9, 6) {
+        0b0000 => dp(DataProcessingOp::And),
+        0b0001 => dp(DataProcessingOp::Eor),
+        0b0010 => shift(Shift::Lsl),
+        0b0011 => shift(Shift::Lsr),
+        0b0100 => shift(Shift::Asr),
+        0b0101 => dp(DataProcessingOp::Adc),
+        0b0110 => dp(DataProcessingOp::Sbc),
+        0b0111 => shift(Shift::Ror),
+        0b1000 => data_processing(
+            DataProcessingOp::Tst,
+            None,
+            Some(dn),
+            reg_operand(m),
+            SetFlags::Always,
+        ),
+        // RSB Rd, Rn, #0
+        0b1001 => data_processing(
+            DataProcessingOp::Rsb,
+            Some(dn),
+            Some(m),
+            imm_operand(0),
+            flags,
+        ),
+        0b1010 => data_processing(
+            DataProcessingOp::Cmp,
+            None,
+            Some(dn),
+            reg_operand(m),
+            SetFlags::Always,
+        ),
+        0b1011 => data_processing(
+            DataProcessingOp::Cmn,
+            None,
+            Some(dn),
+            reg_operand(m),
+            SetFlags::Always,
+        ),
+        0b1100 => dp(DataProcessingOp::Orr),
+        0b1101 => Operation::Mul {
+            d: dn,
+            n: m,
+            m: dn,
+            set_flags: flags,
+        },
+        0b1110 => dp(DataProcessingOp::Bic),
+        _ => data_processing(DataProcessingOp::Mvn, Some(dn), None, reg_operand(m), flags),
+    }
+}
+
+fn decode_16_special_data(hw: u32) -> Result<Operation> {
+    let dn = Register::from_u32((bits(hw, 7, 7) << 3) | bits(hw, 2, 0));
+    let m = reg(hw, 3);
+    Ok(match bits(hw, 9, 6) {
+        0b0000..=0b0011 => data_processing(
+            DataProcessingOp::Add,
+            Some(dn),
+            Some(dn),
+            reg_operand(m),
+            SetFlags::Never,
+        ),
+        0b0100 => return Err(DecodeError::Unpredictable),
+        0b0101..=0b0111 => data_processing(
+            DataProcessingOp::Cmp,
+            None,
+            Some(dn),
+            reg_operand(m),
+            SetFlags::Always,
+        ),
+        0b1000..=0b1011 => data_processing(
+            DataProcessingOp::Mov,
+            Some(dn),
+            None,
+            reg_operand(m),
+            SetFlags::Never,
+        ),
+        0b1100 | 0b1101 => Operation::Bx { m },
+        _ => Operation::Blx { m },
+    })
+}
+
+fn decode_16_load_store(hw: u32) -> Operation {
+    let t = low_reg(hw, 0);
+    let n = low_reg(hw, 3);
+    let imm5 = bits(hw, 10, 6);
+    let load = bit(hw, 11);
+    let access = |bits: u32, signed: bool, load: bool, address: Address| {
+        if load {
+            Operation::Load {
+                t,
+                address,
+                bits,
+                signed,
+            }
+        } else {
+            Operation::Store { t, address, bits }
+        }
+    };
+    match bits(hw, 15, 12) {
+        0b0101 => {
+            let address = Address::offset(
+                n,
+                Offset::Register {
+                    m: low_reg(hw, 6),
+                    shift: 0,
+                },
+            );
+            match bits(hw, 11, 9) {
+                0b000 => access(32, false, false, address),
+                0b001 => access(16, false, false, address),
+                0b010 => access(8, false, false, address),
+                0b011 => access(8, true, true, address),
+                0b100 => access(32, false, true, address),
+                0b101 => access(16, false, true, address),
+                0b110 => access(8, false, true, address),
+                _ => access(16, true, true, address),
+            }
+        }
+        0b0110 => access(
+            32,
+            false,
+            load,
+            Address::offset(n, Offset::Immediate(imm5 << 2)),
+        ),
+        0b0111 => access(8, false, load, Address::offset(n, Offset::Immediate(imm5))),
+        0b1000 => access(
+            16,
+            false,
+            load,
+            Address::offset(n, Offset::Immediate(imm5 << 1)),
+        ),
+        _ => {
+            let t = low_reg(hw, 8);
+            let address = Address::offset(Register::SP, Offset::Immediate(bits(hw, 7, 0) << 2));
+            if load {
+                Operation::Load {
+                    t,
+                    address,
+                    bits: 32,
+                    signed: false,
+                }
+            } else {
+                Operation::Store {
+                    t,
+                    address,
+                    bits: 32,
+                }
+            }
+        }
+    }
+}
+
+fn decode_16_misc(hw: u32) -> Result<Operation> {
+    let d = low_reg(hw, 0);
+    let m = low_reg(hw, 3);
+    let extend = |bits: u32, signed: bool| Operation::Extend {
+        d,
+        m,
+        add: None,
+        rotation: 0,
+        bits,
+        signed,
+    };
+    Ok(match bits(hw, 11, 5) {
+        0b0110011 => Operation::Cps {
+            disable: bit(hw, 4),
+            primask: bit(hw, 1),
+            faultmask: bit(hw, 0),
+        },
+        0b0000000..=0b0000011 => data_processing(
+            DataProcessingOp::Add,
+            Some(Register::SP),
+            Some(Register::SP),
+            imm_operand(bits(hw, 6, 0) << 2),
+            SetFlags::Never,
+        ),
+        0b0000100..=0b0000111 => data_processing(
+            DataProcessingOp::Sub,
+            Some(Register::SP),
+            Some(Register::SP),
+            imm_operand(bits(hw, 6, 0) << 2),
+            SetFlags::Never,
+        ),
+        0b0001000..=0b0001111
+        | 0b0011000..=0b0011111
+        | 0b1001000..=0b1001111
+        | 0b1011000..=0b1011111 => Operation::Cbz {
+            n: low_reg(hw, 0),
+            imm: (bits(hw, 9, 9) << 6) | (bits(hw, 7, 3) << 1),
+            nonzero: bit(hw, 11),
+        },
+        0b0010000 | 0b0010001 => extend(16, true),
+        0b0010010 | 0b0010011 => extend(8, true),
+        0b0010100 | 0b0010101 => extend(16, false),
+        0b0010110 | 0b0010111 => extend(8, false),
+        0b0100000..=0b0101111 => {
+            let mut list = bits(hw, 7, 0);
+            if bit(hw, 8) {
+                list |= 1 << 14;
+            }
+            Operation::StoreMultiple {
+                n: Register::SP,
+                registers: register_list(list),
+                wback: true,
+                decrement_before: true,
+            }
+        }
+        0b1010000 | 0b1010001 => Operation::Rev { d, m },
+        0b1010010 | 0b1010011 => Operation::Rev16 { d, m },
+        0b1010110 | 0b1010111 => Operation::Revsh { d, m },
+        0b1100000..=0b1101111 => {
+            let mut list = bits(hw, 7, 0);
+            if bit(hw, 8) {
+                list |= 1 << 15;
+            }
+            Operation::LoadMultiple {
+                n: Register::SP,
+                registers: register_list(list),
+                wback: true,
+                decrement_before: false,
+            }
+        }
+        0b1110000..=0b1110111 => Operation::Bkpt {
+            imm: bits(hw, 7, 0),
+        },
+        0b1111000..=0b1111111 => {
+            let mask = bits(hw, 3, 0);
+            if mask != 0 {
+                Operation::It {
+                    conditions: it_conditions(bits(hw, 7, 4), mask),
+                }
+            } else {
+                hint(bits(hw, 7, 4))
+            }
+        }
+        _ => return Err(DecodeError::Undefined(hw)),
+    })
+}
+
+/// Expands the first condition and mask of a IT instruction into one condition per instruction.
+fn it_conditions(firstcond: u32, mask: u32) -> Vec<Condition> {
+    let count = 4 - mask.trailing_zeros();
+    let mut conditions = vec![Condition::from_u32(firstcond)];
+    for k in 1..count {
+        let then = bit(mask, 4 - k) == bit(firstcond, 0);
+        let cond = if then { firstcond } else { firstcond ^ 1 };
+        conditions.push(Condition::from_u32(cond));
+    }
+    conditions
+}
+
+fn hint(op: u32) -> Operation {
+    match op {
+        1 => Operation::Yield,
+        2 => Operation::Wfe,
+        3 => Operation::Wfi,
+        4 => Operation::Sev,
+        // Unallocated hints execute as NOP.
+        _ => Operation::Nop,
+    }
+}
+
+fn decode_32(hw1: u32, hw2: u32) -> Result<Operation> {
+    let encoding = (hw1 << 16) | hw2;
+    let op1 = bits(hw1, 12, 11);
+    let op2 = bits(hw1, 10, 4);
+    match op1 {
+        0b01 => {
+            if op2 & 0b1100100 == 0b0000000 {
+                decode_32_load_store_multiple(hw1, hw2)
+            } else if op2 & 0b1100100 == 0b0000100 {
+                decode_32_load_store_dual(hw1, hw2)
+            } else if op2 & 0b1100000 == 0b0100000 {
+                decode_32_data_processing_shifted(hw1, hw2)
+            } else {
+                decode_32_coprocessor(hw1, hw2)
+            }
+        }
+        0b10 => {
+            if bit(hw2, 15) {
+                decode_32_branch_misc(hw1, hw2)
+            } else if bit(hw1, 9) {
+                decode_32_plain_immediate(hw1, hw2)
+            } else {
+                decode_32_modified_immediate(hw1, hw2)
+            }
+        }
+        _ => {
+            if op2 & 0b1110001 == 0b0000000 {
+                decode_32_store_single(hw1, hw2)
+            } else if op2 & 0b1100111 == 0b0000001 {
+                decode_32_load(hw1, hw2, 8)
+            } else if op2 & 0b1100111 == 0b0000011 {
+                decode_32_load(hw1, hw2, 16)
+            } else if op2 & 0b1100111 == 0b0000101 {
+                decode_32_load(hw1, hw2, 32)
+            } else if op2 & 0b1110000 == 0b0100000 {
+                decode_32_data_processing_register(hw1, hw2)
+            } else if op2 & 0b1111000 == 0b0110000 {
+                decode_32_multiply(hw1, hw2)
+            } else if op2 & 0b1111000 == 0b0111000 {
+                decode_32_long_multiply_divide(hw1, hw2)
+            } else {
+                Err(DecodeError::Undefined(encoding))
+            }
+        }
+    }
+}
+
+fn decode_32_load_store_multiple(hw1: u32, hw2: u32) -> Result<Operation> {
+    let n = reg(hw1, 0);
+    let wback = bit(hw1, 5);
+    let registers = register_list(hw2);
+    let decrement_before = match bits(hw1, 8, 7) {
+        0b01 => false,
+        0b10 => true,
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    };
+    Ok(if bit(hw1, 4) {
+        Operation::LoadMultiple {
+            n,
+            registers,
+            wback,
+            decrement_before,
+        }
+    } else {
+        Operation::StoreMultiple {
+            n,
+            registers,
+            wback,
+            decrement_before,
+        }
+    })
+}
+
+fn decode_32_load_store_dual(hw1: u32, hw2: u32) -> Result<Operation> {
+    let op1 = bits(hw1, 8, 7);
+    let op2 = bits(hw1, 5, 4);
+    let op3 = bits(hw2, 7, 4);
+    let n = reg(hw1, 0);
+    let t = reg(hw2, 12);
+    let imm8 = bits(hw2, 7, 0);
+
+    Ok(match (op1, op2) {
+        (0b00, 0b00) => Operation::StoreExclusive {
+            d: reg(hw2, 8),
+            t,
+            n,
+            imm: imm8 << 2,
+            bits: 32,
+        },
+        (0b00, 0b01) => Operation::LoadExclusive {
+            t,
+            n,
+            imm: imm8 << 2,
+            bits: 32,
+        },
+        (0b01, 0b00) => match op3 {
+            0b0100 | 0b0101 => Operation::StoreExclusive {
+                d: reg(hw2, 0),
+                t,
+                n,
+                imm: 0,
+                bits: if op3 == 0b0100 { 8 } else { 16 },
+            },
+            _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+        },
+        (0b01, 0b01) => match op3 {
+            0b0000 | 0b0001 => Operation::TableBranch {
+                n,
+                m: reg(hw2, 0),
+                half: op3 == 0b0001,
+            },
+            0b0100 | 0b0101 => Operation::LoadExclusive {
+                t,
+                n,
+                imm: 0,
+                bits: if op3 == 0b0100 { 8 } else { 16 },
+            },
+            _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+        },
+        _ => {
+            let address = Address {
+                n,
+                offset: Offset::Immediate(imm8 << 2),
+                index: bit(hw1, 8),
+                add: bit(hw1, 7),
+                wback: bit(hw1, 5),
+            };
+            let t2 = reg(hw2, 8);
+            if bit(hw1, 4) {
+                Operation::LoadDual { t, t2, address }
+            } else {
+                Operation::StoreDual { t, t2, address }
+            }
+        }
+    })
+}
+
+fn decode_32_data_processing_shifted(hw1: u32, hw2: u32) -> Result<Operation> {
+    let imm5 = (bits(hw2, 14, 12) << 2) | bits(hw2, 7, 6);
+    let operand = ShifterOperand::Register {
+        m: reg(hw2, 0),
+        shift: ImmShift::decode(bits(hw2, 5, 4), imm5),
+    };
+    decode_32_data_processing(hw1, hw2, operand)
+}
+
+fn decode_32_modified_immediate(hw1: u32, hw2: u32) -> Result<Operation> {
+    let imm12 = (bits(hw1, 10, 10) << 11) | (bits(hw2, 14, 12) << 8) | bits(hw2, 7, 0);
+    decode_32_data_processing(hw1, hw2, thumb_expand_imm(imm12))
+}
+
+/// Shared decoding of the data processing instructions with a modified immediate
+/// or a shifted register as second operand.
+fn decode_32_data_processing(hw1: u32, hw2: u32, operand: ShifterOperand) -> Result<Operation> {
+    let s = bit(hw1, 4);
+    let set_flags = if s { SetFlags::Always } else { SetFlags::Never };
+    let rn = reg(hw1, 0);
+    let rd = reg(hw2, 8);
+    let n = Some(rn);
+    let d = Some(rd);
+    let compare = rd == Register::PC && s;
+
+    let (op, d, n) = match bits(hw1, 8, 5) {
+        0b0000 if compare => (DataProcessingOp::Tst, None, n),
+        0b0000 => (DataProcessingOp::And, d, n),
+        0b0001 => (DataProcessingOp::Bic, d, n),
+        0b0010 if rn == Register::PC => (DataProcessingOp::Mov, d, None),
+        0b0010 => (DataProcessingOp::Orr, d, n),
+        0b0011 if rn == Register::PC => (DataProcessingOp::Mvn, d, None),
+        0b0011 => (DataProcessingOp::Orn, d, n),
+        0b0100 if compare => (DataProcessingOp::Teq, None, n),
+        0b0100 => (DataProcessingOp::Eor, d, n),
+        0b1000 if compare => (DataProcessingOp::Cmn, None, n),
+        0b1000 => (DataProcessingOp::Add, d, n),
+        0b1010 => (DataProcessingOp::Adc, d, n),
+        0b1011 => (DataProcessingOp::Sbc, d, n),
+        0b1101 if compare => (DataProcessingOp::Cmp, None, n),
+        0b1101 => (DataProcessingOp::Sub, d, n),
+        0b1110 => (DataProcessingOp::Rsb, d, n),
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    };
+    Ok(data_processing(op, d, n, operand, set_flags))
+}
+
+fn decode_32_plain_immediate(hw1: u32, hw2: u32) -> Result<Operation> {
+    let n = reg(hw1, 0);
+    let d = reg(hw2, 8);
+    let imm12 = (bits(hw1, 10, 10) << 11) | (bits(hw2, 14, 12) << 8) | bits(hw2, 7, 0);
+    let imm16 = (bits(hw1, 3, 0) << 12) | imm12;
+    let lsb = (bits(hw2, 14, 12) << 2) | bits(hw2, 7, 6);
+    let sat_imm = bits(hw2, 4, 0);
+    let saturate_shift = ImmShift::decode(bits(hw1, 5, 5) << 1, lsb);
+
+    Ok(match bits(hw1, 8, 4) {
+        0b00000 if n == Register::PC => Operation::Adr {
+            d,
+            imm: imm12,
+            add: true,
+        },
+        0b00000 => data_processing(
+            DataProcessingOp::Add,
+            Some(d),
+            Some(n),
+            imm_operand(imm12),
+            SetFlags::Never,
+        ),
+        0b00100 => data_processing(
+            DataProcessingOp::Mov,
+            Some(d),
+            None,
+            imm_operand(imm16),
+            SetFlags::Never,
+        ),
+        0b01010 if n == Register::PC => Operation::Adr {
+            d,
+            imm: imm12,
+            add: false,
+        },
+        0b01010 => data_processing(
+            DataProcessingOp::Sub,
+            Some(d),
+            Some(n),
+            imm_operand(imm12),
+            SetFlags::Never,
+        ),
+        0b01100 => Operation::MovTop { d, imm: imm16 },
+        0b10000 | 0b10010 => Operation::Saturate {
+            d,
+            n,
+            shift: saturate_shift,
+            saturate_to: sat_imm + 1,
+            signed: true,
+        },
+        0b10100 => Operation::Bfx {
+            d,
+            n,
+            lsb,
+            width: sat_imm + 1,
+            signed: true,
+        },
+        0b10110 => {
+            if sat_imm < lsb {
+                return Err(DecodeError::Unpredictable);
+            }
+            let width = sat_imm - lsb + 1;
+            if n == Register::PC {
+                Operation::Bfc { d, lsb, width }
+            } else {
+                Operation::Bfi { d, n, lsb, width }
+            }
+        }
+        0b11000 | 0b11010 => Operation::Saturate {
+            d,
+            n,
+            shift: saturate_shift,
+            saturate_to: sat_imm,
+            signed: false,
+        },
+        0b11100 => Operation::Bfx {
+            d,
+            n,
+            lsb,
+            width: sat_imm + 1,
+            signed: false,
+        },
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    })
+}
+
+fn decode_32_branch_misc(hw1: u32, hw2: u32) -> Result<Operation> {
+    let op = bits(hw1, 10, 4);
+    let op1 = bits(hw2, 14, 12);
+    let s = bits(hw1, 10, 10);
+    let j1 = bits(hw2, 13, 13);
+    let j2 = bits(hw2, 11, 11);
+    let imm11 = bits(hw2, 10, 0);
+
+    Ok(match op1 {
+        0b000 | 0b010 => {
+            if op & 0b0111000 != 0b0111000 {
+                let imm =
+                    (s << 20) | (j2 << 19) | (j1 << 18) | (bits(hw1, 5, 0) << 12) | (imm11 << 1);
+                Operation::B {
+                    cond: Condition::from_u32(bits(hw1, 9, 6)),
+                    imm: sign_extend(imm, 21),
+                }
+            } else if op1 == 0b010 && op == 0b1111111 {
+                Operation::Udf {
+                    imm: (bits(hw1, 3, 0) << 12) | bits(hw2, 11, 0),
+                }
+            } else {
+                match op {
+                    0b0111000 | 0b0111001 => Operation::Msr {
+                        n: reg(hw1, 0),
+                        sysm: SpecialRegister::from_sysm(bits(hw2, 7, 0))?,
+                    },
+                    0b0111010 => {
+                        if bits(hw2, 10, 8) != 0 {
+                            return Err(DecodeError::Undefined((hw1 << 16) | hw2));
+                        }
+                        hint(bits(hw2, 7, 0))
+                    }
+                    0b0111011 => match bits(hw2, 7, 4) {
+                        0b0010 => Operation::ClearExclusive,
+                        0b0100 => Operation::Dsb,
+                        0b0101 => Operation::Dmb,
+                        0b0110 => Operation::Isb,
+                        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+                    },
+                    0b0111110 | 0b0111111 => Operation::Mrs {
+                        d: reg(hw2, 8),
+                        sysm: SpecialRegister::from_sysm(bits(hw2, 7, 0))?,
+                    },
+                    _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+                }
+            }
+        }
+        _ => {
+            let i1 = !(j1 ^ s) & 1;
+            let i2 = !(j2 ^ s) & 1;
+            let imm = (s << 24) | (i1 << 23) | (i2 << 22) | (bits(hw1, 9, 0) << 12) | (imm11 << 1);
+            let imm = sign_extend(imm, 25);
+            match op1 {
+                0b001 | 0b011 => Operation::B {
+                    cond: Condition::None,
+                    imm,
+                },
+                0b101 | 0b111 => Operation::Bl { imm },
+                // BLX immediate changes to the unsupported arm state.
+                _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+            }
+        }
+    })
+}
+
+fn decode_32_store_single(hw1: u32, hw2: u32) -> Result<Operation> {
+    let width = match bits(hw1, 6, 5) {
+        0b00 => 8,
+        0b01 => 16,
+        0b10 => 32,
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    };
+    let address = decode_32_address(hw1, hw2)?;
+    Ok(Operation::Store {
+        t: reg(hw2, 12),
+        address,
+        bits: width,
+    })
+}
+
+/// Address calculation for the imm12, imm8 and register forms of the single loads and stores.
+fn decode_32_address(hw1: u32, hw2: u32) -> Result<Address> {
+    let n = reg(hw1, 0);
+    if n == Register::PC && bit(hw1, 4) {
+        // Literal.
+        return Ok(Address {
+            n,
+            offset: Offset::Immediate(bits(hw2, 11, 0)),
+            index: true,
+            add: bit(hw1, 7),
+            wback: false,
+        });
+    }
+
+    Ok(if bit(hw1, 7) {
+        Address::offset(n, Offset::Immediate(bits(hw2, 11, 0)))
+    } else if bit(hw2, 11) {
+        Address {
+            n,
+            offset: Offset::Immediate(bits(hw2, 7, 0)),
+            index: bit(hw2, 10),
+            add: bit(hw2, 9),
+            wback: bit(hw2, 8),
+        }
+    } else if bits(hw2, 10, 6) == 0 {
+        Address::offset(
+            n,
+            Offset::Register {
+                m: reg(hw2, 0),
+                shift: bits(hw2, 5, 4),
+            },
+        )
+    } else {
+        return Err(DecodeError::Undefined((hw1 << 16) | hw2));
+    })
+}
+
+fn decode_32_load(hw1: u32, hw2: u32, width: u32) -> Result<Operation> {
+    let t = reg(hw2, 12);
+    let address = decode_32_address(hw1, hw2)?;
+    if t == Register::PC && width != 32 {
+        // PLD and PLI.
+        return Ok(Operation::Preload);
+    }
+    Ok(Operation::Load {
+        t,
+        address,
+        bits: width,
+        signed: bit(hw1, 8),
+    })
+}
+
+fn decode_32_data_processing_register(hw1: u32, hw2: u32) -> Result<Operation> {
+    let op1 = bits(hw1, 7, 4);
+    let op2 = bits(hw2, 7, 4);
+    let n = reg(hw1, 0);
+    let d = reg(hw2, 8);
+    let m = reg(hw2, 0);
+    let undefined = Err(DecodeError::Undefined((hw1 << 16) | hw2));
+
+    if op2 == 0 && op1 & 0b1000 == 0 {
+        let shift = match op1 >> 1 {
+            0b00 => Shift::Lsl,
+            0b01 => Shift::Lsr,
+            0b10 => Shift::Asr,
+            _ => Shift::Ror,
+        };
+        return Ok(Operation::ShiftRegister {
+            shift,
+            d,
+            n,
+            m,
+            set_flags: if bit(hw1, 4) {
+                SetFlags::Always
+            } else {
+                SetFlags::Never
+            },
+        });
+    }
+
+    if op2 & 0b1000 == 0b1000 && op1 & 0b1000 == 0 {
+        let (width, signed) = match op1 {
+            0b0000 => (16, true),
+            0b0001 => (16, false),
+            0b0100 => (8, true),
+            0b0101 => (8, false),
+            _ => return undefined,
+        };
+        return Ok(Operation::Extend {
+            d,
+            m,
+            add: if n == Register::PC { None } else { Some(n) },
+            rotation: bits(hw2, 5, 4) * 8,
+            bits: width,
+            signed,
+        });
+    }
+
+    if op2 & 0b1100 == 0b1000 && op1 & 0b1100 == 0b1000 {
+        return Ok(match (bits(hw1, 5, 4), bits(hw2, 5, 4)) {
+            (0b00, op) => Operation::SaturatingArith {
+                op: match op {
+                    0b00 => SaturatingOp::Qadd,
+                    0b01 => SaturatingOp::Qdadd,
+                    0b10 => SaturatingOp::Qsub,
+                    _ => SaturatingOp::Qdsub,
+                },
+                d,
+                n,
+                m,
+            },
+            (0b01, 0b00) => Operation::Rev { d, m },
+            (0b01, 0b01) => Operation::Rev16 { d, m },
+            (0b01, 0b10) => Operation::Rbit { d, m },
+            (0b01, 0b11) => Operation::Revsh { d, m },
+            (0b11, 0b00) => Operation::Clz { d, m },
+            _ => return undefined,
+        });
+    }
+
+    // Parallel addition and subtraction are not supported.
+    undefined
+}
+
+fn decode_32_multiply(hw1: u32, hw2: u32) -> Result<Operation> {
+    let n = reg(hw1, 0);
+    let a = reg(hw2, 12);
+    let d = reg(hw2, 8);
+    let m = reg(hw2, 0);
+    Ok(match (bits(hw1, 6, 4), bits(hw2, 5, 4)) {
+        (0b000, 0b00) if a == Register::PC => Operation::Mul {
+            d,
+            n,
+            m,
+            set_flags: SetFlags::Never,
+        },
+        (0b000, 0b00) => Operation::Mla { d, n, m, a },
+        (0b000, 0b01) => Operation::Mls { d, n, m, a },
+        // The signed multiply variants from the DSP extension are not supported.
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    })
+}
+
+fn decode_32_long_multiply_divide(hw1: u32, hw2: u32) -> Result<Operation> {
+    let n = reg(hw1, 0);
+    let d_lo = reg(hw2, 12);
+    let d_hi = reg(hw2, 8);
+    let m = reg(hw2, 0);
+    let mul_long = |signed, accumulate| Operation::MulLong {
+        d_lo,
+        d_hi,
+        n,
+        m,
+        signed,
+        accumulate,
+    };
+    Ok(match (bits(hw1, 6, 4), bits(hw2, 7, 4)) {
+        (0b000, 0b0000) => mul_long(true, false),
+        (0b001, 0b1111) => Operation::Div {
+            d: d_hi,
+            n,
+            m,
+            signed: true,
+        },
+        (0b010, 0b0000) => mul_long(false, false),
+        (0b011, 0b1111) => Operation::Div {
+            d: d_hi,
+            n,
+            m,
+            signed: false,
+        },
+        (0b100, 0b0000) => mul_long(true, true),
+        (0b110, 0b0000) => mul_long(false, true),
+        _ => return Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    })
+}
+
+/// Decodes the coprocessor instructions, only the FPv4-SP floating point extension is
+/// supported. Double precision registers are only used by the load and store instructions.
+fn decode_32_coprocessor(hw1: u32, hw2: u32) -> Result<Operation> {
+    let encoding = (hw1 << 16) | hw2;
+    if bits(hw2, 11, 9) != 0b101 {
+        // Not a floating point instruction.
+        return Err(DecodeError::Undefined(encoding));
+    }
+    let op1 = bits(hw1, 9, 4);
+    if op1 & 0b100000 == 0 {
+        if op1 & 0b111010 == 0 {
+            // 64 bit transfers are not supported.
+            Err(DecodeError::Undefined(encoding))
+        } else {
+            decode_32_float_load_store(hw1, hw2)
+        }
+    } else if op1 & 0b110000 == 0b100000 {
+        if bit(hw2, 4) {
+            decode_32_float_transfer(hw1, hw2)
+        } else {
+            decode_32_float_data_processing(hw1, hw2)
+        }
+    } else {
+        Err(DecodeError::Undefined(encoding))
+    }
+}
+
+fn float_reg(high: u32, low: u32) -> FloatRegister {
+    FloatRegister(((high << 1) | low) as u8)
+}
+
+/// Expands the 8 bit immediate of VMOV to a single precision value.
+fn vfp_expand_imm(imm8: u32) -> u32 {
+    let sign = bits(imm8, 7, 7);
+    let b = bits(imm8, 6, 6);
+    let exponent = ((b ^ 1) << 7) | (if b == 1 { 0b11111 } else { 0 } << 2) | bits(imm8, 5, 4);
+    (sign << 31) | (exponent << 23) | (bits(imm8, 3, 0) << 19)
+}
+
+fn decode_32_float_load_store(hw1: u32, hw2: u32) -> Result<Operation> {
+    let single = !bit(hw2, 8);
+    let d = if single {
+        float_reg(bits(hw2, 15, 12), bits(hw1, 6, 6))
+    } else {
+        // Double precision register D<x> is S<2x> and S<2x + 1>.
+        FloatRegister(((bits(hw1, 6, 6) << 5) | (bits(hw2, 15, 12) << 1)) as u8)
+    };
+    let n = reg(hw1, 0);
+    let imm8 = bits(hw2, 7, 0);
+    let p = bit(hw1, 8);
+    let u = bit(hw1, 7);
+    let w = bit(hw1, 5);
+    let load = bit(hw1, 4);
+
+    if p && !w {
+        let address = Address {
+            n,
+            offset: Offset::Immediate(imm8 << 2),
+            index: true,
+            add: u,
+            wback: false,
+        };
+        let count = if single { 1 } else { 2 };
+        return Ok(if load {
+            Operation::FloatLoad { d, count, address }
+        } else {
+            Operation::FloatStore { d, count, address }
+        });
+    }
+
+    if p == u {
+        return Err(DecodeError::Undefined((hw1 << 16) | hw2));
+    }
+    // The immediate is the number of words transferred.
+    let count = if single { imm8 } else { imm8 & !1 };
+    if count == 0 || d.0 as u32 + count > 32 {
+        return Err(DecodeError::Unpredictable);
+    }
+    Ok(if load {
+        Operation::FloatLoadMultiple {
+            n,
+            d,
+            count,
+            wback: w,
+            decrement_before: p,
+        }
+    } else {
+        Operation::FloatStoreMultiple {
+            n,
+            d,
+            count,
+            wback: w,
+            decrement_before: p,
+        }
+    })
+}
+
+fn decode_32_float_transfer(hw1: u32, hw2: u32) -> Result<Operation> {
+    let t = reg(hw2, 12);
+    let load = bit(hw1, 4);
+    match (bit(hw2, 8), bits(hw1, 7, 5)) {
+        (false, 0b000) => {
+            let n = float_reg(bits(hw1, 3, 0), bits(hw2, 7, 7));
+            Ok(if load {
+                Operation::FloatToCore { t, n }
+            } else {
+                Operation::CoreToFloat { n, t }
+            })
+        }
+        // Only FPSCR is accessible on the armv7-m cores.
+        (false, 0b111) if bits(hw1, 3, 0) == 0b0001 => Ok(if load {
+            Operation::Vmrs {
+                t: if t == Register::PC { None } else { Some(t) },
+            }
+        } else {
+            Operation::Vmsr { t }
+        }),
+        _ => Err(DecodeError::Undefined((hw1 << 16) | hw2)),
+    }
+}
+
+fn decode_32_float_data_processing(hw1: u32, hw2: u32) -> Result<Operation> {
+    let encoding = (hw1 << 16) | hw2;
+    if bit(hw2, 8) {
+        // Double precision is not part of FPv4-SP.
+        return Err(DecodeError::Undefined(encoding));
+    }
+    let opc1 = bits(hw1, 7, 4) & 0b1011;
+    let opc2 = bits(hw1, 3, 0);
+    let op = bit(hw2, 6);
+    let d = float_reg(bits(hw2, 15, 12), bits(hw1, 6, 6));
+    let n = float_reg(bits(hw1, 3, 0), bits(hw2, 7, 7));
+    let m = float_reg(bits(hw2, 3, 0), bits(hw2, 5, 5));
+
+    let arith = |op| Ok(Operation::FloatArith { op, d, n, m });
+    match opc1 {
+        0b0010 if !op => arith(FloatArithOp::Mul),
+        0b0011 if !op => arith(FloatArithOp::Add),
+        0b0011 => arith(FloatArithOp::Sub),
+        0b1000 if !op => arith(FloatArithOp::Div),
+        0b1011 if !op => Ok(Operation::FloatMoveImmediate {
+            d,
+            value: vfp_expand_imm((opc2 << 4) | bits(hw2, 3, 0)),
+        }),
+        0b1011 => match (opc2, bit(hw2, 7)) {
+            (0b0000, false) => Ok(Operation::FloatMove { d, m }),
+            (0b0000, true) => Ok(Operation::FloatAbs { d, m }),
+            (0b0001, false) => Ok(Operation::FloatNeg { d, m }),
+            (0b0100, _) => Ok(Operation::FloatCompare { d, m: Some(m) }),
+            (0b0101, _) => Ok(Operation::FloatCompare { d, m: None }),
+            (0b1000, signed) => Ok(Operation::FloatConvert {
+                d,
+                m,
+                conversion: if signed {
+                    FloatConversion::FromSigned
+                } else {
+                    FloatConversion::FromUnsigned
+                },
+                round_to_zero: false,
+            }),
+            (0b1100 | 0b1101, round_to_zero) => Ok(Operation::FloatConvert {
+                d,
+                m,
+                conversion: if opc2 == 0b1101 {
+                    FloatConversion::ToSigned
+                } else {
+                    FloatConversion::ToUnsigned
+                },
+                round_to_zero,
+            }),
+            _ => Err(DecodeError::Undefined(encoding)),
+        },
+        _ => Err(DecodeError::Undefined(encoding)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_16(hw: u16) -> Operation {
+        let instruction = parse(&hw.to_le_bytes()).unwrap();
+        assert_eq!(instruction.width, InstructionWidth::Bit16);
+        instruction.operation
+    }
+
+    fn parse_32(hw1: u16, hw2: u16) -> Operation {
+        let mut data = hw1.to_le_bytes().to_vec();
+        data.extend_from_slice(&hw2.to_le_bytes());
+        let instruction = parse(&data).unwrap();
+        assert_eq!(instruction.width, InstructionWidth::Bit32);
+        instruction.operation
+    }
+
+    #[test]
+    fn test_modified_immediate() {
+        assert_eq!(
+            thumb_expand_imm(0x0ab),
+            ShifterOperand::Immediate {
+                value: 0xab,
+                carry: None
+            }
+        );
+        assert_eq!(
+            thumb_expand_imm(0x3ab),
+            ShifterOperand::Immediate {
+                value: 0xabababab,
+                carry: None
+            }
+        );
+        // 0xff rotated right by 8
+        assert_eq!(
+            thumb_expand_imm(0x47f),
+            ShifterOperand::Immediate {
+                value: 0xff000000,
+                carry: Some(true)
+            }
+        );
+    }
+
+    #[test]
+    fn test_it_conditions() {
+        // ITTE EQ
+        assert_eq!(
+            parse_16(0xbf06),
+            Operation::It {
+                conditions: vec![Condition::EQ, Condition::EQ, Condition::NE]
+            }
+        );
+        // IT NE
+        assert_eq!(
+            parse_16(0xbf18),
+            Operation::It {
+                conditions: vec![Condition::NE]
+            }
+        );
+    }
+
+    #[test]
+    fn test_16_bit() {
+        // cbz r0, #4
+        assert_eq!(
+            parse_16(0xb110),
+            Operation::Cbz {
+                n: Register::R0,
+                imm: 4,
+                nonzero: false
+            }
+        );
+        // push {r4, lr}
+        assert_eq!(
+            parse_16(0xb510),
+            Operation::StoreMultiple {
+                n: Register::SP,
+                registers: vec![Register::R4, Register::LR],
+                wback: true,
+                decrement_before: true
+            }
+        );
+        // b.n with a offset of -8
+        assert_eq!(
+            parse_16(0xe7fc),
+            Operation::B {
+                cond: Condition::None,
+                imm: -8
+            }
+        );
+    }
+
+    #[test]
+    fn test_32_bit() {
+        // udiv r0, r1, r2
+        assert_eq!(
+            parse_32(0xfbb1, 0xf0f2),
+            Operation::Div {
+                d: Register::R0,
+                n: Register::R1,
+                m: Register::R2,
+                signed: false
+            }
+        );
+        // movw r0, #0x1234
+        assert_eq!(
+            parse_32(0xf241, 0x2034),
+            Operation::DataProcessing {
+                op: DataProcessingOp::Mov,
+                d: Some(Register::R0),
+                n: None,
+                operand: imm_operand(0x1234),
+                set_flags: SetFlags::Never
+            }
+        );
+        // tbb [pc, r0]
+        assert_eq!(
+            parse_32(0xe8df, 0xf000),
+            Operation::TableBranch {
+                n: Register::PC,
+                m: Register::R0,
+                half: false
+            }
+        );
+        // bl #0x100
+        assert_eq!(parse_32(0xf000, 0xf880), Operation::Bl { imm: 0x100 });
+        // ldrd r0, r1, [sp, #8]
+        assert_eq!(
+            parse_32(0xe9dd, 0x0102),
+            Operation::LoadDual {
+                t: Register::R0,
+                t2: Register::R1,
+                address: Address::offset(Register::SP, Offset::Immediate(8))
+            }
+        );
+        // ubfx r0, r1, #4, #8
+        assert_eq!(
+            parse_32(0xf3c1, 0x1007),
+            Operation::Bfx {
+                d: Register::R0,
+                n: Register::R1,
+                lsb: 4,
+                width: 8,
+                signed: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_armv8m_baseline() {
+        let baseline = |encoding: &[u16]| {
+            let data: Vec<u8> = encoding.iter().flat_map(|hw| hw.to_le_bytes()).collect();
+            parse_armv8m_baseline(&data).is_ok()
+        };
+        // cbz r0, #8
+        assert!(baseline(&[0xb110]));
+        // udiv r0, r0, r1
+        assert!(baseline(&[0xfbb0, 0xf0f1]));
+        // sdiv r0, r0, r1
+        assert!(baseline(&[0xfb90, 0xf0f1]));
+        // movw r0, #0x1234
+        assert!(baseline(&[0xf241, 0x2034]));
+        // b.w #4
+        assert!(baseline(&[0xf000, 0xb802]));
+        // ldrex r0, [r1]
+        assert!(baseline(&[0xe851, 0x0f00]));
+        // bl #0x100
+        assert!(baseline(&[0xf000, 0xf880]));
+        // ite eq is armv7-m only
+        assert!(!baseline(&[0xbf0c]));
+        // ubfx r0, r1, #4, #8 is armv7-m only
+        assert!(!baseline(&[0xf3c1, 0x1007]));
+        // ldrd r0, r1, [sp, #8] is armv7-m only
+        assert!(!baseline(&[0xe9dd, 0x0102]));
+    }
+
+    #[test]
+    fn test_floating_point() {
+        let (s0, s1) = (FloatRegister(0), FloatRegister(1));
+        // vadd.f32 s0, s0, s1
+        assert_eq!(
+            parse_32(0xee30, 0x0a20),
+            Operation::FloatArith {
+                op: FloatArithOp::Add,
+                d: s0,
+                n: s0,
+                m: s1
+            }
+        );
+        // vsub.f32 s0, s0, s1
+        assert_eq!(
+            parse_32(0xee30, 0x0a60),
+            Operation::FloatArith {
+                op: FloatArithOp::Sub,
+                d: s0,
+                n: s0,
+                m: s1
+            }
+        );
+        // vdiv.f32 s0, s0, s1
+        assert_eq!(
+            parse_32(0xee80, 0x0a20),
+            Operation::FloatArith {
+                op: FloatArithOp::Div,
+                d: s0,
+                n: s0,
+                m: s1
+            }
+        );
+        // vcmpe.f32 s0, #0
+        assert_eq!(
+            parse_32(0xeeb5, 0x0ac0),
+            Operation::FloatCompare { d: s0, m: None }
+        );
+        // vmrs APSR_nzcv, fpscr
+        assert_eq!(parse_32(0xeef1, 0xfa10), Operation::Vmrs { t: None });
+        // vcvt.s32.f32 s0, s0
+        assert_eq!(
+            parse_32(0xeebd, 0x0ac0),
+            Operation::FloatConvert {
+                d: s0,
+                m: s0,
+                conversion: FloatConversion::ToSigned,
+                round_to_zero: true
+            }
+        );
+        // vcvt.f32.u32 s0, s0
+        assert_eq!(
+            parse_32(0xeeb8, 0x0a40),
+            Operation::FloatConvert {
+                d: s0,
+                m: s0,
+                conversion: FloatConversion::FromUnsigned,
+                round_to_zero: false
+            }
+        );
+        // vmov.f32 s0, #1.0
+        assert_eq!(
+            parse_32(0xeeb7, 0x0a00),
+            Operation::FloatMoveImmediate {
+                d: s0,
+                value: 0x3f80_0000
+            }
+        );
+        // vmov r0, s1
+        assert_eq!(
+            parse_32(0xee10, 0x0a90),
+            Operation::FloatToCore {
+                t: Register::R0,
+                n: s1
+            }
+        );
+        // vldr s1, [r0, #4]
+        assert_eq!(
+            parse_32(0xedd0, 0x0a01),
+            Operation::FloatLoad {
+                d: s1,
+                count: 1,
+                address: Address::offset(Register::R0, Offset::Immediate(4))
+            }
+        );
+        // vpush {d8}
+        assert_eq!(
+            parse_32(0xed2d, 0x8b02),
+            Operation::FloatStoreMultiple {
+                n: Register::SP,
+                d: FloatRegister(16),
+                count: 2,
+                wback: true,
+                decrement_before: true
+            }
+        );
+        // vpop {s16, s17}
+        assert_eq!(
+            parse_32(0xecbd, 0x8a02),
+            Operation::FloatLoadMultiple {
+                n: Register::SP,
+                d: FloatRegister(16),
+                count: 2,
+                wback: true,
+                decrement_before: false
+            }
+        );
+        // vadd.f64 is not part of FPv4-SP
+        assert!(parse(&[0x30, 0xee, 0x01, 0x0b]).is_err());
+        // floating point instructions are not part of armv8-m baseline
+        assert!(parse_armv8m_baseline(&[0x30, 0xee, 0x20, 0x0a]).is_err());
+        // or of cores without a floating point unit
+        assert!(parse(&[0x30, 0xee, 0x20, 0x0a]).is_ok());
+        assert!(parse_armv7m_no_fpu(&[0x30, 0xee, 0x20, 0x0a]).is_err());
+        assert!(parse_armv7m_no_fpu(&[0xbd, 0xec, 0x02, 0x8a]).is_err());
+        assert!(parse_armv7m_no_fpu(&[0x00, 0xbf]).is_ok());
+    }
+}
@@ -1,2 +1,4 @@
 // All the translators for different instruction sets and encodings.
 pub mod armv6_m;
+pub mod armv7_m;
+pub mod riscv;
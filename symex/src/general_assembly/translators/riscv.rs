@@ -0,0 +1,667 @@
+//! Translator for the RV32IMC instruction set
+
+pub mod decoder;
+
+use regex::Regex;
+use tracing::trace;
+
+use crate::{
+    elf_util::{ExpressionType, Variable},
+    general_assembly::{
+        instruction::{Comparison, Condition, CycleCount, Operand, SpecialInstruction},
+        memory_map::Access,
+        project::{target_config::RiscVCore, PCHook, RegisterReadHook},
+        state::GAState,
+        translator::Translatable,
+        DataWord, RunConfig,
+    },
+};
+
+use self::decoder::{
+    AluOp, BranchCondition, CsrOp, CsrSource, Instruction, InstructionWidth, MulDivOp, Operation,
+    Register,
+};
+
+type GAInstruction = crate::general_assembly::instruction::Instruction;
+type GAOperation = crate::general_assembly::instruction::Operation;
+
+/// A decoded instruction together with the core it executes on.
+#[derive(Debug, Clone)]
+pub struct RiscVInstruction {
+    pub instruction: Instruction,
+    pub core: RiscVCore,
+}
+
+/// Number of cycles lost when the pipeline is flushed by a jump or a taken branch.
+const BRANCH_PENALTY: usize = 2;
+
+fn cycle_count_rv32imc_core(operation: &Operation) -> CycleCount {
+    let branch_max_cycle: fn(state: &GAState) -> usize = |state| {
+        if state.get_has_jumped() {
+            1 + BRANCH_PENALTY
+        } else {
+            1
+        }
+    };
+    let load_cycles: fn(state: &GAState) -> usize =
+        |state| 2 + state.last_access_wait_states(Access::Read);
+    let store_cycles: fn(state: &GAState) -> usize =
+        |state| 1 + state.last_access_wait_states(Access::Write);
+    match operation {
+        Operation::Lui { .. } => CycleCount::Value(1),
+        Operation::Auipc { .. } => CycleCount::Value(1),
+        Operation::Jal { .. } => CycleCount::Value(1 + BRANCH_PENALTY),
+        Operation::Jalr { .. } => CycleCount::Value(1 + BRANCH_PENALTY),
+        Operation::Branch { .. } => CycleCount::Function(branch_max_cycle),
+        // assumes a load use stall
+        Operation::Load { .. } => CycleCount::Function(load_cycles),
+        Operation::Store { .. } => CycleCount::Function(store_cycles),
+        Operation::AluImmediate { .. } => CycleCount::Value(1),
+        Operation::Alu { .. } => CycleCount::Value(1),
+        Operation::MulDiv { op, .. } => match op {
+            MulDivOp::Mul | MulDivOp::Mulh | MulDivOp::Mulhsu | MulDivOp::Mulhu => {
+                CycleCount::Value(1)
+            }
+            // Iterative divider, terminates early depending on the operands.
+            MulDivOp::Div | MulDivOp::Divu | MulDivOp::Rem | MulDivOp::Remu => {
+                CycleCount::Value(33)
+            }
+        },
+        Operation::Csr { .. } => CycleCount::Value(1),
+        Operation::Fence => CycleCount::Value(1),
+        // flushes the pipeline
+        Operation::FenceI => CycleCount::Value(1 + BRANCH_PENALTY),
+        Operation::Ecall => CycleCount::Value(0),
+        Operation::Ebreak => CycleCount::Value(0),
+        Operation::Wfi => CycleCount::Value(1),
+    }
+}
+
+impl Translatable for RiscVInstruction {
+    fn translate(&self) -> GAInstruction {
+        let instruction_width = match self.instruction.width {
+            InstructionWidth::Bit32 => 32,
+            InstructionWidth::Bit16 => 16,
+        };
+
+        let operations = match &self.instruction.operation {
+            Operation::Lui { rd, imm: value } => vec![GAOperation::Move {
+                destination: write_register(rd),
+                source: imm(*value),
+            }],
+            Operation::Auipc { rd, imm: value } => vec![
+                current_pc(instruction_width),
+                GAOperation::Add {
+                    destination: write_register(rd),
+                    operand1: local("pc"),
+                    operand2: imm(*value),
+                },
+            ],
+            Operation::Jal { rd, imm: offset } => vec![
+                current_pc(instruction_width),
+                GAOperation::Add {
+                    destination: local("target"),
+                    operand1: local("pc"),
+                    operand2: imm(*offset as u32),
+                },
+                // PC already points to the next instruction
+                GAOperation::Move {
+                    destination: write_register(rd),
+                    source: Operand::Register("PC".to_owned()),
+                },
+                GAOperation::ConditionalJump {
+                    destination: local("target"),
+                    condition: Condition::None,
+                },
+            ],
+            Operation::Jalr {
+                rd,
+                rs1,
+                imm: offset,
+            } => vec![
+                GAOperation::Add {
+                    destination: local("target"),
+                    operand1: read_register(rs1),
+                    operand2: imm(*offset as u32),
+                },
+                GAOperation::And {
+                    destination: local("target"),
+                    operand1: local("target"),
+                    operand2: imm(!0b1),
+                },
+                GAOperation::Move {
+                    destination: write_register(rd),
+                    source: Operand::Register("PC".to_owned()),
+                },
+                GAOperation::ConditionalJump {
+                    destination: local("target"),
+                    condition: Condition::None,
+                },
+            ],
+            Operation::Branch {
+                condition,
+                rs1,
+                rs2,
+                imm: offset,
+            } => vec![
+                current_pc(instruction_width),
+                GAOperation::Add {
+                    destination: local("target"),
+                    operand1: local("pc"),
+                    operand2: imm(*offset as u32),
+                },
+                GAOperation::CompareAndJump {
+                    destination: local("target"),
+                    operand1: read_register(rs1),
+                    operand2: read_register(rs2),
+                    comparison: branch_condition_to_comparison(condition),
+                },
+            ],
+            Operation::Load {
+                rd,
+                rs1,
+                imm: offset,
+                width,
+                signed,
+            } => {
+                let mut operations = vec![
+                    GAOperation::Add {
+                        destination: local("address"),
+                        operand1: read_register(rs1),
+                        operand2: imm(*offset as u32),
+                    },
+                    GAOperation::Move {
+                        destination: Operand::Register("LastAddr".to_owned()),
+                        source: local("address"),
+                    },
+                    GAOperation::Move {
+                        destination: local("data"),
+                        source: Operand::AddressInLocal("address".to_owned(), *width),
+                    },
+                ];
+                if *width < 32 {
+                    operations.push(if *signed {
+                        GAOperation::SignExtend {
+                            destination: local("data"),
+                            operand: local("data"),
+                            bits: *width,
+                        }
+                    } else {
+                        GAOperation::ZeroExtend {
+                            destination: local("data"),
+                            operand: local("data"),
+                            bits: *width,
+                        }
+                    });
+                }
+                operations.push(GAOperation::Move {
+                    destination: write_register(rd),
+                    source: local("data"),
+                });
+                operations
+            }
+            Operation::Store {
+                rs1,
+                rs2,
+                imm: offset,
+                width,
+            } => vec![
+                GAOperation::Add {
+                    destination: local("address"),
+                    operand1: read_register(rs1),
+                    operand2: imm(*offset as u32),
+                },
+                GAOperation::Move {
+                    destination: Operand::Register("LastAddr".to_owned()),
+                    source: local("address"),
+                },
+                GAOperation::Move {
+                    destination: Operand::AddressInLocal("address".to_owned(), *width),
+                    source: read_register(rs2),
+                },
+            ],
+            Operation::AluImmediate {
+                op,
+                rd,
+                rs1,
+                imm: value,
+            } => vec![alu_operation(
+                op,
+                write_register(rd),
+                read_register(rs1),
+                imm(*value as u32),
+            )],
+            Operation::Alu { op, rd, rs1, rs2 } => {
+                let mut operations = vec![];
+                let operand2 = match op {
+                    // only the lowest five bits are used as shift amount
+                    AluOp::Sll | AluOp::Srl | AluOp::Sra => {
+                        operations.push(GAOperation::And {
+                            destination: local("shift"),
+                            operand1: read_register(rs2),
+                            operand2: imm(0b11111),
+                        });
+                        local("shift")
+                    }
+                    _ => read_register(rs2),
+                };
+                operations.push(alu_operation(
+                    op,
+                    write_register(rd),
+                    read_register(rs1),
+                    operand2,
+                ));
+                operations
+            }
+            Operation::MulDiv { op, rd, rs1, rs2 } => mul_div_operations(
+                op,
+                write_register(rd),
+                read_register(rs1),
+                read_register(rs2),
+            ),
+            Operation::Csr {
+                op,
+                rd,
+                source,
+                csr,
+            } => {
+                let csr = Operand::Register(csr_name(*csr));
+                let source = match source {
+                    CsrSource::Register(rs1) => read_register(rs1),
+                    CsrSource::Immediate(value) => imm(*value),
+                };
+                // a set or clear with zero does not write the register
+                let writes = match (op, &source) {
+                    (CsrOp::ReadWrite, _) => true,
+                    (_, Operand::Immidiate(DataWord::Word32(0))) => false,
+                    _ => true,
+                };
+                let mut operations = vec![
+                    GAOperation::Move {
+                        destination: local("source"),
+                        source,
+                    },
+                    GAOperation::Move {
+                        destination: local("old"),
+                        source: csr.clone(),
+                    },
+                ];
+                if writes {
+                    operations.extend(match op {
+                        CsrOp::ReadWrite => vec![GAOperation::Move {
+                            destination: csr,
+                            source: local("source"),
+                        }],
+                        CsrOp::ReadSet => vec![GAOperation::Or {
+                            destination: csr,
+                            operand1: local("old"),
+                            operand2: local("source"),
+                        }],
+                        CsrOp::ReadClear => vec![
+                            GAOperation::Not {
+                                destination: local("source"),
+                                operand: local("source"),
+                            },
+                            GAOperation::And {
+                                destination: csr,
+                                operand1: local("old"),
+                                operand2: local("source"),
+                            },
+                        ],
+                    });
+                }
+                operations.push(GAOperation::Move {
+                    destination: write_register(rd),
+                    source: local("old"),
+                });
+                operations
+            }
+            Operation::Fence | Operation::FenceI => {
+                // memory ordering and instruction caches are not modeled so do nothing
+                vec![]
+            }
+            Operation::Ecall => {
+                // to be used to call a execution environment
+                // this functionality is not modeled so do nothing
+                vec![]
+            }
+            Operation::Ebreak => vec![GAOperation::Special(SpecialInstruction::Bkpt)],
+            Operation::Wfi => vec![GAOperation::Special(SpecialInstruction::Wfi)],
+        };
+
+        let max_cycle_count = match self.core {
+            RiscVCore::Rv32imc => cycle_count_rv32imc_core(&self.instruction.operation),
+        };
+
+        GAInstruction {
+            instruction_size: instruction_width,
+            operations,
+            max_cycle: max_cycle_count,
+        }
+    }
+
+    fn add_hooks(cfg: &mut RunConfig) {
+        let symbolic_sized = |state: &mut GAState| {
+            let value_ptr = state.get_register("a0".to_owned())?;
+            let size = state.get_register("a1".to_owned())?.get_constant().unwrap() * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Integer(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let ra = state.get_register("ra".to_owned())?;
+            state.set_register("PC".to_owned(), ra)?;
+            Ok(())
+        };
+
+        // Matches every instantiation except `f32` and `f64`, those are hooked below.
+        cfg.pc_hooks.push((
+            Regex::new(r"^symbolic_size<([^f].*|f|f[^36].*|f3([^2].*)?|f6([^4].*)?|f(32|64).+)>$")
+                .unwrap(),
+            PCHook::intrinsic(symbolic_sized),
+        ));
+
+        // Floating point values are displayed as floats.
+        let symbolic_sized_float = |state: &mut GAState| {
+            let value_ptr = state.get_register("a0".to_owned())?;
+            let size = state.get_register("a1".to_owned())?.get_constant().unwrap() * 8;
+            trace!(
+                "trying to create symbolic: addr: {:?}, size: {}",
+                value_ptr,
+                size
+            );
+            let name = "any".to_owned() + &state.marked_symbolic.len().to_string();
+            let symb_value = state.ctx.unconstrained(size as u32, &name);
+            state.marked_symbolic.push(Variable {
+                name: Some(name),
+                value: symb_value.clone(),
+                ty: ExpressionType::Float(size as usize),
+            });
+            state.memory.write(&value_ptr, symb_value)?;
+
+            let ra = state.get_register("ra".to_owned())?;
+            state.set_register("PC".to_owned(), ra)?;
+            Ok(())
+        };
+
+        cfg.pc_hooks.push((
+            Regex::new(r"^symbolic_size<f(32|64)>$").unwrap(),
+            PCHook::intrinsic(symbolic_sized_float),
+        ));
+
+        // Let the cycle counters read the number of cycles counted so far.
+        let read_cycle: RegisterReadHook = |state| {
+            Ok(state
+                .ctx
+                .from_u64(state.cycle_count as u64 & u32::MAX as u64, 32))
+        };
+        let read_cycle_high: RegisterReadHook =
+            |state| Ok(state.ctx.from_u64((state.cycle_count as u64) >> 32, 32));
+
+        for register in ["cycle", "mcycle"] {
+            cfg.register_read_hooks
+                .push((register.to_owned(), Box::new(read_cycle)));
+        }
+        for register in ["cycleh", "mcycleh"] {
+            cfg.register_read_hooks
+                .push((register.to_owned(), Box::new(read_cycle_high)));
+        }
+    }
+}
+
+/// Creates the operation for a integer register register or register immediate operation.
+fn alu_operation(
+    op: &AluOp,
+    destination: Operand,
+    operand1: Operand,
+    operand2: Operand,
+) -> GAOperation {
+    match op {
+        AluOp::Add => GAOperation::Add {
+            destination,
+            operand1,
+            operand2,
+        },
+        AluOp::Sub => GAOperation::Sub {
+            destination,
+            operand1,
+            operand2,
+        },
+        AluOp::Sll => GAOperation::Sl {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        },
+        AluOp::Slt => GAOperation::SetOnComparison {
+            destination,
+            operand1,
+            operand2,
+            comparison: Comparison::LT,
+        },
+        AluOp::Sltu => GAOperation::SetOnComparison {
+            destination,
+            operand1,
+            operand2,
+            comparison: Comparison::LTU,
+        },
+        AluOp::Xor => GAOperation::Xor {
+            destination,
+            operand1,
+            operand2,
+        },
+        AluOp::Srl => GAOperation::Srl {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        },
+        AluOp::Sra => GAOperation::Sra {
+            destination,
+            operand: operand1,
+            shift: operand2,
+        },
+        AluOp::Or => GAOperation::Or {
+            destination,
+            operand1,
+            operand2,
+        },
+        AluOp::And => GAOperation::And {
+            destination,
+            operand1,
+            operand2,
+        },
+    }
+}
+
+/// Creates the operations for the multiply and divide extension.
+fn mul_div_operations(
+    op: &MulDivOp,
+    destination: Operand,
+    operand1: Operand,
+    operand2: Operand,
+) -> Vec<GAOperation> {
+    let high = local("high");
+    let low = local("low");
+    let quotient = local("quotient");
+    let mul_long = |signed| GAOperation::MulLong {
+        destination_high: high.clone(),
+        destination_low: low.clone(),
+        operand1: operand1.clone(),
+        operand2: operand2.clone(),
+        signed,
+        accumulate: false,
+    };
+    let division = match op {
+        MulDivOp::Div | MulDivOp::Rem => GAOperation::SDiv {
+            destination: quotient.clone(),
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+        },
+        _ => GAOperation::UDiv {
+            destination: quotient.clone(),
+            operand1: operand1.clone(),
+            operand2: operand2.clone(),
+        },
+    };
+
+    match op {
+        MulDivOp::Mul => vec![GAOperation::Mul {
+            destination,
+            operand1,
+            operand2,
+        }],
+        MulDivOp::Mulh => vec![
+            mul_long(true),
+            GAOperation::Move {
+                destination,
+                source: high,
+            },
+        ],
+        MulDivOp::Mulhu => vec![
+            mul_long(false),
+            GAOperation::Move {
+                destination,
+                source: high,
+            },
+        ],
+        MulDivOp::Mulhsu => {
+            // The unsigned product is too large by operand2 << 32 when operand1 is negative.
+            vec![
+                mul_long(false),
+                GAOperation::Sra {
+                    destination: local("correction"),
+                    operand: operand1,
+                    shift: imm(31),
+                },
+                GAOperation::And {
+                    destination: local("correction"),
+                    operand1: local("correction"),
+                    operand2,
+                },
+                GAOperation::Sub {
+                    destination,
+                    operand1: high,
+                    operand2: local("correction"),
+                },
+            ]
+        }
+        MulDivOp::Div | MulDivOp::Divu => {
+            // Division by zero gives a quotient with all bits set, the division operations
+            // gives zero so set all bits if the divisor is zero.
+            vec![
+                division,
+                GAOperation::SetOnComparison {
+                    destination: local("by_zero"),
+                    operand1: operand2,
+                    operand2: imm(0),
+                    comparison: Comparison::EQ,
+                },
+                GAOperation::Sub {
+                    destination: local("by_zero"),
+                    operand1: imm(0),
+                    operand2: local("by_zero"),
+                },
+                GAOperation::Or {
+                    destination,
+                    operand1: quotient,
+                    operand2: local("by_zero"),
+                },
+            ]
+        }
+        MulDivOp::Rem | MulDivOp::Remu => {
+            // remainder = operand1 - quotient * operand2, as the quotient is zero when dividing
+            // by zero the remainder is operand1 as required.
+            vec![
+                division,
+                GAOperation::Mul {
+                    destination: local("product"),
+                    operand1: quotient,
+                    operand2: operand2.clone(),
+                },
+                GAOperation::Sub {
+                    destination,
+                    operand1,
+                    operand2: local("product"),
+                },
+            ]
+        }
+    }
+}
+
+/// Calculates the address of the current instruction into the local `pc`.
+fn current_pc(instruction_width: u32) -> GAOperation {
+    GAOperation::Sub {
+        destination: local("pc"),
+        operand1: Operand::Register("PC".to_owned()),
+        operand2: imm(instruction_width / 8),
+    }
+}
+
+fn branch_condition_to_comparison(condition: &BranchCondition) -> Comparison {
+    match condition {
+        BranchCondition::Eq => Comparison::EQ,
+        BranchCondition::Ne => Comparison::NE,
+        BranchCondition::Lt => Comparison::LT,
+        BranchCondition::Ge => Comparison::GE,
+        BranchCondition::Ltu => Comparison::LTU,
+        BranchCondition::Geu => Comparison::GEU,
+    }
+}
+
+fn csr_name(csr: u16) -> String {
+    match csr {
+        0x300 => "mstatus".to_owned(),
+        0x301 => "misa".to_owned(),
+        0x304 => "mie".to_owned(),
+        0x305 => "mtvec".to_owned(),
+        0x340 => "mscratch".to_owned(),
+        0x341 => "mepc".to_owned(),
+        0x342 => "mcause".to_owned(),
+        0x343 => "mtval".to_owned(),
+        0x344 => "mip".to_owned(),
+        0xb00 => "mcycle".to_owned(),
+        0xb02 => "minstret".to_owned(),
+        0xb80 => "mcycleh".to_owned(),
+        0xb82 => "minstreth".to_owned(),
+        0xc00 => "cycle".to_owned(),
+        0xc01 => "time".to_owned(),
+        0xc02 => "instret".to_owned(),
+        0xc80 => "cycleh".to_owned(),
+        0xc81 => "timeh".to_owned(),
+        0xc82 => "instreth".to_owned(),
+        0xf14 => "mhartid".to_owned(),
+        _ => format!("csr{:#05x}", csr),
+    }
+}
+
+fn local(name: &str) -> Operand {
+    Operand::Local(name.to_owned())
+}
+
+fn imm(value: u32) -> Operand {
+    Operand::Immidiate(DataWord::Word32(value))
+}
+
+/// Reading `x0` always gives zero.
+fn read_register(reg: &Register) -> Operand {
+    match reg.0 {
+        0 => imm(0),
+        _ => Operand::Register(reg.abi_name().to_owned()),
+    }
+}
+
+/// Writes to `x0` are discarded.
+fn write_register(reg: &Register) -> Operand {
+    match reg.0 {
+        0 => local("discard"),
+        _ => Operand::Register(reg.abi_name().to_owned()),
+    }
+}
@@ -0,0 +1,804 @@
+//! Decoder for the RV32IMC instruction set.
+//!
+//! Decodes the base integer instructions, the multiply and divide extension,
+//! the compressed extension and the CSR instructions.
+//! Compressed instructions are expanded to the operation of their 32 bit equivalent.
+
+use std::fmt::Display;
+
+/// A integer register `x0` to `x31`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register(pub u8);
+
+const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+impl Register {
+    pub const ZERO: Register = Register(0);
+    pub const RA: Register = Register(1);
+    pub const SP: Register = Register(2);
+
+    fn from_u32(value: u32) -> Register {
+        Register((value & 0b11111) as u8)
+    }
+
+    /// One of the registers `x8` to `x15` used by the compressed instructions.
+    fn compressed(value: u32) -> Register {
+        Register(8 + (value & 0b111) as u8)
+    }
+
+    /// The name of the register in the standard calling convention.
+    pub fn abi_name(&self) -> &'static str {
+        ABI_NAMES[self.0 as usize]
+    }
+}
+
+/// The comparison done by a conditional branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchCondition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulDivOp {
+    Mul,
+    Mulh,
+    Mulhsu,
+    Mulhu,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsrOp {
+    ReadWrite,
+    ReadSet,
+    ReadClear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsrSource {
+    Register(Register),
+    Immediate(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Lui {
+        rd: Register,
+        imm: u32,
+    },
+    Auipc {
+        rd: Register,
+        imm: u32,
+    },
+    Jal {
+        rd: Register,
+        imm: i32,
+    },
+    Jalr {
+        rd: Register,
+        rs1: Register,
+        imm: i32,
+    },
+    Branch {
+        condition: BranchCondition,
+        rs1: Register,
+        rs2: Register,
+        imm: i32,
+    },
+    Load {
+        rd: Register,
+        rs1: Register,
+        imm: i32,
+        width: u32,
+        signed: bool,
+    },
+    Store {
+        rs1: Register,
+        rs2: Register,
+        imm: i32,
+        width: u32,
+    },
+    /// Register immediate operation, for shifts `imm` is the shift amount.
+    AluImmediate {
+        op: AluOp,
+        rd: Register,
+        rs1: Register,
+        imm: i32,
+    },
+    Alu {
+        op: AluOp,
+        rd: Register,
+        rs1: Register,
+        rs2: Register,
+    },
+    MulDiv {
+        op: MulDivOp,
+        rd: Register,
+        rs1: Register,
+        rs2: Register,
+    },
+    Csr {
+        op: CsrOp,
+        rd: Register,
+        source: CsrSource,
+        csr: u16,
+    },
+    Fence,
+    FenceI,
+    Ecall,
+    Ebreak,
+    Wfi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionWidth {
+    Bit16,
+    Bit32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub width: InstructionWidth,
+    pub operation: Operation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Not enough bytes for the instruction.
+    Incomplete,
+    /// The encoding is not a RV32IMC instruction.
+    Undefined(u32),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Incomplete => write!(f, "incomplete instruction"),
+            DecodeError::Undefined(encoding) => write!(f, "undefined encoding {:#X}", encoding),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+/// Extract bits `high` down to `low` from `value`.
+fn bits(value: u32, high: u32, low: u32) -> u32 {
+    (value >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+fn bit(value: u32, n: u32) -> u32 {
+    (value >> n) & 1
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+/// Decode the instruction at the start of `data`.
+pub fn parse(data: &[u8]) -> Result<Instruction> {
+    if data.len() < 2 {
+        return Err(DecodeError::Incomplete);
+    }
+    let low = u16::from_le_bytes([data[0], data[1]]) as u32;
+
+    if low & 0b11 != 0b11 {
+        return Ok(Instruction {
+            width: InstructionWidth::Bit16,
+            operation: decode_16(low)?,
+        });
+    }
+
+    // instructions longer than 32 bits are not supported
+    if bits(low, 4, 2) == 0b111 {
+        return Err(DecodeError::Undefined(low));
+    }
+
+    if data.len() < 4 {
+        return Err(DecodeError::Incomplete);
+    }
+    let instruction = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    Ok(Instruction {
+        width: InstructionWidth::Bit32,
+        operation: decode_32(instruction)?,
+    })
+}
+
+fn decode_32(i: u32) -> Result<Operation> {
+    let rd = Register::from_u32(bits(i, 11, 7));
+    let rs1 = Register::from_u32(bits(i, 19, 15));
+    let rs2 = Register::from_u32(bits(i, 24, 20));
+    let funct3 = bits(i, 14, 12);
+    let funct7 = bits(i, 31, 25);
+    let imm_i = sign_extend(bits(i, 31, 20), 12);
+    let imm_s = sign_extend(bits(i, 31, 25) << 5 | bits(i, 11, 7), 12);
+    let imm_b = sign_extend(
+        bit(i, 31) << 12 | bit(i, 7) << 11 | bits(i, 30, 25) << 5 | bits(i, 11, 8) << 1,
+        13,
+    );
+    let imm_u = i & 0xfffff000;
+    let imm_j = sign_extend(
+        bit(i, 31) << 20 | bits(i, 19, 12) << 12 | bit(i, 20) << 11 | bits(i, 30, 21) << 1,
+        21,
+    );
+    let undefined = Err(DecodeError::Undefined(i));
+
+    Ok(match bits(i, 6, 0) {
+        0b0110111 => Operation::Lui { rd, imm: imm_u },
+        0b0010111 => Operation::Auipc { rd, imm: imm_u },
+        0b1101111 => Operation::Jal { rd, imm: imm_j },
+        0b1100111 if funct3 == 0 => Operation::Jalr {
+            rd,
+            rs1,
+            imm: imm_i,
+        },
+        0b1100011 => {
+            let condition = match funct3 {
+                0b000 => BranchCondition::Eq,
+                0b001 => BranchCondition::Ne,
+                0b100 => BranchCondition::Lt,
+                0b101 => BranchCondition::Ge,
+                0b110 => BranchCondition::Ltu,
+                0b111 => BranchCondition::Geu,
+                _ => return undefined,
+            };
+            Operation::Branch {
+                condition,
+                rs1,
+                rs2,
+                imm: imm_b,
+            }
+        }
+        0b0000011 => {
+            let (width, signed) = match funct3 {
+                0b000 => (8, true),
+                0b001 => (16, true),
+                0b010 => (32, false),
+                0b100 => (8, false),
+                0b101 => (16, false),
+                _ => return undefined,
+            };
+            Operation::Load {
+                rd,
+                rs1,
+                imm: imm_i,
+                width,
+                signed,
+            }
+        }
+        0b0100011 => {
+            let width = match funct3 {
+                0b000 => 8,
+                0b001 => 16,
+                0b010 => 32,
+                _ => return undefined,
+            };
+            Operation::Store {
+                rs1,
+                rs2,
+                imm: imm_s,
+                width,
+            }
+        }
+        0b0010011 => {
+            let shamt = bits(i, 24, 20) as i32;
+            let (op, imm) = match (funct3, funct7) {
+                (0b000, _) => (AluOp::Add, imm_i),
+                (0b010, _) => (AluOp::Slt, imm_i),
+                (0b011, _) => (AluOp::Sltu, imm_i),
+                (0b100, _) => (AluOp::Xor, imm_i),
+                (0b110, _) => (AluOp::Or, imm_i),
+                (0b111, _) => (AluOp::And, imm_i),
+                (0b001, 0b0000000) => (AluOp::Sll, shamt),
+                (0b101, 0b0000000) => (AluOp::Srl, shamt),
+                (0b101, 0b0100000) => (AluOp::Sra, shamt),
+                _ => return undefined,
+            };
+            Operation::AluImmediate { op, rd, rs1, imm }
+        }
+        0b0110011 if funct7 == 0b0000001 => {
+            let op = match funct3 {
+                0b000 => MulDivOp::Mul,
+                0b001 => MulDivOp::Mulh,
+                0b010 => MulDivOp::Mulhsu,
+                0b011 => MulDivOp::Mulhu,
+                0b100 => MulDivOp::Div,
+                0b101 => MulDivOp::Divu,
+                0b110 => MulDivOp::Rem,
+                _ => MulDivOp::Remu,
+            };
+            Operation::MulDiv { op, rd, rs1, rs2 }
+        }
+        0b0110011 => {
+            let op = match (funct3, funct7) {
+                (0b000, 0b0000000) => AluOp::Add,
+                (0b000, 0b0100000) => AluOp::Sub,
+                (0b001, 0b0000000) => AluOp::Sll,
+                (0b010, 0b0000000) => AluOp::Slt,
+                (0b011, 0b0000000) => AluOp::Sltu,
+                (0b100, 0b0000000) => AluOp::Xor,
+                (0b101, 0b0000000) => AluOp::Srl,
+                (0b101, 0b0100000) => AluOp::Sra,
+                (0b110, 0b0000000) => AluOp::Or,
+                (0b111, 0b0000000) => AluOp::And,
+                _ => return undefined,
+            };
+            Operation::Alu { op, rd, rs1, rs2 }
+        }
+        0b0001111 => match funct3 {
+            0b000 => Operation::Fence,
+            0b001 => Operation::FenceI,
+            _ => return undefined,
+        },
+        0b1110011 => {
+            let csr = bits(i, 31, 20) as u16;
+            let (op, source) = match funct3 {
+                0b000 => {
+                    return match i {
+                        0x00000073 => Ok(Operation::Ecall),
+                        0x00100073 => Ok(Operation::Ebreak),
+                        0x10500073 => Ok(Operation::Wfi),
+                        _ => undefined,
+                    }
+                }
+                0b001 => (CsrOp::ReadWrite, CsrSource::Register(rs1)),
+                0b010 => (CsrOp::ReadSet, CsrSource::Register(rs1)),
+                0b011 => (CsrOp::ReadClear, CsrSource::Register(rs1)),
+                0b101 => (CsrOp::ReadWrite, CsrSource::Immediate(bits(i, 19, 15))),
+                0b110 => (CsrOp::ReadSet, CsrSource::Immediate(bits(i, 19, 15))),
+                0b111 => (CsrOp::ReadClear, CsrSource::Immediate(bits(i, 19, 15))),
+                _ => return undefined,
+            };
+            Operation::Csr {
+                op,
+                rd,
+                source,
+                csr,
+            }
+        }
+        _ => return undefined,
+    })
+}
+
+fn decode_16(i: u32) -> Result<Operation> {
+    let funct3 = bits(i, 15, 13);
+    // full register fields
+    let rd = Register::from_u32(bits(i, 11, 7));
+    let rs2 = Register::from_u32(bits(i, 6, 2));
+    // compressed register fields
+    let rd_c = Register::compressed(bits(i, 4, 2));
+    let rs1_c = Register::compressed(bits(i, 9, 7));
+    let imm6 = sign_extend(bit(i, 12) << 5 | bits(i, 6, 2), 6);
+    let shamt = bit(i, 12) << 5 | bits(i, 6, 2);
+    let undefined = Err(DecodeError::Undefined(i));
+
+    Ok(match (bits(i, 1, 0), funct3) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => {
+            let imm = bits(i, 12, 11) << 4 | bits(i, 10, 7) << 6 | bit(i, 6) << 2 | bit(i, 5) << 3;
+            if imm == 0 {
+                return undefined;
+            }
+            Operation::AluImmediate {
+                op: AluOp::Add,
+                rd: rd_c,
+                rs1: Register::SP,
+                imm: imm as i32,
+            }
+        }
+        // C.LW
+        (0b00, 0b010) => Operation::Load {
+            rd: rd_c,
+            rs1: rs1_c,
+            imm: compressed_word_offset(i),
+            width: 32,
+            signed: false,
+        },
+        // C.SW
+        (0b00, 0b110) => Operation::Store {
+            rs1: rs1_c,
+            rs2: rd_c,
+            imm: compressed_word_offset(i),
+            width: 32,
+        },
+        // C.ADDI and C.NOP
+        (0b01, 0b000) => Operation::AluImmediate {
+            op: AluOp::Add,
+            rd,
+            rs1: rd,
+            imm: imm6,
+        },
+        // C.JAL
+        (0b01, 0b001) => Operation::Jal {
+            rd: Register::RA,
+            imm: compressed_jump_offset(i),
+        },
+        // C.LI
+        (0b01, 0b010) => Operation::AluImmediate {
+            op: AluOp::Add,
+            rd,
+            rs1: Register::ZERO,
+            imm: imm6,
+        },
+        // C.ADDI16SP
+        (0b01, 0b011) if rd == Register::SP => {
+            let imm = sign_extend(
+                bit(i, 12) << 9
+                    | bit(i, 6) << 4
+                    | bit(i, 5) << 6
+                    | bits(i, 4, 3) << 7
+                    | bit(i, 2) << 5,
+                10,
+            );
+            if imm == 0 {
+                return undefined;
+            }
+            Operation::AluImmediate {
+                op: AluOp::Add,
+                rd: Register::SP,
+                rs1: Register::SP,
+                imm,
+            }
+        }
+        // C.LUI
+        (0b01, 0b011) => {
+            let imm = sign_extend(bit(i, 12) << 17 | bits(i, 6, 2) << 12, 18);
+            if imm == 0 {
+                return undefined;
+            }
+            Operation::Lui {
+                rd,
+                imm: imm as u32,
+            }
+        }
+        (0b01, 0b100) => match bits(i, 11, 10) {
+            // the shift amount must be below 32 on RV32
+            0b00 | 0b01 if shamt >= 32 => return undefined,
+            0b00 => Operation::AluImmediate {
+                op: AluOp::Srl,
+                rd: rs1_c,
+                rs1: rs1_c,
+                imm: shamt as i32,
+            },
+            0b01 => Operation::AluImmediate {
+                op: AluOp::Sra,
+                rd: rs1_c,
+                rs1: rs1_c,
+                imm: shamt as i32,
+            },
+            0b10 => Operation::AluImmediate {
+                op: AluOp::And,
+                rd: rs1_c,
+                rs1: rs1_c,
+                imm: imm6,
+            },
+            _ => {
+                if bit(i, 12) == 1 {
+                    return undefined;
+                }
+                let op = match bits(i, 6, 5) {
+                    0b00 => AluOp::Sub,
+                    0b01 => AluOp::Xor,
+                    0b10 => AluOp::Or,
+                    _ => AluOp::And,
+                };
+                Operation::Alu {
+                    op,
+                    rd: rs1_c,
+                    rs1: rs1_c,
+                    rs2: rd_c,
+                }
+            }
+        },
+        // C.J
+        (0b01, 0b101) => Operation::Jal {
+            rd: Register::ZERO,
+            imm: compressed_jump_offset(i),
+        },
+        // C.BEQZ and C.BNEZ
+        (0b01, 0b110 | 0b111) => {
+            let imm = sign_extend(
+                bit(i, 12) << 8
+                    | bits(i, 11, 10) << 3
+                    | bits(i, 6, 5) << 6
+                    | bits(i, 4, 3) << 1
+                    | bit(i, 2) << 5,
+                9,
+            );
+            Operation::Branch {
+                condition: if funct3 == 0b110 {
+                    BranchCondition::Eq
+                } else {
+                    BranchCondition::Ne
+                },
+                rs1: rs1_c,
+                rs2: Register::ZERO,
+                imm,
+            }
+        }
+        // C.SLLI
+        (0b10, 0b000) => {
+            if shamt >= 32 {
+                return undefined;
+            }
+            Operation::AluImmediate {
+                op: AluOp::Sll,
+                rd,
+                rs1: rd,
+                imm: shamt as i32,
+            }
+        }
+        // C.LWSP
+        (0b10, 0b010) => {
+            if rd == Register::ZERO {
+                return undefined;
+            }
+            Operation::Load {
+                rd,
+                rs1: Register::SP,
+                imm: (bit(i, 12) << 5 | bits(i, 6, 4) << 2 | bits(i, 3, 2) << 6) as i32,
+                width: 32,
+                signed: false,
+            }
+        }
+        (0b10, 0b100) => match (bit(i, 12), rd, rs2) {
+            (0, Register::ZERO, _) => return undefined,
+            // C.JR
+            (0, rs1, Register::ZERO) => Operation::Jalr {
+                rd: Register::ZERO,
+                rs1,
+                imm: 0,
+            },
+            // C.MV
+            (0, rd, rs2) => Operation::Alu {
+                op: AluOp::Add,
+                rd,
+                rs1: Register::ZERO,
+                rs2,
+            },
+            // C.EBREAK
+            (_, Register::ZERO, Register::ZERO) => Operation::Ebreak,
+            // C.JALR
+            (_, rs1, Register::ZERO) => Operation::Jalr {
+                rd: Register::RA,
+                rs1,
+                imm: 0,
+            },
+            // C.ADD
+            (_, rd, rs2) => Operation::Alu {
+                op: AluOp::Add,
+                rd,
+                rs1: rd,
+                rs2,
+            },
+        },
+        // C.SWSP
+        (0b10, 0b110) => Operation::Store {
+            rs1: Register::SP,
+            rs2,
+            imm: (bits(i, 12, 9) << 2 | bits(i, 8, 7) << 6) as i32,
+            width: 32,
+        },
+        // the remaining encodings are floating point or RV64 instructions
+        _ => return undefined,
+    })
+}
+
+/// Offset used by C.LW and C.SW.
+fn compressed_word_offset(i: u32) -> i32 {
+    (bits(i, 12, 10) << 3 | bit(i, 6) << 2 | bit(i, 5) << 6) as i32
+}
+
+/// Offset used by C.J and C.JAL.
+fn compressed_jump_offset(i: u32) -> i32 {
+    sign_extend(
+        bit(i, 12) << 11
+            | bit(i, 11) << 4
+            | bits(i, 10, 9) << 8
+            | bit(i, 8) << 10
+            | bit(i, 7) << 6
+            | bit(i, 6) << 7
+            | bits(i, 5, 3) << 1
+            | bit(i, 2) << 5,
+        12,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_16(i: u16) -> Operation {
+        let instruction = parse(&i.to_le_bytes()).unwrap();
+        assert_eq!(instruction.width, InstructionWidth::Bit16);
+        instruction.operation
+    }
+
+    fn parse_32(i: u32) -> Operation {
+        let instruction = parse(&i.to_le_bytes()).unwrap();
+        assert_eq!(instruction.width, InstructionWidth::Bit32);
+        instruction.operation
+    }
+
+    #[test]
+    fn test_32_bit() {
+        // addi a0, a0, 1
+        assert_eq!(
+            parse_32(0x00150513),
+            Operation::AluImmediate {
+                op: AluOp::Add,
+                rd: Register(10),
+                rs1: Register(10),
+                imm: 1
+            }
+        );
+        // lui a0, 0x12345
+        assert_eq!(
+            parse_32(0x12345537),
+            Operation::Lui {
+                rd: Register(10),
+                imm: 0x12345000
+            }
+        );
+        // jal ra, 8
+        assert_eq!(
+            parse_32(0x008000ef),
+            Operation::Jal {
+                rd: Register::RA,
+                imm: 8
+            }
+        );
+        // beq a0, a1, 8
+        assert_eq!(
+            parse_32(0x00b50463),
+            Operation::Branch {
+                condition: BranchCondition::Eq,
+                rs1: Register(10),
+                rs2: Register(11),
+                imm: 8
+            }
+        );
+        // lw a0, -4(s0)
+        assert_eq!(
+            parse_32(0xffc42503),
+            Operation::Load {
+                rd: Register(10),
+                rs1: Register(8),
+                imm: -4,
+                width: 32,
+                signed: false
+            }
+        );
+        // sw a0, -20(s0)
+        assert_eq!(
+            parse_32(0xfea42623),
+            Operation::Store {
+                rs1: Register(8),
+                rs2: Register(10),
+                imm: -20,
+                width: 32
+            }
+        );
+        // div a0, a0, a1
+        assert_eq!(
+            parse_32(0x02b54533),
+            Operation::MulDiv {
+                op: MulDivOp::Div,
+                rd: Register(10),
+                rs1: Register(10),
+                rs2: Register(11)
+            }
+        );
+        // csrr a0, mcycle
+        assert_eq!(
+            parse_32(0xb0002573),
+            Operation::Csr {
+                op: CsrOp::ReadSet,
+                rd: Register(10),
+                source: CsrSource::Register(Register::ZERO),
+                csr: 0xb00
+            }
+        );
+    }
+
+    #[test]
+    fn test_16_bit() {
+        // c.addi sp, -16
+        assert_eq!(
+            parse_16(0x1141),
+            Operation::AluImmediate {
+                op: AluOp::Add,
+                rd: Register::SP,
+                rs1: Register::SP,
+                imm: -16
+            }
+        );
+        // c.swsp ra, 12(sp)
+        assert_eq!(
+            parse_16(0xc606),
+            Operation::Store {
+                rs1: Register::SP,
+                rs2: Register::RA,
+                imm: 12,
+                width: 32
+            }
+        );
+        // c.lwsp ra, 12(sp)
+        assert_eq!(
+            parse_16(0x40b2),
+            Operation::Load {
+                rd: Register::RA,
+                rs1: Register::SP,
+                imm: 12,
+                width: 32,
+                signed: false
+            }
+        );
+        // c.lw a0, 0(a1)
+        assert_eq!(
+            parse_16(0x4188),
+            Operation::Load {
+                rd: Register(10),
+                rs1: Register(11),
+                imm: 0,
+                width: 32,
+                signed: false
+            }
+        );
+        // c.mv a0, a1
+        assert_eq!(
+            parse_16(0x852e),
+            Operation::Alu {
+                op: AluOp::Add,
+                rd: Register(10),
+                rs1: Register::ZERO,
+                rs2: Register(11)
+            }
+        );
+        // ret
+        assert_eq!(
+            parse_16(0x8082),
+            Operation::Jalr {
+                rd: Register::ZERO,
+                rs1: Register::RA,
+                imm: 0
+            }
+        );
+        // c.j -2
+        assert_eq!(
+            parse_16(0xbffd),
+            Operation::Jal {
+                rd: Register::ZERO,
+                imm: -2
+            }
+        );
+        // the all zero encoding is illegal
+        assert_eq!(parse(&[0, 0]), Err(DecodeError::Undefined(0)));
+    }
+}
@@ -9,7 +9,7 @@ use super::{
     executor::{GAExecutor, PathResult},
     path_selection::DFSPathSelection,
     project::Project,
-    Result,
+    GAError, Result,
 };
 
 #[derive(Debug)]
@@ -58,7 +58,16 @@ impl VM {
                 executor.state.constraints.assert(&constraint);
             }
 
-            let result = executor.resume_execution()?;
+            // A path that exceeds a limit or faults ends without stopping the other paths.
+            let result = match executor.resume_execution() {
+                Ok(result) => result,
+                Err(GAError::LimitReached(limit)) => PathResult::LimitReached(limit),
+                Err(GAError::BusFault(fault)) => PathResult::Faliure(fault.to_string()),
+                Err(e) => return Err(e),
+            };
+            executor
+                .state
+                .notify(|observer, state| observer.path_end(state, &result));
             return Ok(Some((result, executor.state)));
         }
         Ok(None)
@@ -1,3 +1,4 @@
+pub mod chrome_trace;
 pub mod elf_util;
 pub mod general_assembly;
 pub mod memory;
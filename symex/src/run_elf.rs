@@ -9,19 +9,72 @@ use tracing::{debug, info, trace};
 use crate::{
     elf_util::{ErrorReason, PathStatus, VisualPathResult},
     general_assembly::{
-        self, executor::PathResult, project::PCHook, state::GAState, GAError, RunConfig,
+        self, executor::PathResult, profile::Profile, project::PCHook, state::GAState,
+        timing::FunctionSpans, GAError, RunConfig,
     },
-    smt::DContext,
+    smt::{DContext, DSolver},
 };
 
+/// Longest label of a timing marker that is read, longer labels are cut.
+const MAX_LABEL_LENGTH: u64 = 256;
+
+/// Read the label passed to a timing marker function in `symex_lib`. The marker functions are
+/// `extern "C"` and take the address of the label in the first and its length in the second
+/// argument register.
+fn marker_label(state: &mut GAState) -> Result<String, GAError> {
+    let target = state.project.get_target();
+    let address = state.get_register(target.argument_register(0))?;
+    let len = state.get_register(target.argument_register(1))?;
+    match (address.get_constant(), len.get_constant()) {
+        (Some(address), Some(len)) => state.read_string(address, len.min(MAX_LABEL_LENGTH)),
+        _ => Ok("<symbolic label>".to_owned()),
+    }
+}
+
+/// Continue at the return address, as if the hooked function returned.
+fn return_to_caller(state: &mut GAState) -> Result<(), GAError> {
+    let return_address_register = state.project.get_target().return_address_register();
+    let lr = state.get_register(return_address_register.to_owned())?;
+    state.set_register("PC".to_owned(), lr)
+}
+
+/// Hook of `symex_lib::cycle_mark_raw`, timestamped when the marker function is called.
+pub(crate) fn cycle_mark(state: &mut GAState) -> Result<(), GAError> {
+    let label = marker_label(state)?;
+    trace!("Mark {} (cycle count: {})", label, state.cycle_count);
+    state.mark(label);
+    return_to_caller(state)
+}
+
+/// Hook of `symex_lib::cycle_span_begin_raw`.
+pub(crate) fn cycle_span_begin(state: &mut GAState) -> Result<(), GAError> {
+    let label = marker_label(state)?;
+    trace!("Begin span {} (cycle count: {})", label, state.cycle_count);
+    state.span_begin(label);
+    return_to_caller(state)
+}
+
+/// Hook of `symex_lib::cycle_span_end_raw`.
+pub(crate) fn cycle_span_end(state: &mut GAState) -> Result<(), GAError> {
+    let label = marker_label(state)?;
+    trace!("End span {} (cycle count: {})", label, state.cycle_count);
+    state.span_end(label);
+    return_to_caller(state)
+}
+
 fn add_architecture_independent_hooks(cfg: &mut RunConfig) {
     // intrinsic functions
     let start_cyclecount = |state: &mut GAState| {
         state.cycle_count = 0;
+        state.profile = Profile::default();
+        state.function_spans = FunctionSpans::default();
         trace!("Reset the cycle count (cycle count: {})", state.cycle_count);
 
         // jump back to where the function was called from
-        let lr = state.get_register("LR".to_owned()).unwrap();
+        let return_address_register = state.project.get_target().return_address_register();
+        let lr = state
+            .get_register(return_address_register.to_owned())
+            .unwrap();
         state.set_register("PC".to_owned(), lr)?;
         Ok(())
     };
@@ -34,7 +87,10 @@ fn add_architecture_independent_hooks(cfg: &mut RunConfig) {
         );
 
         // jump back to where the function was called from
-        let lr = state.get_register("LR".to_owned()).unwrap();
+        let return_address_register = state.project.get_target().return_address_register();
+        let lr = state
+            .get_register(return_address_register.to_owned())
+            .unwrap();
         state.set_register("PC".to_owned(), lr)?;
         Ok(())
     };
@@ -42,28 +98,42 @@ fn add_architecture_independent_hooks(cfg: &mut RunConfig) {
     // add all pc hooks
     cfg.pc_hooks.push((
         Regex::new(r"^panic_cold_explicit$").unwrap(),
-        PCHook::EndFaliure("explicit panic"),
+        PCHook::EndFaliure("explicit panic".to_owned()),
     ));
     cfg.pc_hooks.push((
         Regex::new(r"^panic_bounds_check$").unwrap(),
-        PCHook::EndFaliure("bounds check panic"),
+        PCHook::EndFaliure("bounds check panic".to_owned()),
     ));
     cfg.pc_hooks
         .push((Regex::new(r"^suppress_path$").unwrap(), PCHook::Suppress));
     cfg.pc_hooks.push((
         Regex::new(r"^unreachable_unchecked$").unwrap(),
-        PCHook::EndFaliure("reach a unreachable unchecked call undefined behavior"),
+        PCHook::EndFaliure("reach a unreachable unchecked call undefined behavior".to_owned()),
     ));
     cfg.pc_hooks.push((
         Regex::new(r"^start_cyclecount$").unwrap(),
-        PCHook::Intrinsic(start_cyclecount),
+        PCHook::intrinsic(start_cyclecount),
     ));
     cfg.pc_hooks.push((
         Regex::new(r"^end_cyclecount$").unwrap(),
-        PCHook::Intrinsic(end_cyclecount),
+        PCHook::intrinsic(end_cyclecount),
+    ));
+    cfg.pc_hooks.push((
+        Regex::new(r"^cycle_mark_raw$").unwrap(),
+        PCHook::intrinsic(cycle_mark),
+    ));
+    cfg.pc_hooks.push((
+        Regex::new(r"^cycle_span_begin_raw$").unwrap(),
+        PCHook::intrinsic(cycle_span_begin),
+    ));
+    cfg.pc_hooks.push((
+        Regex::new(r"^cycle_span_end_raw$").unwrap(),
+        PCHook::intrinsic(cycle_span_end),
+    ));
+    cfg.pc_hooks.push((
+        Regex::new(r"^panic$").unwrap(),
+        PCHook::EndFaliure("panic".to_owned()),
     ));
-    cfg.pc_hooks
-        .push((Regex::new(r"^panic$").unwrap(), PCHook::EndFaliure("panic")));
 }
 
 /// Run symbolic execution on a elf file where `path` is the path to the ELF file and
@@ -77,18 +147,38 @@ pub fn run_elf(
     let context = Box::new(DContext::new());
     let context = Box::leak(context);
 
+    let user_pc_hooks: Vec<String> = cfg
+        .pc_hooks
+        .iter()
+        .map(|(name, _)| name.as_str().to_owned())
+        .collect();
     add_architecture_independent_hooks(&mut cfg);
 
     let project = Box::new(general_assembly::project::Project::from_path(
         path, &mut cfg,
     )?);
+    if cfg.show_path_results {
+        for name in project.get_unmatched_pc_hooks() {
+            if user_pc_hooks.contains(name) {
+                println!("Warning: no function matched the PC hook {}", name);
+            }
+        }
+    }
     let project = Box::leak(project);
     let end_pc = 0xFFFFFFFE;
     project.add_pc_hook(end_pc, PCHook::EndSuccess);
     debug!("Created project: {:?}", project);
 
     info!("create VM");
-    let mut vm = general_assembly::vm::VM::new(project, context, function, end_pc)?;
+    let mut vm = match cfg.exception_handler {
+        Some(exception_number) => {
+            let solver = DSolver::new(context);
+            let mut state = GAState::new(context, project, solver, function, end_pc)?;
+            state.start_as_exception_handler(exception_number)?;
+            general_assembly::vm::VM::new_with_state(project, state)
+        }
+        None => general_assembly::vm::VM::new(project, context, function, end_pc)?,
+    };
 
     run_elf_paths(&mut vm, &cfg)
 }
@@ -117,11 +207,28 @@ fn run_elf_paths(
             general_assembly::executor::PathResult::Success(_v) => PathStatus::Ok(None),
             general_assembly::executor::PathResult::Faliure(reason) => {
                 PathStatus::Failed(ErrorReason {
-                    error_message: reason.to_owned(),
+                    error_message: reason,
+                    location: state
+                        .last_user_pc
+                        .and_then(|pc| state.project.get_source_location(pc))
+                        .cloned(),
+                    call_site: state.hook_call_site().cloned(),
+                    stack_trace: state.backtrace(),
                 })
             }
-            general_assembly::executor::PathResult::AssumptionUnsat => todo!(),
-            general_assembly::executor::PathResult::Suppress => todo!(),
+            general_assembly::executor::PathResult::LimitReached(limit) => {
+                if cfg.show_path_results {
+                    println!(
+                        "Warning: path {} ended early because the {}, its cycle count is a lower bound",
+                        path_num, limit
+                    );
+                }
+                PathStatus::LimitReached(limit.to_string())
+            }
+            general_assembly::executor::PathResult::AssumptionUnsat
+            | general_assembly::executor::PathResult::Suppress => {
+                unreachable!("suppressed and unsatisfiable paths are skipped above")
+            }
         };
 
         let result = VisualPathResult::from_state(state, path_num, v_path_result)?;
@@ -132,6 +239,10 @@ fn run_elf_paths(
         path_results.push(result);
     }
     if cfg.show_path_results {
+        let worst_case = Profile::worst_case(path_results.iter().map(|result| &result.profile));
+        if !worst_case.is_empty() {
+            println!("Worst case profile:\n{}", worst_case);
+        }
         println!("time: {:?}", start.elapsed());
     }
     Ok(path_results)
@@ -259,7 +259,7 @@ impl BoolectorExpr {
         }
     }
 
-    fn get_ctx(&self) -> BoolectorSolverContext {
+    pub(super) fn get_ctx(&self) -> BoolectorSolverContext {
         let ctx = self.0.get_btor();
         BoolectorSolverContext { ctx }
     }
@@ -0,0 +1,411 @@
+//! Single precision IEEE 754 floating point operations expressed as bit-vector operations.
+//!
+//! All operations round to nearest with ties to even and keep subnormal values, this is the
+//! default behaviour of the ARM floating point units. NaN operands are propagated and quieted
+//! the same way as on ARM, a signalling NaN takes precedence over a quiet NaN and the first
+//! operand takes precedence over the second.
+//!
+//! The floating point exception flags are not modeled.
+use super::{BoolectorExpr, BoolectorSolverContext};
+
+/// Width of the exponents in the intermediate values, wide enough to never overflow.
+const EXPONENT_WIDTH: u32 = 16;
+
+const SIGN_BIT: u64 = 0x8000_0000;
+const MAGNITUDE_MASK: u64 = 0x7fff_ffff;
+const INFINITY: u64 = 0x7f80_0000;
+const QUIET_BIT: u64 = 0x0040_0000;
+const DEFAULT_NAN: u64 = 0x7fc0_0000;
+
+/// A single precision value split into its parts.
+struct Unpacked {
+    /// Sign bit.
+    sign: BoolectorExpr,
+
+    /// Biased exponent, subnormal values and zero have the exponent one.
+    exponent: BoolectorExpr,
+
+    /// 24 bit significand including the implicit bit.
+    significand: BoolectorExpr,
+}
+
+impl Unpacked {
+    fn ite(condition: &BoolectorExpr, then: &Unpacked, otherwise: &Unpacked) -> Unpacked {
+        Unpacked {
+            sign: condition.ite(&then.sign, &otherwise.sign),
+            exponent: condition.ite(&then.exponent, &otherwise.exponent),
+            significand: condition.ite(&then.significand, &otherwise.significand),
+        }
+    }
+}
+
+impl BoolectorExpr {
+    fn fp32_unpack(&self) -> Unpacked {
+        assert_eq!(self.len(), 32);
+        let ctx = self.get_ctx();
+        let exponent = self.slice(23, 30);
+        let is_subnormal = exponent._eq(&ctx.zero(8));
+        Unpacked {
+            sign: self.slice(31, 31),
+            exponent: is_subnormal
+                .ite(&ctx.one(8), &exponent)
+                .zero_ext(EXPONENT_WIDTH),
+            significand: is_subnormal.not().concat(&self.slice(0, 22)),
+        }
+    }
+
+    fn fp32_magnitude(&self) -> Self {
+        self.and(&self.get_ctx().from_u64(MAGNITUDE_MASK, 32))
+    }
+
+    /// Check if a single precision value is NaN, the result has width `1`.
+    pub fn fp32_is_nan(&self) -> Self {
+        let ctx = self.get_ctx();
+        self.fp32_magnitude().ugt(&ctx.from_u64(INFINITY, 32))
+    }
+
+    fn fp32_is_signalling_nan(&self) -> Self {
+        let ctx = self.get_ctx();
+        let quiet = self.slice(22, 22);
+        self.fp32_is_nan().and(&quiet._eq(&ctx.zero(1)))
+    }
+
+    fn fp32_is_infinite(&self) -> Self {
+        let ctx = self.get_ctx();
+        self.fp32_magnitude()._eq(&ctx.from_u64(INFINITY, 32))
+    }
+
+    fn fp32_is_zero(&self) -> Self {
+        let ctx = self.get_ctx();
+        self.fp32_magnitude()._eq(&ctx.zero(32))
+    }
+
+    /// Selects the NaN to return when at least one of the operands is NaN.
+    fn fp32_propagate_nan(&self, other: &Self) -> Self {
+        let quiet = self.get_ctx().from_u64(QUIET_BIT, 32);
+        self.fp32_is_signalling_nan().ite(
+            &self.or(&quiet),
+            &other
+                .fp32_is_signalling_nan()
+                .ite(&other.or(&quiet), &self.fp32_is_nan().ite(self, other)),
+        )
+    }
+
+    /// Maps a value to a signed integer with the same order, `+0` and `-0` both map to zero.
+    fn fp32_order_key(&self) -> Self {
+        let ctx = self.get_ctx();
+        let magnitude = self.fp32_magnitude();
+        self.slice(31, 31)
+            ._eq(&ctx.one(1))
+            .ite(&ctx.zero(32).sub(&magnitude), &magnitude)
+    }
+
+    /// Single precision addition.
+    pub fn fp32_add(&self, other: &Self) -> Self {
+        assert_eq!(self.len(), 32);
+        assert_eq!(other.len(), 32);
+        let ctx = self.get_ctx();
+
+        // Order the operands so that `x` has the largest magnitude.
+        let swap = self.fp32_magnitude().ult(&other.fp32_magnitude());
+        let (a, b) = (self.fp32_unpack(), other.fp32_unpack());
+        let x = Unpacked::ite(&swap, &b, &a);
+        let y = Unpacked::ite(&swap, &a, &b);
+
+        // Six extra bits below the significands are used for rounding.
+        let guard_bits = ctx.from_u64(6, 32);
+        let x_significand = x.significand.zero_ext(32).sll(&guard_bits);
+        let y_significand = y.significand.zero_ext(32).sll(&guard_bits);
+        let difference = x.exponent.sub(&y.exponent);
+        let y_significand = shift_right_sticky(&ctx, &y_significand, &difference);
+
+        let same_sign = x.sign._eq(&y.sign);
+        let sum = same_sign.ite(
+            &x_significand.add(&y_significand),
+            &x_significand.sub(&y_significand),
+        );
+        // An exact zero is positive when rounding to nearest.
+        let sign = sum._eq(&ctx.zero(32)).ite(&ctx.zero(1), &x.sign);
+        let exponent = x.exponent.add(&ctx.from_u64(2, EXPONENT_WIDTH));
+        let result = round_pack(&ctx, &sign, &exponent, &sum);
+
+        let sign_differs = self.slice(31, 31)._ne(&other.slice(31, 31));
+        let both_infinite = self.fp32_is_infinite().and(&other.fp32_is_infinite());
+        let both_zero = self.fp32_is_zero().and(&other.fp32_is_zero());
+        let is_nan = self.fp32_is_nan().or(&other.fp32_is_nan());
+
+        is_nan.ite(
+            &self.fp32_propagate_nan(other),
+            &both_infinite.and(&sign_differs).ite(
+                &ctx.from_u64(DEFAULT_NAN, 32),
+                &self.fp32_is_infinite().ite(
+                    self,
+                    &other.fp32_is_infinite().ite(
+                        other,
+                        // -0 + -0 is the only sum of zeroes that is negative.
+                        &both_zero.ite(&self.and(other), &result),
+                    ),
+                ),
+            ),
+        )
+    }
+
+    /// Single precision subtraction.
+    pub fn fp32_sub(&self, other: &Self) -> Self {
+        let ctx = self.get_ctx();
+        let negated = other.xor(&ctx.from_u64(SIGN_BIT, 32));
+        let is_nan = self.fp32_is_nan().or(&other.fp32_is_nan());
+        is_nan.ite(&self.fp32_propagate_nan(other), &self.fp32_add(&negated))
+    }
+
+    /// Single precision multiplication.
+    pub fn fp32_mul(&self, other: &Self) -> Self {
+        assert_eq!(self.len(), 32);
+        assert_eq!(other.len(), 32);
+        let ctx = self.get_ctx();
+        let (a, b) = (self.fp32_unpack(), other.fp32_unpack());
+
+        let sign = a.sign.xor(&b.sign);
+        let product = a.significand.zero_ext(48).mul(&b.significand.zero_ext(48));
+        let exponent = a
+            .exponent
+            .add(&b.exponent)
+            .sub(&ctx.from_u64(126, EXPONENT_WIDTH));
+        let result = round_pack(&ctx, &sign, &exponent, &product);
+
+        let signed_zero = sign.concat(&ctx.zero(31));
+        let signed_infinity = signed_zero.or(&ctx.from_u64(INFINITY, 32));
+        let any_infinite = self.fp32_is_infinite().or(&other.fp32_is_infinite());
+        let any_zero = self.fp32_is_zero().or(&other.fp32_is_zero());
+        let is_nan = self.fp32_is_nan().or(&other.fp32_is_nan());
+
+        is_nan.ite(
+            &self.fp32_propagate_nan(other),
+            &any_infinite.and(&any_zero).ite(
+                &ctx.from_u64(DEFAULT_NAN, 32),
+                &any_infinite.ite(&signed_infinity, &any_zero.ite(&signed_zero, &result)),
+            ),
+        )
+    }
+
+    /// Single precision division.
+    pub fn fp32_div(&self, other: &Self) -> Self {
+        assert_eq!(self.len(), 32);
+        assert_eq!(other.len(), 32);
+        let ctx = self.get_ctx();
+        let (a, b) = (self.fp32_unpack(), other.fp32_unpack());
+
+        let sign = a.sign.xor(&b.sign);
+        let (a_exponent, a_significand) = normalize(&ctx, &a.exponent, &a.significand);
+        let (b_exponent, b_significand) = normalize(&ctx, &b.exponent, &b.significand);
+
+        // The quotient has at least 26 significant bits, a non zero remainder is kept as a
+        // sticky bit.
+        let dividend = a_significand.zero_ext(50).sll(&ctx.from_u64(26, 50));
+        let divisor = b_significand.zero_ext(50);
+        let remainder = dividend.urem(&divisor);
+        let sticky = remainder._ne(&ctx.zero(50)).zero_ext(50);
+        let quotient = dividend.udiv(&divisor).or(&sticky);
+        let exponent = a_exponent
+            .sub(&b_exponent)
+            .add(&ctx.from_u64(150, EXPONENT_WIDTH));
+        let result = round_pack(&ctx, &sign, &exponent, &quotient);
+
+        let signed_zero = sign.concat(&ctx.zero(31));
+        let signed_infinity = signed_zero.or(&ctx.from_u64(INFINITY, 32));
+        let both_infinite = self.fp32_is_infinite().and(&other.fp32_is_infinite());
+        let both_zero = self.fp32_is_zero().and(&other.fp32_is_zero());
+        let is_nan = self.fp32_is_nan().or(&other.fp32_is_nan());
+
+        is_nan.ite(
+            &self.fp32_propagate_nan(other),
+            &both_infinite.or(&both_zero).ite(
+                &ctx.from_u64(DEFAULT_NAN, 32),
+                &self.fp32_is_infinite().or(&other.fp32_is_zero()).ite(
+                    &signed_infinity,
+                    &self
+                        .fp32_is_zero()
+                        .or(&other.fp32_is_infinite())
+                        .ite(&signed_zero, &result),
+                ),
+            ),
+        )
+    }
+
+    /// Single precision equality, false if any of the operands is NaN. The result has width `1`.
+    pub fn fp32_eq(&self, other: &Self) -> Self {
+        let ordered = self.fp32_is_nan().or(&other.fp32_is_nan()).not();
+        ordered.and(&self.fp32_order_key()._eq(&other.fp32_order_key()))
+    }
+
+    /// Single precision less than, false if any of the operands is NaN. The result has width `1`.
+    pub fn fp32_lt(&self, other: &Self) -> Self {
+        let ordered = self.fp32_is_nan().or(&other.fp32_is_nan()).not();
+        ordered.and(&self.fp32_order_key().slt(&other.fp32_order_key()))
+    }
+
+    /// Converts a single precision value to a 32 bit integer.
+    ///
+    /// Values outside of the range of the integer saturate and NaN is converted to zero. If
+    /// `round_to_zero` is not set the value is rounded to nearest.
+    pub fn fp32_to_int(&self, signed: bool, round_to_zero: bool) -> Self {
+        assert_eq!(self.len(), 32);
+        let ctx = self.get_ctx();
+        let unpacked = self.fp32_unpack();
+        let significand = unpacked.significand.zero_ext(64);
+
+        // The value is significand * 2^(exponent - 150).
+        let bias = ctx.from_u64(150, EXPONENT_WIDTH);
+        let is_integer = unpacked.exponent.sgte(&bias);
+        let left_shift = unpacked.exponent.sub(&bias);
+        let right_shift = bias.sub(&unpacked.exponent);
+        let max_left_shift = ctx.from_u64(40, EXPONENT_WIDTH);
+        let too_large = is_integer.and(&left_shift.ugt(&max_left_shift));
+        let left_shift = too_large.ite(&max_left_shift, &left_shift).zero_ext(64);
+        let max_right_shift = ctx.from_u64(63, EXPONENT_WIDTH);
+        let right_shift = is_integer
+            .ite(&ctx.zero(EXPONENT_WIDTH), &right_shift)
+            .zero_ext(64);
+        let right_shift = right_shift
+            .ugt(&max_right_shift.zero_ext(64))
+            .ite(&max_right_shift.zero_ext(64), &right_shift);
+
+        let truncated = significand.srl(&right_shift);
+        let truncated = if round_to_zero {
+            truncated
+        } else {
+            let fraction = significand.and(&ctx.unsigned_max(64).sll(&right_shift).not());
+            let half = ctx.one(64).sll(&right_shift).srl(&ctx.one(64));
+            let odd = truncated.slice(0, 0)._eq(&ctx.one(1));
+            let round_up = half
+                ._ne(&ctx.zero(64))
+                .and(&fraction.ugt(&half).or(&fraction._eq(&half).and(&odd)));
+            truncated.add(&round_up.zero_ext(64))
+        };
+        let magnitude = is_integer.ite(&significand.sll(&left_shift), &truncated);
+
+        let negative = unpacked.sign._eq(&ctx.one(1));
+        let result = if signed {
+            let overflow = too_large.or(&negative.ite(
+                &magnitude.ugt(&ctx.from_u64(0x8000_0000, 64)),
+                &magnitude.ugt(&ctx.from_u64(0x7fff_ffff, 64)),
+            ));
+            let saturated = negative.ite(
+                &ctx.from_u64(0x8000_0000, 32),
+                &ctx.from_u64(0x7fff_ffff, 32),
+            );
+            let value = negative
+                .ite(&ctx.zero(64).sub(&magnitude), &magnitude)
+                .slice(0, 31);
+            overflow.ite(&saturated, &value)
+        } else {
+            let overflow = too_large.or(&magnitude.ugt(&ctx.from_u64(0xffff_ffff, 64)));
+            let saturated = negative.ite(&ctx.zero(32), &ctx.unsigned_max(32));
+            let value = negative.ite(&ctx.zero(32), &magnitude.slice(0, 31));
+            overflow.ite(&saturated, &value)
+        };
+
+        self.fp32_is_nan().ite(&ctx.zero(32), &result)
+    }
+
+    /// Converts a 32 bit integer to single precision, rounding to nearest.
+    pub fn fp32_from_int(&self, signed: bool) -> Self {
+        assert_eq!(self.len(), 32);
+        let ctx = self.get_ctx();
+        let sign = if signed {
+            self.slice(31, 31)
+        } else {
+            ctx.zero(1)
+        };
+        let magnitude = sign._eq(&ctx.one(1)).ite(&ctx.zero(32).sub(self), self);
+        round_pack(&ctx, &sign, &ctx.from_u64(158, EXPONENT_WIDTH), &magnitude)
+    }
+}
+
+/// Shifts `value` right by `shift` bits, if any of the shifted out bits are set the least
+/// significant bit of the result is set.
+fn shift_right_sticky(
+    ctx: &BoolectorSolverContext,
+    value: &BoolectorExpr,
+    shift: &BoolectorExpr,
+) -> BoolectorExpr {
+    let width = value.len();
+    let max_shift = ctx.from_u64(width as u64, shift.len());
+    let shift = shift
+        .ugt(&max_shift)
+        .ite(&max_shift, shift)
+        .resize_unsigned(width);
+    let lost_mask = ctx.unsigned_max(width).sll(&shift).not();
+    let lost = value.and(&lost_mask)._ne(&ctx.zero(width));
+    value.srl(&shift).or(&lost.zero_ext(width))
+}
+
+/// Shifts `significand` left until the most significant bit is set and adjusts the exponent.
+/// The result is undefined if `significand` is zero.
+fn normalize(
+    ctx: &BoolectorSolverContext,
+    exponent: &BoolectorExpr,
+    significand: &BoolectorExpr,
+) -> (BoolectorExpr, BoolectorExpr) {
+    let width = significand.len();
+    let mut exponent = exponent.clone();
+    let mut significand = significand.clone();
+    let mut step = (width - 1).next_power_of_two() / 2;
+    while step > 0 {
+        let top_is_zero = significand
+            .slice(width - step, width - 1)
+            ._eq(&ctx.zero(step));
+        significand = top_is_zero.ite(
+            &significand.sll(&ctx.from_u64(step as u64, width)),
+            &significand,
+        );
+        exponent = top_is_zero.ite(
+            &exponent.sub(&ctx.from_u64(step as u64, EXPONENT_WIDTH)),
+            &exponent,
+        );
+        step /= 2;
+    }
+    (exponent, significand)
+}
+
+/// Rounds and packs a value into single precision.
+///
+/// The value is `significand / 2^(width - 1) * 2^(exponent - 127)` where `exponent` is a signed
+/// value. The significand must be at least 26 bits wide.
+fn round_pack(
+    ctx: &BoolectorSolverContext,
+    sign: &BoolectorExpr,
+    exponent: &BoolectorExpr,
+    significand: &BoolectorExpr,
+) -> BoolectorExpr {
+    let width = significand.len();
+    assert!(width >= 26);
+    let is_zero = significand._eq(&ctx.zero(width));
+    let (exponent, significand) = normalize(ctx, exponent, significand);
+
+    // Values below the normal range are shifted right to the subnormal exponent.
+    let one = ctx.one(EXPONENT_WIDTH);
+    let is_tiny = exponent.slt(&one);
+    let shift = is_tiny.ite(&one.sub(&exponent), &ctx.zero(EXPONENT_WIDTH));
+    let shifted = shift_right_sticky(ctx, &significand, &shift);
+    // The exponent field minus the implicit bit, the implicit bit is added with the mantissa.
+    let biased_exponent = is_tiny.ite(&ctx.zero(EXPONENT_WIDTH), &exponent.sub(&one));
+
+    let mantissa = shifted.slice(width - 24, width - 1);
+    let guard = shifted.slice(width - 25, width - 25);
+    let sticky = shifted.slice(0, width - 26)._ne(&ctx.zero(width - 25));
+    let odd = mantissa.slice(0, 0);
+    let round_up = guard.and(&sticky.or(&odd));
+
+    // A carry from the rounding propagates into the exponent.
+    let magnitude = biased_exponent
+        .zero_ext(64)
+        .sll(&ctx.from_u64(23, 64))
+        .add(&mantissa.zero_ext(64))
+        .add(&round_up.zero_ext(64));
+    let infinity = ctx.from_u64(INFINITY, 64);
+    let magnitude = magnitude.ugte(&infinity).ite(&infinity, &magnitude);
+
+    let result = sign.concat(&magnitude.slice(0, 30));
+    is_zero.ite(&sign.concat(&ctx.zero(31)), &result)
+}
@@ -5,6 +5,7 @@ use boolector::{
 use std::rc::Rc;
 
 mod expr;
+mod fp;
 mod solver;
 
 // Re-exports.
@@ -1,4 +1,7 @@
-use symex::{general_assembly::RunConfig, run_elf::run_elf};
+use symex::{
+    general_assembly::{memory_map::MemoryMap, Config, RunConfig, Statics},
+    run_elf::run_elf,
+};
 
 fn main() {
     println!("Simple WCET analasis");
@@ -12,6 +15,15 @@ fn main() {
         register_write_hooks: vec![],
         memory_write_hooks: vec![],
         memory_read_hooks: vec![],
+        exception_handler: None,
+        interrupts: vec![],
+        special_instructions: vec![],
+        clock_frequency: None,
+        config: Config::default(),
+        statics: Statics::default(),
+        memory_map: MemoryMap::rp2040(),
+        peripherals: vec![],
+        observers: vec![],
         show_path_results: false,
     };
 
@@ -1,5 +1,8 @@
 use symex::{
-    general_assembly::{project::MemoryHookAddress, state::GAState, Result, RunConfig},
+    general_assembly::{
+        memory_map::MemoryMap, project::MemoryHookAddress, state::GAState, timing::Span, Config,
+        Result, RunConfig, Statics,
+    },
     run_elf::run_elf,
     smt::DExpr,
 };
@@ -7,9 +10,8 @@ use symex::{
 use srp::common::Trace;
 
 // This example show how hooks can be used to get at which cycle a resource is locked and unlocked in a simple
-// RTIC application. To keep in mind is that cycles are added after the instruction is executed and the hook
-// is run during instruction execution. Therefore care needs to be taken to measure the critical section
-// correctly.
+// RTIC application. Each critical section is recorded as a span, the span includes both the instruction
+// locking and the instruction unlocking the resource.
 
 // To run the example first build the "rtic_simple_resourse" in armv6-m-examples by doing:
 // cd armv6-m-examples
@@ -18,37 +20,12 @@ use srp::common::Trace;
 //
 // Then run the analysis by: cargo run -p wcet-analasis-examples --release --example wcet_resource_times
 
-fn make_trace(start: usize, end: usize, laps: &[(usize, String)], id: String) -> Trace {
-    let mut inner = vec![];
-
-    let mut current = "";
-    let mut inner_start = 0;
-    let mut in_inner = false;
-    let mut start_i = 0;
-    for i in 0..laps.len() {
-        if !in_inner {
-            current = &laps[i].1;
-            inner_start = laps[i].0;
-            start_i = i;
-            in_inner = true
-        } else {
-            if current == &laps[i].1 {
-                inner.push(make_trace(
-                    inner_start,
-                    laps[i].0,
-                    &laps[(start_i + 1)..i],
-                    laps[i].1.to_owned(),
-                ));
-                in_inner = false;
-            }
-        }
-    }
-
+fn make_trace(span: &Span) -> Trace {
     Trace {
-        id,
-        start: start as u32,
-        end: end as u32,
-        inner,
+        id: span.name.to_owned(),
+        start: span.start as u32,
+        end: span.end as u32,
+        inner: span.children.iter().map(make_trace).collect(),
     }
 }
 
@@ -63,26 +40,18 @@ fn main() {
     // Hook to run when the interrupt mask is reset (looked).
     let lock_hook: fn(state: &mut GAState, addr: u64, value: DExpr, bits: u32) -> Result<()> =
         |state, _addr, value, _bits| {
-            // save the current cycle count to the laps vector.
+            // begin a span named after the interrupt mask.
             let val = value.get_constant().unwrap().to_string();
-            state.cycle_laps.push((state.cycle_count, val));
+            state.span_begin(val);
             Ok(())
         };
 
     // Hook to run when the interrupt mask is set (unlocked).
     let unlock_hook: fn(state: &mut GAState, addr: u64, value: DExpr, bits: u32) -> Result<()> =
         |state, _addr, value, _bits| {
-            // save the current cycle count to the laps vector.
+            // end the span of the interrupt mask.
             let val = value.get_constant().unwrap().to_string();
-            let current_instruction_cycle_count =
-                match state.current_instruction.as_ref().unwrap().max_cycle {
-                    symex::general_assembly::instruction::CycleCount::Value(v) => v,
-                    symex::general_assembly::instruction::CycleCount::Function(f) => f(state),
-                };
-
-            // add the current instruction to the cycle count to compensate for cycles added after instruction completed
-            let cycle_count = state.cycle_count + current_instruction_cycle_count;
-            state.cycle_laps.push((cycle_count, val));
+            state.span_end(val);
             Ok(())
         };
 
@@ -92,10 +61,19 @@ fn main() {
         register_read_hooks: vec![],
         register_write_hooks: vec![],
         memory_write_hooks: vec![
-            (MemoryHookAddress::Single(0xe000e100), unlock_hook),
-            (MemoryHookAddress::Single(0xe000e180), lock_hook),
+            (MemoryHookAddress::Single(0xe000e100), Box::new(unlock_hook)),
+            (MemoryHookAddress::Single(0xe000e180), Box::new(lock_hook)),
         ],
         memory_read_hooks: vec![],
+        exception_handler: None,
+        interrupts: vec![],
+        special_instructions: vec![],
+        clock_frequency: None,
+        config: Config::default(),
+        statics: Statics::default(),
+        memory_map: MemoryMap::rp2040(),
+        peripherals: vec![],
+        observers: vec![],
         show_path_results: false,
     };
 
@@ -106,14 +84,13 @@ fn main() {
     let mut max = 0;
     let paths = results.len();
     for result in results {
-        //println!("cycle laps: {:?}", result.cycle_laps);
         max = max.max(result.max_cycles);
-        let trace = make_trace(
-            0,
-            result.max_cycles,
-            &result.cycle_laps,
-            function_name.to_owned(),
-        );
+        let trace = Trace {
+            id: function_name.to_owned(),
+            start: 0,
+            end: result.max_cycles as u32,
+            inner: result.spans.iter().map(make_trace).collect(),
+        };
         println!("trace: {:#?}", trace);
     }
 